@@ -0,0 +1,301 @@
+use std::collections::HashMap;
+use std::error::Error;
+
+use crate::datasets_config::datasets_config::OutputFormat;
+use crate::loader::loader_manager::{granger_dataframe, regression_dataframe};
+use crate::loader::parquet::{self, RESULT_PATH};
+use crate::loader::partitioned::is_valid_scenario;
+use crate::loader::revisions::{diff_results, diff_summary, revision_dataframe};
+use crate::loader::store::{list_datasets, list_runs, read_dataset, StoreLayer, STORE_DIRECTORY};
+use crate::transformer::regression::{run_granger_test, run_regression};
+
+pub const USAGE: &str = "usage:
+  financial_engine [--formats <formats>] [--scenario <name>]          run the full pipeline
+  financial_engine update [--formats <formats>] [--scenario <name>]   run the pipeline, refetching FRED and ECB data from
+                                                                      the last cached period minus the revision window only
+  financial_engine regress <dependent> <regressor>... [--newey-west <lags>]
+  financial_engine granger <cause> <effect> [--lags <lags>[,<lags>...]]
+  financial_engine diff <snapshot.parquet>
+  financial_engine query <raw|processed|result> [<dataset>] [--run <run_id>]
+
+--formats replaces the configured extra outputs of the result with a comma-separated list
+of json, json-columnar, ndjson and ipc, and --scenario the scenario the result is appended
+under in outputs/dataset.
+regress and granger work on the columns of the last outputs/parquet/result.parquet,
+diff lists what changed in it since an earlier result, query prints a dataset of the
+latest or the given run from outputs/store, or lists the stored datasets and runs";
+
+const DEFAULT_GRANGER_LAG_ORDERS: [usize; 3] = [1, 2, 4];
+
+#[derive(Debug, PartialEq)]
+pub enum Command {
+    Run { output_formats: Option<Vec<OutputFormat>>, scenario: Option<String> },
+    Update { output_formats: Option<Vec<OutputFormat>>, scenario: Option<String> },
+    Regress { dependent: String, regressors: Vec<String>, newey_west_lags: Option<usize> },
+    Granger { cause: String, effect: String, lag_orders: Vec<usize> },
+    Diff { snapshot: String },
+    Query { layer: StoreLayer, dataset: Option<String>, run_id: Option<String> },
+}
+
+/// Parses the arguments after the program name.
+pub fn parse_command(args: &[String]) -> Result<Command, String> {
+    let Some((command, rest)) = args.split_first() else {
+        return Ok(Command::Run { output_formats: None, scenario: None });
+    };
+
+    match command.as_str() {
+        option if option.starts_with("--") => {
+            let (output_formats, scenario) = parse_run_options(args)?;
+            Ok(Command::Run { output_formats, scenario })
+        },
+        "update" => {
+            let (output_formats, scenario) = parse_run_options(rest)?;
+            Ok(Command::Update { output_formats, scenario })
+        },
+        "regress" => {
+            let (columns, newey_west_lags) = split_option(rest, "--newey-west")?;
+            let newey_west_lags = newey_west_lags.map(|lags| parse_lags(&lags)).transpose()?;
+
+            match columns.split_first() {
+                Some((dependent, regressors)) if !regressors.is_empty() => Ok(Command::Regress {
+                    dependent: dependent.clone(),
+                    regressors: regressors.to_vec(),
+                    newey_west_lags,
+                }),
+                _ => Err("regress needs a dependent column and at least one regressor".to_string()),
+            }
+        },
+        "granger" => {
+            let (columns, lag_orders) = split_option(rest, "--lags")?;
+            let lag_orders = match lag_orders {
+                Some(lag_orders) => lag_orders.split(',').map(parse_lags).collect::<Result<Vec<usize>, String>>()?,
+                None => DEFAULT_GRANGER_LAG_ORDERS.to_vec(),
+            };
+
+            match columns.as_slice() {
+                [cause, effect] => Ok(Command::Granger { cause: cause.clone(), effect: effect.clone(), lag_orders }),
+                _ => Err("granger needs exactly a cause and an effect column".to_string()),
+            }
+        },
+        "diff" => match rest {
+            [snapshot] if !snapshot.starts_with("--") => Ok(Command::Diff { snapshot: snapshot.clone() }),
+            _ => Err("diff needs exactly the path of an earlier result.parquet".to_string()),
+        },
+        "query" => {
+            let (positional, run_id) = split_option(rest, "--run")?;
+
+            match positional.as_slice() {
+                [layer, dataset @ ..] if dataset.len() <= 1 => Ok(Command::Query {
+                    layer: StoreLayer::from_name(layer).ok_or(format!("unknown store layer '{layer}'"))?,
+                    dataset: dataset.first().cloned(),
+                    run_id,
+                }),
+                _ => Err("query needs a store layer and at most one dataset".to_string()),
+            }
+        },
+        other => Err(format!("unknown command '{other}'")),
+    }
+}
+
+/// Separates `--option <value>` from the positional arguments.
+fn split_option(args: &[String], option: &str) -> Result<(Vec<String>, Option<String>), String> {
+    let (positional, [value]) = split_options(args, [option])?;
+
+    Ok((positional, value))
+}
+
+/// Separates `--option <value>` pairs of any of `options` from the positional arguments.
+fn split_options<const N: usize>(args: &[String], options: [&str; N]) -> Result<(Vec<String>, [Option<String>; N]), String> {
+    let mut positional: Vec<String> = vec![];
+    let mut values: [Option<String>; N] = std::array::from_fn(|_| None);
+    let mut args = args.iter();
+
+    while let Some(arg) = args.next() {
+        if let Some(index) = options.iter().position(|option| arg == option) {
+            values[index] = Some(args.next().ok_or(format!("{arg} needs a value"))?.clone());
+        } else if arg.starts_with("--") {
+            return Err(format!("unknown option '{arg}'"));
+        } else {
+            positional.push(arg.clone());
+        }
+    }
+
+    Ok((positional, values))
+}
+
+/// Parses `--formats json,ipc` and `--scenario ppp_only`, the options of run and update.
+fn parse_run_options(args: &[String]) -> Result<(Option<Vec<OutputFormat>>, Option<String>), String> {
+    let (positional, [output_formats, scenario]) = split_options(args, ["--formats", "--scenario"])?;
+    if let Some(arg) = positional.first() {
+        return Err(format!("unexpected argument '{arg}'"));
+    }
+
+    let output_formats = output_formats
+        .map(|output_formats| output_formats.split(',')
+            .map(|name| OutputFormat::from_name(name.trim()).ok_or(format!("unknown output format '{name}'")))
+            .collect())
+        .transpose()?;
+
+    if let Some(scenario) = scenario.as_deref().filter(|scenario| !is_valid_scenario(scenario)) {
+        return Err(format!("invalid scenario name '{scenario}'"));
+    }
+
+    Ok((output_formats, scenario))
+}
+
+fn parse_lags(value: &str) -> Result<usize, String> {
+    value.trim().parse::<usize>().map_err(|_| format!("invalid lag order '{value}'"))
+}
+
+/// Runs a regression, Granger causality or diff command on the last result, or a query on the
+/// store, and prints the table.
+pub fn run_analysis_command(command: &Command) -> Result<(), Box<dyn Error>> {
+    let read_result = || parquet::read(RESULT_PATH);
+
+    match command {
+        Command::Run { .. } | Command::Update { .. } => Ok(()),
+        Command::Regress { dependent, regressors, newey_west_lags } => {
+            let regressors: Vec<&str> = regressors.iter().map(|regressor| regressor.as_str()).collect();
+            let regression = run_regression(&read_result()?, dependent, &regressors, *newey_west_lags)?;
+
+            println!("{dependent} ~ {}: R² {:.4}, adjusted R² {:.4}, {} observations, {} standard errors",
+                     regressors.join(" + "),
+                     regression.r_squared,
+                     regression.adjusted_r_squared,
+                     regression.observations,
+                     regression.covariance);
+            println!("{}", regression_dataframe(&[regression], &HashMap::new())
+                .select(["term", "estimate", "standard_error", "t_statistic", "p_value"])?);
+            Ok(())
+        },
+        Command::Granger { cause, effect, lag_orders } => {
+            let result = read_result()?;
+            let granger_tests = lag_orders.iter()
+                .map(|lags| run_granger_test(&result, cause, effect, *lags))
+                .collect::<Result<Vec<_>, _>>()?;
+
+            println!("{}", granger_dataframe(&granger_tests, &HashMap::new()));
+            Ok(())
+        },
+        Command::Diff { snapshot } => {
+            let result_diff = diff_results(&parquet::read(snapshot)?, &read_result()?);
+
+            println!("changes since {snapshot}: {}", diff_summary(&result_diff));
+            println!("{}", revision_dataframe(&result_diff.revisions));
+            Ok(())
+        },
+        Command::Query { layer, dataset: Some(dataset), run_id } => {
+            println!("{}", read_dataset(STORE_DIRECTORY, *layer, dataset, run_id.as_deref())?);
+            Ok(())
+        },
+        Command::Query { layer, dataset: None, .. } => {
+            for dataset in list_datasets(STORE_DIRECTORY, *layer) {
+                println!("{dataset}: {}", list_runs(STORE_DIRECTORY, *layer, &dataset).join(", "));
+            }
+            Ok(())
+        },
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|arg| arg.to_string()).collect()
+    }
+
+    #[test]
+    fn test_parse_command_without_arguments_runs_pipeline() {
+        assert_eq!(parse_command(&[]), Ok(Command::Run { output_formats: None, scenario: None }));
+    }
+
+    #[test]
+    fn test_parse_update_command() {
+        assert_eq!(parse_command(&args(&["update"])), Ok(Command::Update { output_formats: None, scenario: None }));
+        assert!(parse_command(&args(&["update", "--full"])).is_err());
+        assert!(parse_command(&args(&["update", "now"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_output_formats() {
+        assert_eq!(parse_command(&args(&["--formats", "json, ipc"])),
+                   Ok(Command::Run { output_formats: Some(vec![OutputFormat::Json, OutputFormat::ArrowIpc]), scenario: None }));
+        assert_eq!(parse_command(&args(&["update", "--formats", "ndjson"])),
+                   Ok(Command::Update { output_formats: Some(vec![OutputFormat::Ndjson]), scenario: None }));
+
+        assert_eq!(parse_command(&args(&["--formats", "json,xml"])), Err("unknown output format 'xml'".to_string()));
+        assert!(parse_command(&args(&["--formats"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_scenario() {
+        assert_eq!(parse_command(&args(&["--scenario", "ppp_only", "--formats", "json"])),
+                   Ok(Command::Run { output_formats: Some(vec![OutputFormat::Json]), scenario: Some("ppp_only".to_string()) }));
+        assert_eq!(parse_command(&args(&["update", "--scenario", "ppp_only"])),
+                   Ok(Command::Update { output_formats: None, scenario: Some("ppp_only".to_string()) }));
+
+        assert_eq!(parse_command(&args(&["--scenario", "run_date=today"])), Err("invalid scenario name 'run_date=today'".to_string()));
+        assert!(parse_command(&args(&["--scenario"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_regress_command() {
+        assert_eq!(
+            parse_command(&args(&["regress", "sp500_yoy_perc", "us_gdp_real_yoy_perc", "--newey-west", "4", "us_inflation_perc"])),
+            Ok(Command::Regress {
+                dependent: "sp500_yoy_perc".to_string(),
+                regressors: args(&["us_gdp_real_yoy_perc", "us_inflation_perc"]),
+                newey_west_lags: Some(4),
+            }));
+
+        assert!(parse_command(&args(&["regress", "sp500_yoy_perc"])).is_err());
+        assert!(parse_command(&args(&["regress", "a", "b", "--newey-west"])).is_err());
+        assert!(parse_command(&args(&["regress", "a", "b", "--robust"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_granger_command() {
+        assert_eq!(
+            parse_command(&args(&["granger", "eu_gdp_qoq_perc", "sp500_log_return_perc", "--lags", "1,3"])),
+            Ok(Command::Granger {
+                cause: "eu_gdp_qoq_perc".to_string(),
+                effect: "sp500_log_return_perc".to_string(),
+                lag_orders: vec![1, 3],
+            }));
+
+        assert_eq!(
+            parse_command(&args(&["granger", "a", "b"])),
+            Ok(Command::Granger { cause: "a".to_string(), effect: "b".to_string(), lag_orders: vec![1, 2, 4] }));
+
+        assert_eq!(parse_command(&args(&["granger", "a", "b", "--lags", "x"])), Err("invalid lag order 'x'".to_string()));
+        assert!(parse_command(&args(&["granger", "a"])).is_err());
+        assert!(parse_command(&args(&["forecast"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_diff_command() {
+        assert_eq!(parse_command(&args(&["diff", "archive/2025-06-30.parquet"])),
+                   Ok(Command::Diff { snapshot: "archive/2025-06-30.parquet".to_string() }));
+
+        assert!(parse_command(&args(&["diff"])).is_err());
+        assert!(parse_command(&args(&["diff", "a.parquet", "b.parquet"])).is_err());
+    }
+
+    #[test]
+    fn test_parse_query_command() {
+        assert_eq!(parse_command(&args(&["query", "raw", "us_gdp", "--run", "20250630T141502"])),
+                   Ok(Command::Query {
+                       layer: StoreLayer::Raw,
+                       dataset: Some("us_gdp".to_string()),
+                       run_id: Some("20250630T141502".to_string()),
+                   }));
+        assert_eq!(parse_command(&args(&["query", "result"])),
+                   Ok(Command::Query { layer: StoreLayer::Result, dataset: None, run_id: None }));
+
+        assert_eq!(parse_command(&args(&["query", "joined", "result"])), Err("unknown store layer 'joined'".to_string()));
+        assert!(parse_command(&args(&["query"])).is_err());
+        assert!(parse_command(&args(&["query", "raw", "us_gdp", "eu_gdp"])).is_err());
+    }
+}
@@ -1,4 +1,6 @@
-#[derive(Clone, Copy)]
+use serde::Serialize;
+
+#[derive(Clone, Copy, Debug)]
 pub struct QuarterlyAverageConfig {
     pub date_column_name: &'static str,
     pub target_column_name: &'static str,
@@ -21,13 +23,94 @@ impl QuarterlyAverageConfig {
     }
 }
 
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Scale {
+    Units,
+    Thousands,
+    Millions,
+    Billions,
+}
+
+impl Scale {
+    pub fn multiplier(&self) -> f64 {
+        match self {
+            Scale::Units => 1.0,
+            Scale::Thousands => 1_000.0,
+            Scale::Millions => 1_000_000.0,
+            Scale::Billions => 1_000_000_000.0,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Scale::Units => "units",
+            Scale::Thousands => "thousands",
+            Scale::Millions => "millions",
+            Scale::Billions => "billions",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct UnitConfig {
+    pub unit: &'static str,
+    pub scale: Scale,
+    pub monetary: bool,
+}
+
+impl UnitConfig {
+    pub fn new(unit: &'static str,
+               scale: Scale,
+               monetary: bool
+    ) -> Self {
+        UnitConfig {
+            unit,
+            scale,
+            monetary
+        }
+    }
+}
+
+/// Additive decomposition suits series that can be negative or whose seasonal swings do not
+/// grow with the level, such as rates. Multiplicative decomposition suits positive levels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SeasonalAdjustmentMode {
+    Additive,
+    Multiplicative,
+}
+
+impl SeasonalAdjustmentMode {
+    pub fn name(&self) -> &'static str {
+        match self {
+            SeasonalAdjustmentMode::Additive => "additive",
+            SeasonalAdjustmentMode::Multiplicative => "multiplicative",
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SeasonalAdjustmentConfig {
+    pub mode: SeasonalAdjustmentMode,
+}
+
+impl SeasonalAdjustmentConfig {
+    pub fn new(mode: SeasonalAdjustmentMode) -> Self {
+        SeasonalAdjustmentConfig {
+            mode
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
 pub struct DatasetConfig {
     pub name: &'static str,
     pub source: &'static str,
     pub identifier: &'static str,
     pub quarterly_avg_required: bool,
     pub quarterly_average_config: Option<QuarterlyAverageConfig>,
+    pub unit_config: Option<UnitConfig>,
+    pub seasonal_adjustment_config: Option<SeasonalAdjustmentConfig>,
 }
 
 impl DatasetConfig {
@@ -35,13 +118,17 @@ impl DatasetConfig {
                source: &'static str,
                identifier: &'static str,
                quarterly_avg_required: bool,
-               quarterly_average_config: Option<QuarterlyAverageConfig>) -> Self {
+               quarterly_average_config: Option<QuarterlyAverageConfig>,
+               unit_config: Option<UnitConfig>,
+               seasonal_adjustment_config: Option<SeasonalAdjustmentConfig>) -> Self {
         DatasetConfig {
             name,
             source,
             identifier,
             quarterly_avg_required,
-            quarterly_average_config
+            quarterly_average_config,
+            unit_config,
+            seasonal_adjustment_config
         }
     }
 }
@@ -56,7 +143,9 @@ pub fn get_all_datasets_configs() -> Vec<DatasetConfig> {
                                "observation_date",
                                "DEXUSEU",
                                "avg_fx_rate",
-                               "%Y-%m-%d"))),
+                               "%Y-%m-%d")),
+                           Some(UnitConfig::new("USD per EUR", Scale::Units, false)),
+                           None),
         DatasetConfig::new("sp500",
                            "fred",
                            "SP500",
@@ -64,7 +153,9 @@ pub fn get_all_datasets_configs() -> Vec<DatasetConfig> {
                            Some(QuarterlyAverageConfig::new("date",
                                                             "value",
                                                             "sp500_usd",
-                                                            "%Y-%m-%d"))),
+                                                            "%Y-%m-%d")),
+                           Some(UnitConfig::new("index points", Scale::Units, false)),
+                           None),
         DatasetConfig::new("us_gdp",
                            "fred",
                            "GDP",
@@ -72,7 +163,9 @@ pub fn get_all_datasets_configs() -> Vec<DatasetConfig> {
                            Some(QuarterlyAverageConfig::new("date",
                                                             "value",
                                                             "us_gdp_usd",
-                                                            "%Y-%m-%d"))),
+                                                            "%Y-%m-%d")),
+                           Some(UnitConfig::new("USD", Scale::Billions, true)),
+                           None),
         DatasetConfig::new("us_total_public_debt",
                            "fred",
                            "GFDEBTN",
@@ -80,7 +173,9 @@ pub fn get_all_datasets_configs() -> Vec<DatasetConfig> {
                            Some(QuarterlyAverageConfig::new("date",
                                                             "value",
                                                             "us_total_debt_usd",
-                                                            "%Y-%m-%d"))),
+                                                            "%Y-%m-%d")),
+                           Some(UnitConfig::new("USD", Scale::Millions, true)),
+                           None),
         DatasetConfig::new("us_inflation",
                            "fred",
                            "CORESTICKM159SFRBATL",
@@ -88,16 +183,22 @@ pub fn get_all_datasets_configs() -> Vec<DatasetConfig> {
                            Some(QuarterlyAverageConfig::new("date",
                                                             "value",
                                                             "us_inflation_usd",
-                                                            "%Y-%m-%d"))),
+                                                            "%Y-%m-%d")),
+                           Some(UnitConfig::new("percent", Scale::Units, false)),
+                           None),
         DatasetConfig::new("eu_government_debt",
                            "ecb",
                            "GFS/Q.N.I9.W0.S13.S1.C.L.LE.GD.T._Z.XDC._T.F.V.N._T",
                            false,
-                           None),
+                           None,
+                           Some(UnitConfig::new("EUR", Scale::Millions, true)),
+                           Some(SeasonalAdjustmentConfig::new(SeasonalAdjustmentMode::Multiplicative))),
         DatasetConfig::new("eu_gdp",
                            "ecb",
                            "MNA/Q.Y.I9.W2.S1.S1.B.B1GQ._Z._Z._Z.EUR.LR.N",
                            false,
+                           None,
+                           Some(UnitConfig::new("EUR", Scale::Millions, true)),
                            None),
         DatasetConfig::new("eu_inflation",
                            "ecb",
@@ -106,12 +207,1075 @@ pub fn get_all_datasets_configs() -> Vec<DatasetConfig> {
                            Some(QuarterlyAverageConfig::new("quarter",
                                                             "value",
                                                             "value",
-                                                            "%Y-%m"))),
+                                                            "%Y-%m")),
+                           Some(UnitConfig::new("percent", Scale::Units, false)),
+                           Some(SeasonalAdjustmentConfig::new(SeasonalAdjustmentMode::Additive))),
+        DatasetConfig::new("eu_ppp",
+                           "world_bank",
+                           "EMU/PA.NUS.PPP",
+                           false,
+                           None,
+                           Some(UnitConfig::new("EUR per international dollar", Scale::Units, false)),
+                           None),
+        DatasetConfig::new("us_gdp_deflator",
+                           "fred",
+                           "GDPDEF",
+                           true,
+                           Some(QuarterlyAverageConfig::new("date",
+                                                            "value",
+                                                            "us_gdp_deflator",
+                                                            "%Y-%m-%d")),
+                           Some(UnitConfig::new("index 2017=100", Scale::Units, false)),
+                           None),
+        DatasetConfig::new("us_population",
+                           "fred",
+                           "POPTHM",
+                           true,
+                           Some(QuarterlyAverageConfig::new("date",
+                                                            "value",
+                                                            "us_population",
+                                                            "%Y-%m-%d")),
+                           Some(UnitConfig::new("persons", Scale::Thousands, false)),
+                           None),
+        DatasetConfig::new("eu_population",
+                           "world_bank",
+                           "EMU/SP.POP.TOTL",
+                           false,
+                           None,
+                           Some(UnitConfig::new("persons", Scale::Units, false)),
+                           None),
     ];
 
     all_datasets_configs
 }
 
+/// Operations reference columns of the joined dataset, including previously derived series.
+/// Growth rates are expressed in percent and compare each quarter to the preceding rows,
+/// so they assume the joined quarters are consecutive.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DerivedSeriesOperation {
+    Ratio { numerator: &'static str, denominator: &'static str },
+    Deflate { nominal: &'static str, price_index: &'static str },
+    PerCapita { value: &'static str, population: &'static str },
+    QuarterOnQuarterGrowth(&'static str),
+    YearOnYearGrowth(&'static str),
+    AnnualizedGrowth(&'static str),
+    LogDifference(&'static str),
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct DerivedSeriesConfig {
+    pub name: &'static str,
+    pub operation: DerivedSeriesOperation,
+    pub multiplier: f64,
+    pub unit_config: UnitConfig,
+}
+
+impl DerivedSeriesConfig {
+    pub fn new(name: &'static str,
+               operation: DerivedSeriesOperation,
+               multiplier: f64,
+               unit_config: UnitConfig
+    ) -> Self {
+        DerivedSeriesConfig {
+            name,
+            operation,
+            multiplier,
+            unit_config
+        }
+    }
+}
+
+/// ECB quarterly GDP is the output of a single quarter, while FRED reports GDP at an annual rate,
+/// so EU GDP is multiplied by this before it is compared with annual figures.
+const ECB_QUARTERLY_TO_ANNUAL: f64 = 4.0;
+/// Turns a ratio into percent.
+const PERCENT: f64 = 100.0;
+
+pub fn get_all_derived_series_configs() -> Vec<DerivedSeriesConfig> {
+    let percent = UnitConfig::new("percent", Scale::Units, false);
+
+    let all_derived_series_configs: Vec<DerivedSeriesConfig> = vec![
+        DerivedSeriesConfig::new("us_debt_to_gdp_perc",
+                                 DerivedSeriesOperation::Ratio { numerator: "us_total_debt_usd", denominator: "us_gdp_usd" },
+                                 PERCENT,
+                                 percent),
+        DerivedSeriesConfig::new("eu_debt_to_gdp_perc",
+                                 DerivedSeriesOperation::Ratio { numerator: "eu_government_debt_converted", denominator: "eu_gdp_converted" },
+                                 PERCENT / ECB_QUARTERLY_TO_ANNUAL,
+                                 percent),
+        DerivedSeriesConfig::new("us_gdp_real_usd",
+                                 DerivedSeriesOperation::Deflate { nominal: "us_gdp_usd", price_index: "us_gdp_deflator" },
+                                 1.0,
+                                 UnitConfig::new("chained 2017 USD", Scale::Billions, true)),
+        // Growth of EU GDP is measured in EUR, so that EUR/USD movements do not show up as growth
+        DerivedSeriesConfig::new("eu_gdp_eur",
+                                 DerivedSeriesOperation::Ratio { numerator: "eu_gdp_converted", denominator: "eur_to_usd" },
+                                 1.0,
+                                 UnitConfig::new("EUR", Scale::Billions, true)),
+        DerivedSeriesConfig::new("us_gdp_per_capita_usd",
+                                 DerivedSeriesOperation::PerCapita { value: "us_gdp_usd", population: "us_population" },
+                                 1.0,
+                                 UnitConfig::new("USD", Scale::Units, true)),
+        DerivedSeriesConfig::new("eu_gdp_per_capita_usd",
+                                 DerivedSeriesOperation::PerCapita { value: "eu_gdp_converted", population: "eu_population" },
+                                 ECB_QUARTERLY_TO_ANNUAL,
+                                 UnitConfig::new("USD", Scale::Units, true)),
+        DerivedSeriesConfig::new("us_gdp_real_qoq_perc",
+                                 DerivedSeriesOperation::QuarterOnQuarterGrowth("us_gdp_real_usd"),
+                                 1.0,
+                                 percent),
+        DerivedSeriesConfig::new("us_gdp_real_yoy_perc",
+                                 DerivedSeriesOperation::YearOnYearGrowth("us_gdp_real_usd"),
+                                 1.0,
+                                 percent),
+        DerivedSeriesConfig::new("us_gdp_real_annualized_perc",
+                                 DerivedSeriesOperation::AnnualizedGrowth("us_gdp_real_usd"),
+                                 1.0,
+                                 percent),
+        DerivedSeriesConfig::new("eu_gdp_qoq_perc",
+                                 DerivedSeriesOperation::QuarterOnQuarterGrowth("eu_gdp_eur"),
+                                 1.0,
+                                 percent),
+        DerivedSeriesConfig::new("eu_gdp_yoy_perc",
+                                 DerivedSeriesOperation::YearOnYearGrowth("eu_gdp_eur"),
+                                 1.0,
+                                 percent),
+        DerivedSeriesConfig::new("eu_gdp_annualized_perc",
+                                 DerivedSeriesOperation::AnnualizedGrowth("eu_gdp_eur"),
+                                 1.0,
+                                 percent),
+        DerivedSeriesConfig::new("sp500_yoy_perc",
+                                 DerivedSeriesOperation::YearOnYearGrowth("sp500_usd"),
+                                 1.0,
+                                 percent),
+        DerivedSeriesConfig::new("sp500_log_return_perc",
+                                 DerivedSeriesOperation::LogDifference("sp500_usd"),
+                                 1.0,
+                                 percent),
+    ];
+
+    all_derived_series_configs
+}
+
+/// Expressions are written against the joined column names, see `transformer::expression`
+/// for the supported syntax, e.g. `us_gdp_usd / us_gdp_deflator * 100`.
+#[derive(Clone, Copy, Debug)]
+pub struct ExpressionSeriesConfig {
+    pub name: &'static str,
+    pub expression: &'static str,
+    pub unit_config: UnitConfig,
+}
+
+impl ExpressionSeriesConfig {
+    pub fn new(name: &'static str,
+               expression: &'static str,
+               unit_config: UnitConfig
+    ) -> Self {
+        ExpressionSeriesConfig {
+            name,
+            expression,
+            unit_config
+        }
+    }
+}
+
+pub fn get_all_expression_series_configs() -> Vec<ExpressionSeriesConfig> {
+    let all_expression_series_configs: Vec<ExpressionSeriesConfig> = vec![
+        ExpressionSeriesConfig::new("inflation_spread_us_eu_perc",
+                                    "us_inflation_usd - eu_inflation",
+                                    UnitConfig::new("percentage points", Scale::Units, false)),
+        ExpressionSeriesConfig::new("sp500_usd_rolling_mean_4q",
+                                    "rolling_mean(sp500_usd, 4)",
+                                    UnitConfig::new("index points", Scale::Units, false)),
+    ];
+
+    all_expression_series_configs
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RebaseBasePeriod {
+    YearAverage(i32),
+}
+
+impl RebaseBasePeriod {
+    pub fn label(&self) -> String {
+        match self {
+            RebaseBasePeriod::YearAverage(year) => year.to_string(),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct RebaseConfig {
+    pub base_period: RebaseBasePeriod,
+    pub columns: &'static [&'static str],
+}
+
+impl RebaseConfig {
+    pub fn new(base_period: RebaseBasePeriod,
+               columns: &'static [&'static str]
+    ) -> Self {
+        RebaseConfig {
+            base_period,
+            columns
+        }
+    }
+}
+
+pub fn get_rebase_config() -> RebaseConfig {
+    RebaseConfig::new(RebaseBasePeriod::YearAverage(2015),
+                      &["sp500_usd",
+                        "us_gdp_usd",
+                        "eu_gdp_converted",
+                        "us_total_debt_usd",
+                        "eu_government_debt_converted"])
+}
+
+/// Splits a series into trend and cycle. Baxter-King keeps cycles between `min_period` and
+/// `max_period` quarters using `lead_lags` quarters on each side, so that many quarters are lost
+/// at both ends. Hamilton regresses the value `horizon` quarters ahead on the latest `lags` values.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CycleFilter {
+    HodrickPrescott { lambda: f64 },
+    BaxterKing { min_period: usize, max_period: usize, lead_lags: usize },
+    Hamilton { horizon: usize, lags: usize },
+}
+
+impl CycleFilter {
+    pub fn suffix(&self) -> &'static str {
+        match self {
+            CycleFilter::HodrickPrescott { .. } => "hp",
+            CycleFilter::BaxterKing { .. } => "bk",
+            CycleFilter::Hamilton { .. } => "hamilton",
+        }
+    }
+}
+
+/// Adds `<column>_<filter>_trend` and `<column>_<filter>_cycle`. With `logarithm` set the filter
+/// runs on the log of the column, so the cycle is the percent deviation from trend, e.g. an output gap.
+#[derive(Clone, Copy, Debug)]
+pub struct CycleFilterConfig {
+    pub column: &'static str,
+    pub filter: CycleFilter,
+    pub logarithm: bool,
+}
+
+impl CycleFilterConfig {
+    pub fn new(column: &'static str,
+               filter: CycleFilter,
+               logarithm: bool
+    ) -> Self {
+        CycleFilterConfig {
+            column,
+            filter,
+            logarithm
+        }
+    }
+}
+
+pub fn get_all_cycle_filter_configs() -> Vec<CycleFilterConfig> {
+    let hodrick_prescott = CycleFilter::HodrickPrescott { lambda: 1600.0 };
+    let baxter_king = CycleFilter::BaxterKing { min_period: 6, max_period: 32, lead_lags: 12 };
+    let hamilton = CycleFilter::Hamilton { horizon: 8, lags: 4 };
+
+    vec![
+        CycleFilterConfig::new("us_gdp_real_usd", hodrick_prescott, true),
+        CycleFilterConfig::new("eu_gdp_eur", hodrick_prescott, true),
+        CycleFilterConfig::new("us_gdp_real_usd", baxter_king, true),
+        CycleFilterConfig::new("eu_gdp_eur", baxter_king, true),
+        CycleFilterConfig::new("us_gdp_real_usd", hamilton, true),
+        CycleFilterConfig::new("eu_gdp_eur", hamilton, true),
+    ]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RecessionSource {
+    /// CSV file with `region,start_quarter,end_quarter` rows, both quarters in recession
+    DateRangeFile { path: &'static str, region: &'static str },
+    /// Monthly 0/1 recession indicator from FRED, e.g. `USREC`
+    Fred(&'static str),
+}
+
+/// Adds `column` to the joined dataset, set to 1 for quarters in recession and 0 otherwise.
+#[derive(Clone, Copy, Debug)]
+pub struct RecessionConfig {
+    pub column: &'static str,
+    pub source: RecessionSource,
+}
+
+impl RecessionConfig {
+    pub fn new(column: &'static str,
+               source: RecessionSource
+    ) -> Self {
+        RecessionConfig {
+            column,
+            source
+        }
+    }
+}
+
+/// Flags quarters belonging to a run of at least `consecutive_quarters` negative growth rates
+/// in `growth_column`, the "technical recession" rule of thumb.
+#[derive(Clone, Copy, Debug)]
+pub struct RecessionRuleConfig {
+    pub column: &'static str,
+    pub growth_column: &'static str,
+    pub consecutive_quarters: usize,
+}
+
+impl RecessionRuleConfig {
+    pub fn new(column: &'static str,
+               growth_column: &'static str,
+               consecutive_quarters: usize
+    ) -> Self {
+        RecessionRuleConfig {
+            column,
+            growth_column,
+            consecutive_quarters
+        }
+    }
+}
+
+pub fn get_all_recession_configs() -> Vec<RecessionConfig> {
+    vec![
+        RecessionConfig::new("us_recession_nber", RecessionSource::Fred("USREC")),
+        RecessionConfig::new("eu_recession_cepr",
+                             RecessionSource::DateRangeFile { path: "csv_data/recessions.csv", region: "euro_area" }),
+    ]
+}
+
+pub fn get_all_recession_rule_configs() -> Vec<RecessionRuleConfig> {
+    vec![
+        RecessionRuleConfig::new("us_technical_recession", "us_gdp_real_qoq_perc", 2),
+        RecessionRuleConfig::new("eu_technical_recession", "eu_gdp_qoq_perc", 2),
+    ]
+}
+
+/// Every column is compared to the benchmark over the quarters where both have a value.
+/// Cross-correlations are computed for leads and lags of up to `max_lag` quarters.
+#[derive(Clone, Copy, Debug)]
+pub struct StatisticsConfig {
+    pub benchmark: &'static str,
+    pub columns: &'static [&'static str],
+    pub rolling_window: usize,
+    pub max_lag: usize,
+}
+
+impl StatisticsConfig {
+    pub fn new(benchmark: &'static str,
+               columns: &'static [&'static str],
+               rolling_window: usize,
+               max_lag: usize
+    ) -> Self {
+        StatisticsConfig {
+            benchmark,
+            columns,
+            rolling_window,
+            max_lag
+        }
+    }
+}
+
+pub fn get_statistics_config() -> StatisticsConfig {
+    StatisticsConfig::new("sp500_yoy_perc",
+                          &["us_gdp_real_yoy_perc",
+                            "eu_gdp_yoy_perc",
+                            "us_inflation_usd",
+                            "eu_inflation",
+                            "inflation_spread_us_eu_perc",
+                            "us_debt_to_gdp_perc",
+                            "eu_debt_to_gdp_perc"],
+                          8,
+                          4)
+}
+
+/// Regresses `dependent` on the `regressors` and an intercept by ordinary least squares.
+/// With `newey_west_lags` set, standard errors are robust to heteroskedasticity and to
+/// autocorrelation up to that many quarters, which overlapping YoY growth rates exhibit.
+#[derive(Clone, Copy, Debug)]
+pub struct RegressionConfig {
+    pub dependent: &'static str,
+    pub regressors: &'static [&'static str],
+    pub newey_west_lags: Option<usize>,
+}
+
+impl RegressionConfig {
+    pub fn new(dependent: &'static str,
+               regressors: &'static [&'static str],
+               newey_west_lags: Option<usize>
+    ) -> Self {
+        RegressionConfig {
+            dependent,
+            regressors,
+            newey_west_lags
+        }
+    }
+}
+
+/// Tests whether lags of `cause` help predict `effect` beyond the lags of `effect` itself,
+/// once for every lag order.
+#[derive(Clone, Copy, Debug)]
+pub struct GrangerCausalityConfig {
+    pub cause: &'static str,
+    pub effect: &'static str,
+    pub lag_orders: &'static [usize],
+}
+
+impl GrangerCausalityConfig {
+    pub fn new(cause: &'static str,
+               effect: &'static str,
+               lag_orders: &'static [usize]
+    ) -> Self {
+        GrangerCausalityConfig {
+            cause,
+            effect,
+            lag_orders
+        }
+    }
+}
+
+pub fn get_all_regression_configs() -> Vec<RegressionConfig> {
+    vec![
+        RegressionConfig::new("sp500_yoy_perc", &["us_gdp_real_yoy_perc", "us_inflation_usd"], Some(4)),
+        RegressionConfig::new("sp500_yoy_perc", &["eu_gdp_yoy_perc", "eu_inflation"], Some(4)),
+    ]
+}
+
+pub fn get_all_granger_causality_configs() -> Vec<GrangerCausalityConfig> {
+    vec![
+        GrangerCausalityConfig::new("us_gdp_real_qoq_perc", "sp500_log_return_perc", &[1, 2, 4]),
+        GrangerCausalityConfig::new("eu_gdp_qoq_perc", "sp500_log_return_perc", &[1, 2, 4]),
+        GrangerCausalityConfig::new("us_inflation_usd", "sp500_log_return_perc", &[1, 2, 4]),
+        GrangerCausalityConfig::new("eu_inflation", "sp500_log_return_perc", &[1, 2, 4]),
+    ]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EuConversionMethod {
+    MarketRate,
+    PurchasingPowerParity,
+}
+
+impl EuConversionMethod {
+    /// The transformation recorded on the columns converted with this method.
+    pub fn description(&self) -> &'static str {
+        match self {
+            EuConversionMethod::MarketRate => "converted from EUR at the quarterly average EUR/USD market rate",
+            EuConversionMethod::PurchasingPowerParity => "converted from EUR at purchasing power parity (EUR per international dollar)",
+        }
+    }
+}
+
+pub fn get_target_monetary_scale() -> Scale {
+    Scale::Billions
+}
+
+pub fn get_eu_conversion_methods() -> Vec<EuConversionMethod> {
+    vec![
+        EuConversionMethod::PurchasingPowerParity,
+        EuConversionMethod::MarketRate,
+    ]
+}
+
+/// Checks run on a dataset straight after extraction. `date_column` must hold text dates
+/// (`2024-01-31`, `2024-01`, `2024-Q1` or `2024`) and `value_column` floats. Gaps and
+/// staleness are measured in days between the first days of the observed periods.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ValidationConfig {
+    pub dataset: &'static str,
+    pub date_column: &'static str,
+    pub value_column: &'static str,
+    pub min_non_null_ratio: f64,
+    pub value_bounds: Option<(f64, f64)>,
+    pub max_gap_days: i64,
+    pub max_staleness_days: i64,
+}
+
+impl ValidationConfig {
+    pub fn new(dataset: &'static str,
+               date_column: &'static str,
+               value_column: &'static str,
+               min_non_null_ratio: f64,
+               value_bounds: Option<(f64, f64)>,
+               max_gap_days: i64,
+               max_staleness_days: i64
+    ) -> Self {
+        ValidationConfig {
+            dataset,
+            date_column,
+            value_column,
+            min_non_null_ratio,
+            value_bounds,
+            max_gap_days,
+            max_staleness_days
+        }
+    }
+}
+
+pub fn get_all_validation_configs() -> Vec<ValidationConfig> {
+    vec![
+        ValidationConfig::new("fx_rates", "observation_date", "DEXUSEU", 0.9, Some((0.5, 2.0)), 7, 60),
+        ValidationConfig::new("sp500", "date", "value", 0.9, Some((100.0, 100_000.0)), 7, 14),
+        ValidationConfig::new("us_gdp", "date", "value", 0.95, Some((0.0, 100_000.0)), 92, 200),
+        ValidationConfig::new("us_total_public_debt", "date", "value", 0.95, Some((0.0, 1_000_000_000.0)), 92, 250),
+        ValidationConfig::new("us_inflation", "date", "value", 0.95, Some((-10.0, 30.0)), 31, 75),
+        ValidationConfig::new("eu_government_debt", "quarter", "value", 0.95, Some((0.0, 100_000_000.0)), 92, 300),
+        ValidationConfig::new("eu_gdp", "quarter", "value", 0.95, Some((0.0, 100_000_000.0)), 92, 200),
+        ValidationConfig::new("eu_inflation", "quarter", "value", 0.95, Some((-10.0, 30.0)), 31, 75),
+        ValidationConfig::new("eu_ppp", "date", "value", 0.9, Some((0.1, 5.0)), 366, 1000),
+        ValidationConfig::new("us_gdp_deflator", "date", "value", 0.95, Some((0.0, 1000.0)), 92, 200),
+        ValidationConfig::new("us_population", "date", "value", 0.95, Some((100_000.0, 1_000_000.0)), 31, 75),
+        ValidationConfig::new("eu_population", "date", "value", 0.9, Some((100_000_000.0, 1_000_000_000.0)), 366, 1000),
+    ]
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutlierMethod {
+    /// Distance from the mean of the preceding `window` observations, in standard deviations.
+    RollingZScore,
+    /// Hampel filter: 0.6745 * (x - median) / MAD over a `window` centred on the observation.
+    RollingMedianAbsoluteDeviation,
+}
+
+impl OutlierMethod {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutlierMethod::RollingZScore => "rolling_z_score",
+            OutlierMethod::RollingMedianAbsoluteDeviation => "rolling_mad",
+        }
+    }
+}
+
+/// Anomaly detection applied to every extracted dataset, using the columns of its
+/// `ValidationConfig`. Each of the `outlier_methods` scores every observation against a `window`
+/// of its neighbours in period order. A scale change is flagged when the median ratio to the
+/// previous run's values leaves [1 / `scale_change_factor`, `scale_change_factor`], a level shift
+/// when the median difference exceeds `level_shift_tolerance` times the median absolute cached
+/// value. With `max_anomalies` set, the run fails once a dataset has more anomalies than that.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct AnomalyConfig {
+    pub outlier_methods: &'static [OutlierMethod],
+    pub window: usize,
+    pub outlier_threshold: f64,
+    pub scale_change_factor: f64,
+    pub level_shift_tolerance: f64,
+    pub max_anomalies: Option<usize>,
+}
+
+impl AnomalyConfig {
+    pub fn new(outlier_methods: &'static [OutlierMethod],
+               window: usize,
+               outlier_threshold: f64,
+               scale_change_factor: f64,
+               level_shift_tolerance: f64,
+               max_anomalies: Option<usize>
+    ) -> Self {
+        AnomalyConfig {
+            outlier_methods,
+            window,
+            outlier_threshold,
+            scale_change_factor,
+            level_shift_tolerance,
+            max_anomalies
+        }
+    }
+}
+
+pub fn get_anomaly_config() -> AnomalyConfig {
+    AnomalyConfig::new(&[OutlierMethod::RollingMedianAbsoluteDeviation, OutlierMethod::RollingZScore], 9, 5.0, 2.0, 0.05, None)
+}
+
+/// Incremental updates refetch FRED and ECB series from the last cached period moved back by
+/// `revision_window_days`, so that recently revised observations are picked up as well.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IncrementalConfig {
+    pub revision_window_days: i64,
+}
+
+impl IncrementalConfig {
+    pub fn new(revision_window_days: i64) -> Self {
+        IncrementalConfig {
+            revision_window_days
+        }
+    }
+}
+
+pub fn get_incremental_config() -> IncrementalConfig {
+    IncrementalConfig::new(1095)
+}
+
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum LineStyle {
+    Solid,
+    Dashed,
+}
+
+/// The y-axis a series is drawn against: the left one, or the right one for series whose
+/// values are of a different magnitude than the rest of the chart.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChartAxis {
+    Primary,
+    Secondary,
+}
+
+/// A column of the result drawn as a line, with its legend label and RGB color.
+#[derive(Clone, Debug)]
+pub struct ChartSeriesConfig {
+    pub column: &'static str,
+    pub label: String,
+    pub color: (u8, u8, u8),
+    pub line_style: LineStyle,
+    pub axis: ChartAxis,
+}
+
+impl ChartSeriesConfig {
+    pub fn new(column: &'static str,
+               label: &str,
+               color: (u8, u8, u8),
+               line_style: LineStyle,
+               axis: ChartAxis
+    ) -> Self {
+        ChartSeriesConfig {
+            column,
+            label: label.to_string(),
+            color,
+            line_style,
+            axis
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChartFormat {
+    Png,
+    Svg,
+}
+
+impl ChartFormat {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            ChartFormat::Png => "png",
+            ChartFormat::Svg => "svg",
+        }
+    }
+}
+
+/// Charts are `size_inches` large, drawn at `dpi` pixels per inch. Text and lines scale with the
+/// DPI, so a chart keeps its layout at any resolution.
+#[derive(Clone, Copy, Debug)]
+pub struct ChartOutputConfig {
+    pub formats: &'static [ChartFormat],
+    pub size_inches: (f64, f64),
+    pub dpi: u32,
+}
+
+impl ChartOutputConfig {
+    pub fn new(formats: &'static [ChartFormat],
+               size_inches: (f64, f64),
+               dpi: u32
+    ) -> Self {
+        ChartOutputConfig {
+            formats,
+            size_inches,
+            dpi
+        }
+    }
+
+    pub fn pixel_size(&self) -> (u32, u32) {
+        let (width, height) = self.size_inches;
+        ((width * self.dpi as f64).round() as u32, (height * self.dpi as f64).round() as u32)
+    }
+}
+
+/// How a chart draws its series: as lines over the quarters, as bars of their average per
+/// calendar year grouped by year, as a scatter of the second series against the first with a
+/// least-squares line, or as a heatmap of the Pearson correlations between every pair of series.
+/// A heatmap without series covers every numeric column of the result.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ChartKind {
+    Line,
+    AnnualBar,
+    Scatter,
+    CorrelationHeatmap,
+}
+
+/// A chart written to `outputs/graph/<file_name>.<format>`. Only quarters where every series
+/// has a value are drawn, limited to the inclusive `date_range` of quarters when set.
+/// `y_labels` describes the left axis and, for charts with secondary series, the right one.
+#[derive(Clone, Debug)]
+pub struct ChartConfig {
+    pub file_name: &'static str,
+    pub kind: ChartKind,
+    pub title: &'static str,
+    pub y_labels: (String, Option<String>),
+    pub series: Vec<ChartSeriesConfig>,
+    pub date_range: Option<(&'static str, &'static str)>,
+    pub output: ChartOutputConfig,
+}
+
+impl ChartConfig {
+    pub fn new(file_name: &'static str,
+               kind: ChartKind,
+               title: &'static str,
+               y_labels: (&str, Option<&str>),
+               series: Vec<ChartSeriesConfig>,
+               date_range: Option<(&'static str, &'static str)>,
+               output: ChartOutputConfig
+    ) -> Self {
+        ChartConfig {
+            file_name,
+            kind,
+            title,
+            y_labels: (y_labels.0.to_string(), y_labels.1.map(str::to_string)),
+            series,
+            date_range,
+            output
+        }
+    }
+}
+
+const SP500_COLOR: (u8, u8, u8) = (0, 102, 0);
+const EU_COLOR: (u8, u8, u8) = (0, 0, 204);
+const US_COLOR: (u8, u8, u8) = (204, 0, 0);
+
+/// The line charts compare an EU and a US series with the S&P 500 and the bar chart compares their
+/// annual growth. The scatter charts plot each region's growth against S&P 500 returns, and the
+/// heatmap correlates every result column. `series` draws solid lines on the left axis.
+pub fn get_all_chart_configs() -> Vec<ChartConfig> {
+    let series = |column, label, color| ChartSeriesConfig::new(column, label, color, LineStyle::Solid, ChartAxis::Primary);
+    let output = get_chart_output_config();
+    let base_period = get_rebase_config().base_period.label();
+    let rebased = |label: &str| format!("{label} ({base_period} = 100)");
+    let sp500 = || ChartSeriesConfig::new("sp500_usd", "S&P 500", SP500_COLOR, LineStyle::Dashed, ChartAxis::Secondary);
+
+    vec![
+        ChartConfig::new("inflation",
+                         ChartKind::Line,
+                         "Inflation comparison EU vs USA",
+                         ("Inflation in %", Some("S&P 500 in index points")),
+                         vec![sp500(),
+                              series("eu_inflation_perc", "EU Inflation in %", EU_COLOR),
+                              series("us_inflation_perc", "US Inflation in %", US_COLOR)],
+                         None,
+                         output),
+        ChartConfig::new("gdp",
+                         ChartKind::Line,
+                         "GDP comparison EU vs USA",
+                         ("GDP in billions USD", Some("S&P 500 in index points")),
+                         vec![sp500(),
+                              series("eu_gdp_usd_billions", "EU GDP in billions USD", EU_COLOR),
+                              series("us_gdp_usd_billions", "US GDP in billions USD", US_COLOR)],
+                         None,
+                         output),
+        ChartConfig::new("debt",
+                         ChartKind::Line,
+                         "Debt comparison EU vs USA",
+                         ("Debt in billions USD", Some("S&P 500 in index points")),
+                         vec![sp500(),
+                              series("eu_government_debt_usd_billions", "EU Government debt in billions USD", EU_COLOR),
+                              series("us_total_debt_usd_billions", "US Debt in billions USD", US_COLOR)],
+                         None,
+                         output),
+        ChartConfig::new("growth",
+                         ChartKind::Line,
+                         "GDP growth comparison EU vs USA",
+                         ("GDP growth YoY in %", Some("S&P 500 YoY in %")),
+                         vec![ChartSeriesConfig::new("sp500_yoy_perc", "S&P 500 YoY in %", SP500_COLOR, LineStyle::Dashed, ChartAxis::Secondary),
+                              series("eu_gdp_yoy_perc", "EU GDP growth YoY in %", EU_COLOR),
+                              series("us_gdp_real_yoy_perc", "US real GDP growth YoY in %", US_COLOR)],
+                         None,
+                         output),
+        ChartConfig::new("gdp_rebased",
+                         ChartKind::Line,
+                         "GDP vs S&P 500 growth index EU vs USA",
+                         (&rebased("Index"), None),
+                         vec![series("sp500_usd_rebased", &rebased("S&P 500"), SP500_COLOR),
+                              series("eu_gdp_usd_rebased", &rebased("EU GDP in USD"), EU_COLOR),
+                              series("us_gdp_usd_rebased", &rebased("US GDP in USD"), US_COLOR)],
+                         None,
+                         output),
+        ChartConfig::new("debt_rebased",
+                         ChartKind::Line,
+                         "Debt vs S&P 500 growth index EU vs USA",
+                         (&rebased("Index"), None),
+                         vec![series("sp500_usd_rebased", &rebased("S&P 500"), SP500_COLOR),
+                              series("eu_government_debt_usd_rebased", &rebased("EU Government debt in USD"), EU_COLOR),
+                              series("us_total_debt_usd_rebased", &rebased("US Debt in USD"), US_COLOR)],
+                         None,
+                         output),
+        ChartConfig::new("output_gap",
+                         ChartKind::Line,
+                         "Business cycle comparison EU vs USA",
+                         ("Output gap in %", Some("S&P 500 YoY in %")),
+                         vec![ChartSeriesConfig::new("sp500_yoy_perc", "S&P 500 YoY in %", SP500_COLOR, LineStyle::Dashed, ChartAxis::Secondary),
+                              series("eu_gdp_eur_hp_cycle", "EU output gap in % (HP filter)", EU_COLOR),
+                              series("us_gdp_real_usd_hp_cycle", "US output gap in % (HP filter)", US_COLOR)],
+                         None,
+                         output),
+        ChartConfig::new("growth_annual",
+                         ChartKind::AnnualBar,
+                         "Annual GDP growth EU vs USA",
+                         ("Average YoY growth in %", None),
+                         vec![series("eu_gdp_yoy_perc", "EU GDP growth in %", EU_COLOR),
+                              series("us_gdp_real_yoy_perc", "US real GDP growth in %", US_COLOR)],
+                         None,
+                         output),
+        ChartConfig::new("us_growth_vs_sp500",
+                         ChartKind::Scatter,
+                         "US real GDP growth vs S&P 500 returns",
+                         ("S&P 500 YoY in %", None),
+                         vec![series("us_gdp_real_yoy_perc", "US real GDP growth YoY in %", US_COLOR),
+                              series("sp500_yoy_perc", "S&P 500 YoY in %", SP500_COLOR)],
+                         None,
+                         output),
+        ChartConfig::new("eu_growth_vs_sp500",
+                         ChartKind::Scatter,
+                         "EU GDP growth vs S&P 500 returns",
+                         ("S&P 500 YoY in %", None),
+                         vec![series("eu_gdp_yoy_perc", "EU GDP growth YoY in %", EU_COLOR),
+                              series("sp500_yoy_perc", "S&P 500 YoY in %", SP500_COLOR)],
+                         None,
+                         output),
+        ChartConfig::new("correlations",
+                         ChartKind::CorrelationHeatmap,
+                         "Correlations between all columns",
+                         ("", None),
+                         vec![],
+                         None,
+                         ChartOutputConfig::new(output.formats, (15.0, 15.0), 200)),
+    ]
+}
+
+/// 2000 x 1500 pixels as PNG, plus SVG for reports.
+pub fn get_chart_output_config() -> ChartOutputConfig {
+    ChartOutputConfig::new(&[ChartFormat::Png, ChartFormat::Svg], (10.0, 7.5), 200)
+}
+
+/// Every chart is also bundled as a page of `outputs/graph/<file name>.pdf`, in vector graphics
+/// at the chart's size in inches; `None` skips the PDF.
+pub fn get_chart_pdf_file_name() -> Option<&'static str> {
+    Some("charts")
+}
+
+
+/// A row of the EU–US differentials in the HTML report: the EU value minus the US value.
+#[derive(Clone, Copy, Debug)]
+pub struct DifferentialConfig {
+    pub label: &'static str,
+    pub eu_column: &'static str,
+    pub us_column: &'static str,
+}
+
+impl DifferentialConfig {
+    pub fn new(label: &'static str,
+               eu_column: &'static str,
+               us_column: &'static str
+    ) -> Self {
+        DifferentialConfig {
+            label,
+            eu_column,
+            us_column
+        }
+    }
+}
+
+/// Differentials of the final output columns.
+pub fn get_all_differential_configs() -> Vec<DifferentialConfig> {
+    vec![
+        DifferentialConfig::new("GDP growth YoY in %", "eu_gdp_yoy_perc", "us_gdp_real_yoy_perc"),
+        DifferentialConfig::new("Inflation in %", "eu_inflation_perc", "us_inflation_perc"),
+        DifferentialConfig::new("Output gap in % (HP filter)", "eu_gdp_eur_hp_cycle", "us_gdp_real_usd_hp_cycle"),
+        DifferentialConfig::new("GDP in billions USD", "eu_gdp_usd_billions", "us_gdp_usd_billions"),
+        DifferentialConfig::new("Government debt in billions USD", "eu_government_debt_usd_billions", "us_total_debt_usd_billions"),
+    ]
+}
+
+/// The HTML report is written to `outputs/html/<file name>.html`; `None` skips it.
+pub fn get_html_report_file_name() -> Option<&'static str> {
+    Some("report")
+}
+
+/// The Excel workbook is written to `outputs/xlsx/<file name>.xlsx`; `None` skips it.
+pub fn get_workbook_file_name() -> Option<&'static str> {
+    Some("result")
+}
+
+/// Formats the result is written in besides CSV and Parquet: JSON as one record per quarter
+/// (`outputs/json/result.json`) or one array per column (`result_columnar.json`), NDJSON with
+/// one record per line (`result.ndjson`) and Arrow IPC, also known as Feather
+/// (`outputs/ipc/result.arrow`).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Json,
+    JsonColumnar,
+    Ndjson,
+    ArrowIpc,
+}
+
+impl OutputFormat {
+    pub fn name(&self) -> &'static str {
+        match self {
+            OutputFormat::Json => "json",
+            OutputFormat::JsonColumnar => "json-columnar",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::ArrowIpc => "ipc",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [OutputFormat::Json, OutputFormat::JsonColumnar, OutputFormat::Ndjson, OutputFormat::ArrowIpc]
+            .into_iter()
+            .find(|format| format.name() == name)
+    }
+}
+
+/// Used unless the formats are given with `--formats` on the command line.
+pub fn get_output_formats() -> Vec<OutputFormat> {
+    vec![OutputFormat::Json, OutputFormat::JsonColumnar, OutputFormat::Ndjson, OutputFormat::ArrowIpc]
+}
+
+
+/// How the values of a column are written in exported tables. `Percent` appends a percent sign
+/// to values that already are percentages, e.g. `2.50%` for `2.5`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NumberFormat {
+    Decimal { decimals: usize, thousands_separator: bool },
+    Percent { decimals: usize },
+}
+
+#[derive(Clone, Copy, Debug)]
+pub struct TableColumnConfig {
+    pub column: &'static str,
+    pub header: &'static str,
+    pub format: NumberFormat,
+}
+
+impl TableColumnConfig {
+    pub fn new(column: &'static str,
+               header: &'static str,
+               format: NumberFormat
+    ) -> Self {
+        TableColumnConfig {
+            column,
+            header,
+            format
+        }
+    }
+}
+
+/// Writes the latest `quarters` of the result and summary statistics of its columns to
+/// `outputs/tables/<file name>.md` and `.tex`, with the columns in the given order. Without
+/// columns, every numeric column is exported with two decimals and thousands separators.
+#[derive(Clone, Debug)]
+pub struct TableExportConfig {
+    pub file_name: &'static str,
+    pub quarters: usize,
+    pub columns: Vec<TableColumnConfig>,
+}
+
+impl TableExportConfig {
+    pub fn new(file_name: &'static str,
+               quarters: usize,
+               columns: Vec<TableColumnConfig>
+    ) -> Self {
+        TableExportConfig {
+            file_name,
+            quarters,
+            columns
+        }
+    }
+}
+
+pub fn get_table_export_config() -> TableExportConfig {
+    let whole = NumberFormat::Decimal { decimals: 0, thousands_separator: true };
+    let percent = NumberFormat::Percent { decimals: 1 };
+
+    TableExportConfig::new("summary", 8, vec![
+        TableColumnConfig::new("sp500_usd", "S&P 500", whole),
+        TableColumnConfig::new("sp500_yoy_perc", "S&P 500 YoY", percent),
+        TableColumnConfig::new("eu_gdp_yoy_perc", "EU GDP YoY", percent),
+        TableColumnConfig::new("us_gdp_real_yoy_perc", "US real GDP YoY", percent),
+        TableColumnConfig::new("eu_inflation_perc", "EU inflation", percent),
+        TableColumnConfig::new("us_inflation_perc", "US inflation", percent),
+        TableColumnConfig::new("eu_gdp_usd_billions", "EU GDP (bn USD)", whole),
+        TableColumnConfig::new("us_gdp_usd_billions", "US GDP (bn USD)", whole),
+    ])
+}
+
+/// How the runs appended to the partitioned dataset are split into Hive partitions: by the date
+/// the run fetched its data, `run_date=2025-06-30`, or by the year of the quarters, `year=2024`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DatasetPartitioning {
+    RunDate,
+    Year,
+}
+
+impl DatasetPartitioning {
+    /// The name of the partition column.
+    pub fn name(&self) -> &'static str {
+        match self {
+            DatasetPartitioning::RunDate => "run_date",
+            DatasetPartitioning::Year => "year",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [DatasetPartitioning::RunDate, DatasetPartitioning::Year].into_iter().find(|partitioning| partitioning.name() == name)
+    }
+}
+
+/// Every run appends its result to `<directory>/result/scenario=<scenario>/<partition>/<run id>.parquet`
+/// and records itself in `<directory>/manifest.json`, so that runs of different configurations
+/// accumulate side by side under their own scenario.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PartitionedDatasetConfig {
+    pub directory: &'static str,
+    pub partitioning: DatasetPartitioning,
+    pub scenario: &'static str,
+}
+
+impl PartitionedDatasetConfig {
+    pub fn new(directory: &'static str,
+               partitioning: DatasetPartitioning,
+               scenario: &'static str) -> Self {
+        PartitionedDatasetConfig {
+            directory,
+            partitioning,
+            scenario
+        }
+    }
+}
+
+/// `None` writes no partitioned dataset.
+pub fn get_partitioned_dataset_config() -> Option<PartitionedDatasetConfig> {
+    Some(PartitionedDatasetConfig::new("outputs/dataset", DatasetPartitioning::RunDate, "baseline"))
+}
+
+/// A fingerprint of the configuration the result depends on: the datasets and every
+/// transformation, test and check applied to them, but not the charts and other outputs.
+/// Written as 16 hex digits of the 64-bit FNV-1a hash of the configs' `Debug` output.
+pub fn config_hash() -> String {
+    let configuration = format!("{:?}", (
+        get_all_datasets_configs(),
+        get_all_derived_series_configs(),
+        get_all_expression_series_configs(),
+        get_rebase_config(),
+        get_all_cycle_filter_configs(),
+        get_all_recession_configs(),
+        get_all_recession_rule_configs(),
+        get_statistics_config(),
+        get_all_regression_configs(),
+        get_all_granger_causality_configs(),
+        (get_target_monetary_scale(), get_eu_conversion_methods()),
+        (get_all_validation_configs(), get_anomaly_config(), get_incremental_config()),
+    ));
+
+    let hash = configuration.bytes().fold(0xcbf29ce484222325_u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    format!("{hash:016x}")
+}
+
 
 #[cfg(test)]
 mod tests {
@@ -137,6 +1301,8 @@ mod tests {
             "test_identifier",
             true,
             Some(quarterly_config),
+            Some(UnitConfig::new("USD", Scale::Millions, true)),
+            Some(SeasonalAdjustmentConfig::new(SeasonalAdjustmentMode::Multiplicative)),
         );
 
         assert_eq!(dataset.name, "test_dataset");
@@ -150,6 +1316,30 @@ mod tests {
         assert_eq!(qa_config.target_column_name, "value");
         assert_eq!(qa_config.target_column_alias, "avg_value");
         assert_eq!(qa_config.date_format_mask, "%Y-%m");
+
+        let unit_config = dataset.unit_config.unwrap();
+        assert_eq!(unit_config.unit, "USD");
+        assert_eq!(unit_config.scale, Scale::Millions);
+        assert!(unit_config.monetary);
+
+        let seasonal_adjustment_config = dataset.seasonal_adjustment_config.unwrap();
+        assert_eq!(seasonal_adjustment_config.mode, SeasonalAdjustmentMode::Multiplicative);
+    }
+
+    #[test]
+    fn test_scale_multiplier() {
+        assert_eq!(Scale::Units.multiplier(), 1.0);
+        assert_eq!(Scale::Millions.multiplier() / Scale::Billions.multiplier(), 0.001);
+        assert_eq!(Scale::Billions.name(), "billions");
+    }
+
+    #[test]
+    fn test_all_datasets_declare_units() {
+        let configs = get_all_datasets_configs();
+
+        for config in configs {
+            assert!(config.unit_config.is_some(), "{} is missing a unit", config.name);
+        }
     }
 
     #[test]
@@ -169,5 +1359,189 @@ mod tests {
         assert_eq!(qa_config.date_column_name, "date");
         assert_eq!(qa_config.target_column_name, "value");
         assert_eq!(qa_config.target_column_alias, "sp500_usd");
+        assert!(sp500_config.seasonal_adjustment_config.is_none());
+    }
+
+    #[test]
+    fn test_derived_series_names_are_unique() {
+        let configs = get_all_derived_series_configs();
+
+        let mut names: Vec<&str> = configs.iter().map(|c| c.name).collect();
+        names.sort();
+        names.dedup();
+
+        assert_eq!(names.len(), configs.len());
+    }
+
+    #[test]
+    fn test_expression_series_names_do_not_clash_with_derived_series() {
+        let derived_names: Vec<&str> = get_all_derived_series_configs().iter().map(|c| c.name).collect();
+
+        for config in get_all_expression_series_configs() {
+            assert!(!derived_names.contains(&config.name), "{} is defined twice", config.name);
+        }
+    }
+
+    #[test]
+    fn test_statistics_columns_are_configured_series() {
+        let statistics_config = get_statistics_config();
+        let derived_names: Vec<&str> = get_all_derived_series_configs().iter().map(|config| config.name).collect();
+
+        assert!(derived_names.contains(&statistics_config.benchmark));
+        assert!(!statistics_config.columns.contains(&statistics_config.benchmark));
+        assert!(statistics_config.rolling_window > statistics_config.max_lag);
+    }
+
+    #[test]
+    fn test_regression_and_granger_configs() {
+        for regression_config in get_all_regression_configs() {
+            assert!(!regression_config.regressors.is_empty());
+            assert!(!regression_config.regressors.contains(&regression_config.dependent));
+        }
+
+        for granger_config in get_all_granger_causality_configs() {
+            assert_ne!(granger_config.cause, granger_config.effect);
+            assert!(granger_config.lag_orders.iter().all(|lags| *lags > 0));
+        }
+    }
+
+    #[test]
+    fn test_cycle_filter_configs() {
+        let configs = get_all_cycle_filter_configs();
+        let derived_names: Vec<&str> = get_all_derived_series_configs().iter().map(|config| config.name).collect();
+
+        assert_eq!(CycleFilter::HodrickPrescott { lambda: 1600.0 }.suffix(), "hp");
+        for config in configs {
+            assert!(derived_names.contains(&config.column), "{} is not a derived series", config.column);
+            if let CycleFilter::BaxterKing { min_period, max_period, .. } = config.filter {
+                assert!(min_period >= 2 && min_period < max_period);
+            }
+        }
+    }
+
+    #[test]
+    fn test_recession_configs() {
+        let derived_names: Vec<&str> = get_all_derived_series_configs().iter().map(|config| config.name).collect();
+
+        for recession_config in get_all_recession_configs() {
+            if let RecessionSource::DateRangeFile { path, .. } = recession_config.source {
+                assert!(std::path::Path::new(path).exists(), "{path} is missing");
+            }
+        }
+
+        for rule_config in get_all_recession_rule_configs() {
+            assert!(derived_names.contains(&rule_config.growth_column));
+            assert!(rule_config.consecutive_quarters > 0);
+        }
+    }
+
+    #[test]
+    fn test_validation_configs() {
+        let dataset_names: Vec<&str> = get_all_datasets_configs().iter().map(|config| config.name).collect();
+        let validation_configs = get_all_validation_configs();
+
+        assert_eq!(validation_configs.len(), dataset_names.len());
+
+        for validation_config in validation_configs {
+            assert!(dataset_names.contains(&validation_config.dataset));
+            assert!((0.0..=1.0).contains(&validation_config.min_non_null_ratio));
+            if let Some((min, max)) = validation_config.value_bounds {
+                assert!(min < max);
+            }
+        }
+    }
+
+    #[test]
+    fn test_anomaly_config() {
+        let anomaly_config = get_anomaly_config();
+
+        assert!(!anomaly_config.outlier_methods.is_empty());
+        assert!(anomaly_config.window >= 3);
+        assert!(anomaly_config.outlier_threshold > 0.0);
+        assert!(anomaly_config.scale_change_factor > 1.0);
+    }
+
+    #[test]
+    fn test_get_incremental_config() {
+        assert!(get_incremental_config().revision_window_days >= 365);
+    }
+
+    #[test]
+    fn test_get_all_chart_configs() {
+        let chart_configs = get_all_chart_configs();
+
+        let mut file_names: Vec<&str> = chart_configs.iter().map(|config| config.file_name).collect();
+        file_names.sort();
+        file_names.dedup();
+        assert_eq!(file_names.len(), chart_configs.len());
+        assert!(chart_configs.iter()
+            .filter(|config| config.kind != ChartKind::CorrelationHeatmap)
+            .all(|config| !config.series.is_empty()));
+        assert!(chart_configs.iter()
+            .filter(|config| config.kind == ChartKind::Scatter)
+            .all(|config| config.series.len() == 2));
+        assert!(chart_configs.iter().all(|config| {
+            config.series.iter().any(|series| series.axis == ChartAxis::Secondary) == config.y_labels.1.is_some()
+        }));
+        assert!(chart_configs.iter().all(|config| config.date_range.is_none_or(|(start, end)| start <= end)));
+
+        let base_period = format!("({} = 100)", get_rebase_config().base_period.label());
+        assert!(chart_configs.iter()
+            .flat_map(|config| &config.series)
+            .filter(|series| series.column.ends_with("_rebased"))
+            .all(|series| series.label.ends_with(&base_period)));
+    }
+
+    #[test]
+    fn test_chart_output_pixel_size() {
+        assert_eq!(get_chart_output_config().pixel_size(), (2000, 1500));
+        assert_eq!(ChartOutputConfig::new(&[ChartFormat::Svg], (6.4, 4.8), 150).pixel_size(), (960, 720));
+    }
+
+    #[test]
+    fn test_get_all_differential_configs() {
+        let differential_configs = get_all_differential_configs();
+
+        assert!(differential_configs.iter().all(|config| config.eu_column.starts_with("eu_") && config.us_column.starts_with("us_")));
+        assert_eq!(differential_configs[0].label, "GDP growth YoY in %");
+    }
+
+    #[test]
+    fn test_get_table_export_config() {
+        let table_export_config = get_table_export_config();
+        let mut columns: Vec<&str> = table_export_config.columns.iter().map(|column| column.column).collect();
+        columns.sort();
+        columns.dedup();
+
+        assert!(table_export_config.quarters > 0);
+        assert_eq!(columns.len(), table_export_config.columns.len());
+    }
+
+    #[test]
+    fn test_output_format_from_name() {
+        assert!(get_output_formats().iter().all(|format| OutputFormat::from_name(format.name()) == Some(*format)));
+        assert_eq!(OutputFormat::from_name("feather"), None);
+    }
+
+    #[test]
+    fn test_config_hash_is_stable() {
+        assert_eq!(config_hash(), config_hash());
+        assert_eq!(config_hash().len(), 16);
+        assert!(config_hash().chars().all(|digit| digit.is_ascii_hexdigit()));
+    }
+
+    #[test]
+    fn test_dataset_partitioning_from_name() {
+        assert_eq!(DatasetPartitioning::from_name("run_date"), Some(DatasetPartitioning::RunDate));
+        assert_eq!(DatasetPartitioning::from_name("year"), Some(DatasetPartitioning::Year));
+        assert_eq!(DatasetPartitioning::from_name("quarter"), None);
+    }
+
+    #[test]
+    fn test_get_eu_conversion_methods() {
+        let methods = get_eu_conversion_methods();
+
+        assert!(methods.contains(&EuConversionMethod::MarketRate));
+        assert!(methods.contains(&EuConversionMethod::PurchasingPowerParity));
     }
 }
\ No newline at end of file
@@ -1,5 +1,5 @@
 use log::{info};
-use std::collections::HashMap;
+use std::collections::BTreeMap;
 use std::error::Error;
 use std::str;
 
@@ -12,7 +12,10 @@ use reqwest;
 use reqwest::Client;
 use reqwest::header;
 
-pub async fn get_data(endpoint: String, input_base_url: Option<&str>) -> Result<DataFrame, Box<dyn Error + Send + Sync>> {
+/// Fetches the series at `endpoint`, from `start_period` (e.g. `2024-Q1` or `2024-01`) onwards if given.
+pub async fn get_data(endpoint: String,
+                      start_period: Option<String>,
+                      input_base_url: Option<&str>) -> Result<DataFrame, Box<dyn Error + Send + Sync>> {
     let client = Client::new();
     let mut headers = header::HeaderMap::new();
     headers.insert(
@@ -22,7 +25,10 @@ pub async fn get_data(endpoint: String, input_base_url: Option<&str>) -> Result<
     let default_base_url = "https://data-api.ecb.europa.eu/service/data/";
     let base_url = input_base_url.unwrap_or(default_base_url);
 
-    let url = format!("{}{}", base_url, endpoint);
+    let mut url = format!("{}{}", base_url, endpoint);
+    if let Some(start_period) = start_period {
+        url.push_str(&format!("?startPeriod={start_period}"));
+    }
     info!("retrieving data from ecb: {url}");
 
     let resp = client
@@ -52,7 +58,8 @@ pub async fn get_data(endpoint: String, input_base_url: Option<&str>) -> Result<
     Ok(df)
     }
 
-fn parse_xml(xml: &str) -> Result<HashMap<String, f64>, Box<dyn Error>> {
+/// The observations by period, which sorts them as the periods are ISO-like (`2024-Q1`, `2024-01`).
+fn parse_xml(xml: &str) -> Result<BTreeMap<String, f64>, Box<dyn Error>> {
     let mut reader = Reader::from_str(xml);
 
     let mut quarters: Vec<String> = Vec::new();
@@ -96,9 +103,9 @@ fn parse_xml(xml: &str) -> Result<HashMap<String, f64>, Box<dyn Error>> {
 
     validate_quarters_and_values(&quarters, &values);
 
-    let result_hashmap: HashMap<String, f64> = quarters.into_iter().zip(values.into_iter()).collect();
+    let result_map: BTreeMap<String, f64> = quarters.into_iter().zip(values.into_iter()).collect();
 
-    Ok(result_hashmap)
+    Ok(result_map)
 }
 
 fn validate_quarters_and_values(quarters: &Vec<String>, values: &Vec<f64>) -> () {
@@ -114,7 +121,12 @@ mod tests {
     use polars::datatypes::PlSmallStr;
     use tokio;
 
+    use chrono::NaiveDate;
+
+    use crate::datasets_config::datasets_config::get_all_validation_configs;
+    use crate::model::data_model::ValidationStatus;
     use crate::tests::test_helpers::test_helpers::assert_frame_equal;
+    use crate::validator::validation::validate_dataset;
 
     use super::*;
 
@@ -133,7 +145,7 @@ mod tests {
 
         let result = parse_xml(xml_data).expect("XML parsing failed");
 
-        let mut expected = HashMap::new();
+        let mut expected = BTreeMap::new();
         expected.insert("2024-Q1".to_string(), 1000.50);
         expected.insert("2024-Q2".to_string(), 2000.75);
         expected.insert("2024-Q3".to_string(), 3000.25);
@@ -147,7 +159,7 @@ mod tests {
 
         let result = parse_xml(xml_data).expect("Should return an empty map");
 
-        let expected: HashMap<String, f64> = HashMap::new();
+        let expected: BTreeMap<String, f64> = BTreeMap::new();
 
         assert_eq!(result, expected);
     }
@@ -205,7 +217,7 @@ mod tests {
         let endpoint = "/mock-endpoint".to_string();
         let base_url = server.url();
 
-        let df_result = get_data(endpoint, Some(&base_url)).await.expect("Failed to get data");
+        let df_result = get_data(endpoint, None, Some(&base_url)).await.expect("Failed to get data");
 
         // Expected DataFrame
         let expected_quarters = Series::new(PlSmallStr::from_str("quarter"), &["2023-Q1", "2023-Q2"]);
@@ -216,6 +228,60 @@ mod tests {
         assert_frame_equal(&df_result, &expected_df);
     }
 
+    #[tokio::test]
+    async fn test_get_data_from_start_period() {
+        let xml_response = r#"
+        <root>
+            <generic:ObsDimension value="2024-Q4" xmlns:generic="generic"/>
+            <generic:ObsValue value="3.45" xmlns:generic="generic"/>
+        </root>
+        "#;
+
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/mock-endpoint?startPeriod=2024-Q4")
+            .with_status(200)
+            .with_body(xml_response)
+            .create();
+
+        let df_result = get_data("/mock-endpoint".to_string(), Some("2024-Q4".to_string()), Some(&server.url()))
+            .await
+            .expect("Failed to get data");
+
+        assert_frame_equal(&df_result, &df!["quarter" => &["2024-Q4"], "value" => &[3.45]].unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_get_data_sorts_observations_by_period() {
+        let xml_response = r#"
+        <root>
+            <generic:ObsDimension value="2024-Q3" xmlns:generic="generic"/>
+            <generic:ObsValue value="3.45" xmlns:generic="generic"/>
+            <generic:ObsDimension value="2023-Q4" xmlns:generic="generic"/>
+            <generic:ObsValue value="1.23" xmlns:generic="generic"/>
+            <generic:ObsDimension value="2024-Q1" xmlns:generic="generic"/>
+            <generic:ObsValue value="2.34" xmlns:generic="generic"/>
+        </root>
+        "#;
+
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/mock-endpoint")
+            .with_status(200)
+            .with_body(xml_response)
+            .create();
+
+        let df_result = get_data("/mock-endpoint".to_string(), None, Some(&server.url())).await.unwrap();
+
+        assert_eq!(df_result.column("quarter").unwrap().str().unwrap().into_no_null_iter().collect::<Vec<_>>(),
+                   vec!["2023-Q4", "2024-Q1", "2024-Q3"]);
+
+        let config = get_all_validation_configs().into_iter().find(|config| config.dataset == "eu_gdp").unwrap();
+        let checks = validate_dataset(&df_result, &config, NaiveDate::from_ymd_opt(2024, 12, 31).unwrap());
+        let monotonic_dates = checks.iter().find(|check| check.rule == "monotonic_dates").unwrap();
+        assert_eq!(monotonic_dates.status, ValidationStatus::Passed);
+    }
+
     #[tokio::test]
     async fn test_get_data_http_error() {
         let mut server = mockito::Server::new_async().await;
@@ -227,7 +293,7 @@ mod tests {
         let endpoint = "/mock-endpoint".to_string();
         let base_url = server.url();
 
-        let result = get_data(endpoint, Some(&base_url)).await;
+        let result = get_data(endpoint, None, Some(&base_url)).await;
         assert!(result.is_err(), "Expected an error on HTTP 500 response");
     }
 }
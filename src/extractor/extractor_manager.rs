@@ -1,27 +1,33 @@
+use std::collections::HashMap;
 use std::future::Future;
 
 use polars::prelude::DataFrame;
 use tokio::task::JoinHandle;
 
 use crate::datasets_config::datasets_config::{DatasetConfig, get_all_datasets_configs};
-use crate::extractor::{csv, ecb, fred};
+use crate::extractor::{csv, ecb, fred, world_bank};
 use crate::extractor::fred::get_fred_api_key;
 use crate::model::data_model::{AllDatasets, DatasetWithConfig};
 
 type DynError = Box<dyn std::error::Error + Send + Sync>;
 
-pub async fn extract_data() -> AllDatasets {
+/// Fetches every configured dataset. Datasets listed in `start_periods` are only fetched from
+/// that period onwards, which FRED and ECB support; the other sources always return the full history.
+pub async fn extract_data(start_periods: &HashMap<String, String>) -> AllDatasets {
     let all_datasets_configs = get_all_datasets_configs();
 
     let mut handles: Vec<JoinHandle<DatasetWithConfig>> = vec![];
 
     for dataset_config in all_datasets_configs {
+        let start_period = start_periods.get(dataset_config.name).cloned();
         let handle: JoinHandle<DatasetWithConfig> = tokio::spawn(async move {
             retrieve_dataset(
                 dataset_config,
+                start_period,
                 |id| csv::get_data(id),
-                |id, _opt, get_key| fred::get_data(id, None, get_key),
-                |id, _opt| ecb::get_data(id, None),
+                |id, start_period, get_key| fred::get_data(id, start_period, None, get_key),
+                |id, start_period| ecb::get_data(id, start_period, None),
+                |id, _opt| world_bank::get_data(id, None),
             ).await
         });
 
@@ -48,6 +54,10 @@ pub async fn extract_data() -> AllDatasets {
         all_datasets_vector[5].clone(),
         all_datasets_vector[6].clone(),
         all_datasets_vector[7].clone(),
+        all_datasets_vector[8].clone(),
+        all_datasets_vector[9].clone(),
+        all_datasets_vector[10].clone(),
+        all_datasets_vector[11].clone(),
     );
 
     all_datasets
@@ -60,21 +70,28 @@ pub async fn retrieve_dataset<
     FredFut,
     EcbFn,
     EcbFut,
+    WorldBankFn,
+    WorldBankFut,
 >(
     dataset_config: DatasetConfig,
+    start_period: Option<String>,
     get_csv_data: CsvFn,
     get_fred_data: FredFn,
     get_ecb_data: EcbFn,
+    get_world_bank_data: WorldBankFn,
 ) -> DatasetWithConfig
 where
     CsvFn: Fn(String) -> CsvFut + Send + Sync,
     CsvFut: Future<Output = Result<DataFrame, DynError>> + Send,
 
-    FredFn: Fn(String, Option<()>, fn() -> String) -> FredFut + Send + Sync,
+    FredFn: Fn(String, Option<String>, fn() -> String) -> FredFut + Send + Sync,
     FredFut: Future<Output = Result<DataFrame, DynError>> + Send,
 
-    EcbFn: Fn(String, Option<()>) -> EcbFut + Send + Sync,
+    EcbFn: Fn(String, Option<String>) -> EcbFut + Send + Sync,
     EcbFut: Future<Output = Result<DataFrame, DynError>> + Send,
+
+    WorldBankFn: Fn(String, Option<()>) -> WorldBankFut + Send + Sync,
+    WorldBankFut: Future<Output = Result<DataFrame, DynError>> + Send,
 {
     let source = dataset_config.source;
     let identifier = dataset_config.identifier;
@@ -85,10 +102,13 @@ where
             get_csv_data(identifier.to_string()).await.unwrap()
         },
         "fred" => {
-            get_fred_data(identifier.to_string(), None, get_fred_api_key).await.unwrap()
+            get_fred_data(identifier.to_string(), start_period, get_fred_api_key).await.unwrap()
         },
         "ecb" => {
-            get_ecb_data(identifier.to_string(), None).await.unwrap()
+            get_ecb_data(identifier.to_string(), start_period).await.unwrap()
+        },
+        "world_bank" => {
+            get_world_bank_data(identifier.to_string(), None).await.unwrap()
         },
         _ => {
             panic!("Unknown source type: {}", source);
@@ -123,7 +143,9 @@ mod tests {
             identifier: "test_id",
             name: "test_dataset",
             quarterly_avg_required: true,
-            quarterly_average_config: None
+            quarterly_average_config: None,
+            unit_config: None,
+            seasonal_adjustment_config: None
         }
     }
 
@@ -162,6 +184,7 @@ mod tests {
         AllDatasets::new(
             all[0].clone(), all[1].clone(), all[2].clone(), all[0].clone(),
             all[1].clone(), all[2].clone(), all[0].clone(), all[1].clone(),
+            all[2].clone(), all[0].clone(), all[1].clone(), all[2].clone(),
         )
     }
 
@@ -171,12 +194,14 @@ mod tests {
 
         let result = retrieve_dataset(
             config.clone(),
+            None,
             |id| async move {
                 assert_eq!(id, "test_id");
                 Ok(dummy_dataframe())
             },
             |_id, _opt, _key_fn| async { panic!("should not be called in csv test") },
             |_id, _opt| async { panic!("should not be called in csv test") },
+            |_id, _opt| async { panic!("should not be called in csv test") },
         ).await;
 
         assert_eq!(result.name, "test_dataset");
@@ -193,13 +218,16 @@ mod tests {
         }
         let result = retrieve_dataset(
             config.clone(),
+            None,
             |_id| async { panic!("should not be called in fred test") },
-            |id, _opt, key_fn| async move {
+            |id, start_period, key_fn| async move {
                 assert_eq!(id, "test_id");
+                assert_eq!(start_period, None);
                 assert_eq!(key_fn(), "mocked_api_key");
                 Ok(dummy_dataframe())
             },
             |_id, _opt| async { panic!("should not be called in fred test") },
+            |_id, _opt| async { panic!("should not be called in fred test") },
         ).await;
 
         assert_eq!(result.name, "test_dataset");
@@ -212,18 +240,57 @@ mod tests {
 
         let result = retrieve_dataset(
             config.clone(),
+            None,
             |_id| async { panic!("should not be called in ecb test") },
             |_id, _opt, _key_fn| async { panic!("should not be called in ecb test") },
             |id, _opt| async move {
                 assert_eq!(id, "test_id");
                 Ok(dummy_dataframe())
             },
+            |_id, _opt| async { panic!("should not be called in ecb test") },
         ).await;
 
         assert_eq!(result.name, "test_dataset");
         assert_eq!(result.dataset_config.source, "ecb");
     }
 
+    #[tokio::test]
+    async fn test_retrieve_dataset_passes_start_period() {
+        let result = retrieve_dataset(
+            sample_config("ecb"),
+            Some("2024-Q3".to_string()),
+            |_id| async { panic!("should not be called in ecb test") },
+            |_id, _opt, _key_fn| async { panic!("should not be called in ecb test") },
+            |_id, start_period| async move {
+                assert_eq!(start_period.as_deref(), Some("2024-Q3"));
+                Ok(dummy_dataframe())
+            },
+            |_id, _opt| async { panic!("should not be called in ecb test") },
+        ).await;
+
+        assert_eq!(result.dataset_config.source, "ecb");
+    }
+
+    #[tokio::test]
+    async fn test_retrieve_dataset_world_bank() {
+        let config = sample_config("world_bank");
+
+        let result = retrieve_dataset(
+            config,
+            None,
+            |_id| async { panic!("should not be called in world bank test") },
+            |_id, _opt, _key_fn| async { panic!("should not be called in world bank test") },
+            |_id, _opt| async { panic!("should not be called in world bank test") },
+            |id, _opt| async move {
+                assert_eq!(id, "test_id");
+                Ok(dummy_dataframe())
+            },
+        ).await;
+
+        assert_eq!(result.name, "test_dataset");
+        assert_eq!(result.dataset_config.source, "world_bank");
+    }
+
 
     #[tokio::test]
     async fn test_extract_data_with_mocked_sources() {
@@ -232,21 +299,27 @@ mod tests {
             identifier: "id_1",
             name: "dataset_1",
             quarterly_avg_required: false,
-            quarterly_average_config: None
+            quarterly_average_config: None,
+            unit_config: None,
+            seasonal_adjustment_config: None
         };
         let dataset_config_fred = DatasetConfig {
             source: "fred",
             identifier: "id_2",
             name: "dataset_2",
             quarterly_avg_required: false,
-            quarterly_average_config: None
+            quarterly_average_config: None,
+            unit_config: None,
+            seasonal_adjustment_config: None
         };
         let dataset_config_ecb = DatasetConfig {
             source: "ecb",
             identifier: "id_3",
             name: "dataset_3",
             quarterly_avg_required: false,
-            quarterly_average_config: None
+            quarterly_average_config: None,
+            unit_config: None,
+            seasonal_adjustment_config: None
         };
 
         let configs: Vec<DatasetConfig> = vec![dataset_config_csv, dataset_config_fred, dataset_config_ecb];
@@ -283,6 +356,6 @@ mod tests {
 
         let result = extract_data_with(configs, get_csv, get_fred, get_ecb).await;
 
-        assert_eq!(result.items().len(), 8);
+        assert_eq!(result.items().len(), 12);
     }
 }
\ No newline at end of file
@@ -21,13 +21,20 @@ pub struct FredResponse {
     observations: Vec<Observation>,
 }
 
-pub async fn get_data(series_id: String, input_base_url: Option<&str>, get_api_key: fn() -> String) -> Result<DataFrame, Box<dyn Error + Send + Sync>> {
+/// Fetches the observations of `series_id`, from `observation_start` (`YYYY-MM-DD`) onwards if given.
+pub async fn get_data(series_id: String,
+                      observation_start: Option<String>,
+                      input_base_url: Option<&str>,
+                      get_api_key: fn() -> String) -> Result<DataFrame, Box<dyn Error + Send + Sync>> {
     let api_key = get_api_key();
 
     let default_base_url = "https://api.stlouisfed.org/fred/series/observations";
     let base_url = input_base_url.unwrap_or(default_base_url);
 
-    let url = format!("{}?series_id={}&api_key={}&file_type=json", base_url, series_id, api_key);
+    let mut url = format!("{}?series_id={}&api_key={}&file_type=json", base_url, series_id, api_key);
+    if let Some(observation_start) = observation_start {
+        url.push_str(&format!("&observation_start={observation_start}"));
+    }
     info!("retrieving data from fred: {url}");
 
     let client = Client::new();
@@ -107,7 +114,7 @@ mod tests {
         let base_url = server.url();
         let input_url = format!("{}{}", base_url, endpoint);
 
-        let df_result = get_data(series_id, Some(&input_url), mock_get_api_key).await.expect("Failed to get data");
+        let df_result = get_data(series_id, None, Some(&input_url), mock_get_api_key).await.expect("Failed to get data");
 
         // Expected DataFrame
         let expected_quarters = Series::new(PlSmallStr::from_str("quarter"), &["1966-01-01", "1966-04-01", "1966-07-01"]);
@@ -118,6 +125,33 @@ mod tests {
         assert_frame_equal(&df_result, &expected_df);
     }
 
+    #[tokio::test]
+    async fn test_get_data_from_observation_start() {
+        let json_response = r#"
+        {"observations":[
+            {"realtime_start":"2025-03-24","realtime_end":"2025-03-24","date":"2024-10-01","value":"29700.5"}
+        ]}
+        "#;
+
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/mock-endpoint?series_id=GDP&api_key=mocked_api_key&file_type=json&observation_start=2024-10-01")
+            .with_status(200)
+            .with_body(json_response)
+            .create();
+
+        fn mock_get_api_key() -> String {
+            "mocked_api_key".to_string()
+        }
+        let input_url = format!("{}/mock-endpoint", server.url());
+
+        let df_result = get_data("GDP".to_string(), Some("2024-10-01".to_string()), Some(&input_url), mock_get_api_key)
+            .await
+            .expect("Failed to get data");
+
+        assert_frame_equal(&df_result, &df!["date" => &["2024-10-01"], "value" => &[29700.5]].unwrap());
+    }
+
     #[test]
     fn test_get_fred_api_key_from_env() {
         unsafe {
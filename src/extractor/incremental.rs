@@ -0,0 +1,220 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use chrono::{Datelike, Duration, NaiveDate};
+use log::info;
+use polars::prelude::*;
+
+use crate::datasets_config::datasets_config::{DatasetConfig, IncrementalConfig, ValidationConfig};
+use crate::model::data_model::{AllDatasets, DatasetWithConfig};
+use crate::validator::validation::period_start;
+
+// ECB's `updatedAfter` is not used: for a single series it returns the whole requested span as
+// soon as any observation changed, so `startPeriod` with the revision window already bounds
+// what is fetched.
+const INCREMENTAL_SOURCES: [&str; 2] = ["fred", "ecb"];
+
+/// The period from which each cached FRED or ECB dataset is refetched: its last cached period
+/// moved back by the revision window, in the same format as the cached periods. Datasets that
+/// are not cached, or whose cache does not match their validation config, are fetched in full.
+pub fn start_periods(dataset_configs: &[DatasetConfig],
+                     cached_datasets: &HashMap<String, DataFrame>,
+                     validation_configs: &[ValidationConfig],
+                     incremental_config: &IncrementalConfig) -> HashMap<String, String> {
+    let mut start_periods: HashMap<String, String> = HashMap::new();
+
+    for dataset_config in dataset_configs.iter().filter(|config| INCREMENTAL_SOURCES.contains(&config.source)) {
+        let Some(cached) = cached_datasets.get(dataset_config.name) else {
+            continue;
+        };
+        let Some(validation_config) = validation_configs.iter().find(|config| config.dataset == dataset_config.name) else {
+            continue;
+        };
+        if cached.column(validation_config.value_column).map(|column| column.dtype() != &DataType::Float64).unwrap_or(true) {
+            continue;
+        }
+
+        let Some((last_period, last_period_start)) = cached_periods(cached, validation_config.date_column)
+            .and_then(|periods| periods.into_iter().max_by_key(|(_, start)| *start)) else {
+            continue;
+        };
+
+        let start = last_period_start - Duration::days(incremental_config.revision_window_days);
+        start_periods.insert(dataset_config.name.to_string(), format_like(start, &last_period));
+    }
+
+    start_periods
+}
+
+/// Replaces the cached observations from each start period onwards with the fetched ones;
+/// datasets fetched in full replace their cache. Returns the merged datasets and, per cached
+/// dataset, the periods that are new or whose value changed compared to the cache.
+pub fn merge_with_cache(all_datasets: AllDatasets,
+                        cached_datasets: &HashMap<String, DataFrame>,
+                        start_periods: &HashMap<String, String>,
+                        validation_configs: &[ValidationConfig]) -> (AllDatasets, BTreeMap<String, Vec<String>>) {
+    let mut updated_periods: BTreeMap<String, Vec<String>> = BTreeMap::new();
+
+    let merged_datasets: Vec<DatasetWithConfig> = all_datasets.items()
+        .into_iter()
+        .map(|dataset| {
+            let (Some(cached), Some(validation_config)) = (
+                cached_datasets.get(dataset.name),
+                validation_configs.iter().find(|config| config.dataset == dataset.name),
+            ) else {
+                return dataset;
+            };
+
+            let merged = match start_periods.get(dataset.name) {
+                Some(start_period) => merge_dataset(dataset.name, cached, &dataset.dataframe, start_period, validation_config),
+                None => dataset.dataframe.clone(),
+            };
+
+            let updated = updated_periods_of(cached, &dataset.dataframe, validation_config);
+            info!("{}: {} new or revised observations", dataset.name, updated.len());
+            updated_periods.insert(dataset.name.to_string(), updated);
+
+            DatasetWithConfig::new(dataset.name, merged, dataset.dataset_config)
+        })
+        .collect();
+
+    (AllDatasets::from_items(merged_datasets), updated_periods)
+}
+
+/// The quarters containing any of the updated periods. They are logged only: the update
+/// recomputes every quarter, as seasonal adjustment, filters and rebasing depend on the whole
+/// history.
+pub fn affected_quarters(updated_periods: &BTreeMap<String, Vec<String>>) -> BTreeSet<String> {
+    updated_periods.values()
+        .flatten()
+        .filter_map(|period| period_start(period))
+        .map(|start| format_like(start, "2000-Q1"))
+        .collect()
+}
+
+fn merge_dataset(dataset_name: &str,
+                 cached: &DataFrame,
+                 fetched: &DataFrame,
+                 start_period: &str,
+                 validation_config: &ValidationConfig) -> DataFrame {
+    let start = period_start(start_period).unwrap();
+
+    let kept_mask: BooleanChunked = cached.column(validation_config.date_column).unwrap().str().unwrap()
+        .into_iter()
+        .map(|date| date.and_then(period_start).is_some_and(|period| period < start))
+        .collect();
+
+    // Periods of one dataset share a format that sorts chronologically as text, e.g. `2024-Q1`
+    cached.filter(&kept_mask).unwrap()
+        .vstack(fetched)
+        .and_then(|merged| merged.sort([validation_config.date_column], SortMultipleOptions::default()))
+        .unwrap_or_else(|error| panic!("could not merge the fetched {dataset_name} into its cache: {error}"))
+}
+
+fn updated_periods_of(cached: &DataFrame, fetched: &DataFrame, validation_config: &ValidationConfig) -> Vec<String> {
+    let cached_values: HashMap<String, Option<f64>> = observations(cached, validation_config).into_iter().collect();
+
+    observations(fetched, validation_config)
+        .into_iter()
+        .filter(|(period, value)| cached_values.get(period) != Some(value))
+        .map(|(period, _)| period)
+        .collect()
+}
+
+fn observations(dataframe: &DataFrame, validation_config: &ValidationConfig) -> Vec<(String, Option<f64>)> {
+    let (Ok(dates), Ok(values)) = (dataframe.column(validation_config.date_column).and_then(|dates| dates.str()),
+                                   dataframe.column(validation_config.value_column).and_then(|values| values.f64())) else {
+        return vec![];
+    };
+
+    dates.into_iter()
+        .zip(values)
+        .filter_map(|(date, value)| Some((date?.to_string(), value)))
+        .collect()
+}
+
+fn cached_periods(cached: &DataFrame, date_column: &str) -> Option<Vec<(String, NaiveDate)>> {
+    cached.column(date_column).ok()?.str().ok()?
+        .into_iter()
+        .map(|date| Some((date?.to_string(), period_start(date?)?)))
+        .collect()
+}
+
+/// Formats `date` as the period containing it, in the format of `template`.
+fn format_like(date: NaiveDate, template: &str) -> String {
+    if template.contains("-Q") {
+        format!("{}-Q{}", date.year(), date.month0() / 3 + 1)
+    } else if template.len() == 7 {
+        date.format("%Y-%m").to_string()
+    } else {
+        date.format("%Y-%m-%d").to_string()
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::datasets_config::datasets_config::get_all_datasets_configs;
+
+    use super::*;
+
+    fn validation_config() -> ValidationConfig {
+        ValidationConfig::new("eu_gdp", "quarter", "value", 0.9, None, 92, 200)
+    }
+
+    fn cached() -> DataFrame {
+        df![
+            "quarter" => &["2023-Q3", "2023-Q4", "2024-Q1", "2024-Q2"],
+            "value" => &[Some(1.0), Some(2.0), Some(3.0), None]
+        ].unwrap()
+    }
+
+    #[test]
+    fn test_format_like() {
+        let date = NaiveDate::from_ymd_opt(2024, 5, 17).unwrap();
+
+        assert_eq!(format_like(date, "2024-Q1"), "2024-Q2");
+        assert_eq!(format_like(date, "2024-01"), "2024-05");
+        assert_eq!(format_like(date, "2024-01-01"), "2024-05-17");
+    }
+
+    #[test]
+    fn test_start_periods() {
+        let cached_datasets: HashMap<String, DataFrame> = [
+            ("eu_gdp".to_string(), cached()),
+            ("fx_rates".to_string(), df!["observation_date" => &["2024-01-02"], "DEXUSEU" => &[1.1]].unwrap()),
+        ].into_iter().collect();
+        let validation_configs = [validation_config(),
+                                  ValidationConfig::new("fx_rates", "observation_date", "DEXUSEU", 0.9, None, 7, 60)];
+
+        let start_periods = start_periods(&get_all_datasets_configs(), &cached_datasets, &validation_configs, &IncrementalConfig::new(180));
+
+        assert_eq!(start_periods, [("eu_gdp".to_string(), "2023-Q4".to_string())].into_iter().collect());
+    }
+
+    #[test]
+    fn test_merge_dataset() {
+        let fetched = df![
+            "quarter" => &["2024-Q3", "2024-Q1", "2024-Q2"],
+            "value" => &[Some(5.0), Some(3.0), Some(4.0)]
+        ].unwrap();
+
+        let merged = merge_dataset("eu_gdp", &cached(), &fetched, "2024-Q1", &validation_config());
+
+        assert_eq!(merged, df![
+            "quarter" => &["2023-Q3", "2023-Q4", "2024-Q1", "2024-Q2", "2024-Q3"],
+            "value" => &[1.0, 2.0, 3.0, 4.0, 5.0]
+        ].unwrap());
+        assert_eq!(updated_periods_of(&cached(), &fetched, &validation_config()), vec!["2024-Q3", "2024-Q2"]);
+    }
+
+    #[test]
+    fn test_affected_quarters() {
+        let updated_periods: BTreeMap<String, Vec<String>> = [
+            ("eu_gdp".to_string(), vec!["2024-Q3".to_string()]),
+            ("sp500".to_string(), vec!["2024-09-30".to_string(), "2024-10-01".to_string()]),
+            ("us_gdp".to_string(), vec![]),
+        ].into_iter().collect();
+
+        assert_eq!(affected_quarters(&updated_periods).into_iter().collect::<Vec<_>>(), vec!["2024-Q3", "2024-Q4"]);
+    }
+}
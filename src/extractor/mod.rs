@@ -1,4 +1,7 @@
 pub mod csv;
 pub mod fred;
 pub mod ecb;
-pub mod extractor_manager;
\ No newline at end of file
+pub mod world_bank;
+pub mod recession;
+pub mod extractor_manager;
+pub mod incremental;
\ No newline at end of file
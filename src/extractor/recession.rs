@@ -0,0 +1,158 @@
+use std::collections::BTreeSet;
+use std::error::Error;
+
+use log::{info, warn};
+use polars::prelude::*;
+
+use crate::datasets_config::datasets_config::{RecessionConfig, RecessionSource};
+use crate::extractor::fred::get_fred_api_key;
+use crate::extractor::{csv, fred};
+use crate::model::data_model::RecessionPeriods;
+
+type DynError = Box<dyn Error + Send + Sync>;
+
+/// Loads the recession quarters of every config. Sources that cannot be loaded are skipped, so
+/// their flag column is missing from the result rather than failing the run.
+pub async fn extract_recessions(recession_configs: &[RecessionConfig]) -> Vec<RecessionPeriods> {
+    let mut recession_periods: Vec<RecessionPeriods> = vec![];
+
+    for recession_config in recession_configs {
+        let column_name = recession_config.column;
+
+        let (source, quarters) = match recession_config.source {
+            RecessionSource::Fred(series_id) => {
+                let quarters = fred::get_data(series_id.to_string(), None, None, get_fred_api_key).await
+                    .and_then(|indicator| quarters_from_indicator(&indicator));
+                (format!("FRED {series_id}"), quarters)
+            },
+            RecessionSource::DateRangeFile { path, region } => {
+                let quarters = csv::get_data(path.to_string()).await
+                    .and_then(|date_ranges| quarters_from_date_ranges(&date_ranges, region));
+                (format!("{path} ({region})"), quarters)
+            },
+        };
+
+        match quarters {
+            Ok(quarters) => {
+                info!("loaded {} recession quarters for {column_name} from {source}", quarters.len());
+                recession_periods.push(RecessionPeriods::new(column_name, &source, quarters));
+            },
+            Err(error) => warn!("skipping {column_name}, could not load recessions from {source}: {error}"),
+        }
+    }
+
+    recession_periods
+}
+
+/// Quarters in which any observation of a `date`/`value` indicator is positive.
+pub fn quarters_from_indicator(indicator: &DataFrame) -> Result<Vec<String>, DynError> {
+    let dates = indicator.column("date")?.str()?;
+    let values = indicator.column("value")?.cast(&DataType::Float64)?;
+
+    let quarters: BTreeSet<String> = dates.into_iter()
+        .zip(values.f64()?)
+        .filter_map(|(date, value)| if value? > 0.0 { quarter_of(date?) } else { None })
+        .collect();
+
+    Ok(quarters.into_iter().collect())
+}
+
+/// Expands the `start_quarter`..=`end_quarter` ranges of `region` into their quarters.
+pub fn quarters_from_date_ranges(date_ranges: &DataFrame, region: &str) -> Result<Vec<String>, DynError> {
+    let regions = date_ranges.column("region")?.str()?;
+    let start_quarters = date_ranges.column("start_quarter")?.str()?;
+    let end_quarters = date_ranges.column("end_quarter")?.str()?;
+
+    let mut quarters: BTreeSet<String> = BTreeSet::new();
+
+    for ((row_region, start_quarter), end_quarter) in regions.into_iter().zip(start_quarters).zip(end_quarters) {
+        if row_region != Some(region) {
+            continue;
+        }
+
+        let (Some(start_quarter), Some(end_quarter)) = (start_quarter, end_quarter) else {
+            return Err(format!("incomplete date range for {region}").into());
+        };
+
+        let start = parse_quarter(start_quarter).ok_or(format!("invalid quarter '{start_quarter}'"))?;
+        let end = parse_quarter(end_quarter).ok_or(format!("invalid quarter '{end_quarter}'"))?;
+
+        let mut current = start;
+        while current <= end {
+            quarters.insert(format!("{}-Q{}", current.0, current.1));
+            current = if current.1 == 4 { (current.0 + 1, 1) } else { (current.0, current.1 + 1) };
+        }
+    }
+
+    Ok(quarters.into_iter().collect())
+}
+
+fn quarter_of(date: &str) -> Option<String> {
+    let mut parts = date.split('-');
+    let year: i32 = parts.next()?.parse().ok()?;
+    let month: u32 = parts.next()?.parse().ok()?;
+
+    (1..=12).contains(&month).then(|| format!("{year}-Q{}", (month - 1) / 3 + 1))
+}
+
+fn parse_quarter(quarter: &str) -> Option<(i32, u32)> {
+    let (year, quarter) = quarter.split_once("-Q")?;
+    let quarter: u32 = quarter.parse().ok()?;
+
+    (1..=4).contains(&quarter).then_some((year.parse().ok()?, quarter))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_quarters_from_indicator() {
+        let indicator = df![
+            "date" => &["2019-12-01", "2020-01-01", "2020-02-01", "2020-04-01", "2020-07-01"],
+            "value" => &[0.0, 0.0, 1.0, 1.0, 0.0]
+        ].unwrap();
+
+        assert_eq!(quarters_from_indicator(&indicator).unwrap(), vec!["2020-Q1", "2020-Q2"]);
+    }
+
+    #[test]
+    fn test_quarters_from_date_ranges() {
+        let date_ranges = df![
+            "region" => &["euro_area", "united_states", "euro_area"],
+            "start_quarter" => &["2008-Q2", "2008-Q1", "2011-Q4"],
+            "end_quarter" => &["2008-Q3", "2009-Q2", "2012-Q1"]
+        ].unwrap();
+
+        assert_eq!(quarters_from_date_ranges(&date_ranges, "euro_area").unwrap(),
+                   vec!["2008-Q2", "2008-Q3", "2011-Q4", "2012-Q1"]);
+        assert!(quarters_from_date_ranges(&date_ranges, "japan").unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_quarters_from_date_ranges_rejects_invalid_quarters() {
+        let date_ranges = df![
+            "region" => &["euro_area"],
+            "start_quarter" => &["2008-Q5"],
+            "end_quarter" => &["2009-Q1"]
+        ].unwrap();
+
+        let error = quarters_from_date_ranges(&date_ranges, "euro_area").unwrap_err();
+        assert_eq!(error.to_string(), "invalid quarter '2008-Q5'");
+    }
+
+    #[tokio::test]
+    async fn test_extract_recessions_from_date_range_file() {
+        let recession_configs = [RecessionConfig::new("eu_recession_cepr",
+                                                      RecessionSource::DateRangeFile { path: "csv_data/recessions.csv", region: "euro_area" })];
+
+        let recession_periods = extract_recessions(&recession_configs).await;
+
+        assert_eq!(recession_periods.len(), 1);
+        assert_eq!(recession_periods[0].column_name, "eu_recession_cepr");
+        assert_eq!(recession_periods[0].source, "csv_data/recessions.csv (euro_area)");
+        assert!(recession_periods[0].quarters.contains(&"2009-Q1".to_string()));
+        assert!(!recession_periods[0].quarters.contains(&"2010-Q1".to_string()));
+    }
+}
@@ -0,0 +1,110 @@
+use log::{info};
+use std::error::Error;
+
+use polars::prelude::*;
+use reqwest;
+use reqwest::Client;
+use serde::Deserialize;
+use serde::de::IgnoredAny;
+
+#[derive(Debug, Deserialize)]
+pub struct WorldBankObservation {
+    date: String,
+    value: Option<f64>,
+}
+
+#[derive(Deserialize)]
+pub struct WorldBankResponse(IgnoredAny, Option<Vec<WorldBankObservation>>);
+
+pub async fn get_data(identifier: String, input_base_url: Option<&str>) -> Result<DataFrame, Box<dyn Error + Send + Sync>> {
+    let (country, indicator) = identifier
+        .split_once('/')
+        .ok_or_else(|| format!("World Bank identifier must be in the format <country>/<indicator>, got: {identifier}"))?;
+
+    let default_base_url = "https://api.worldbank.org/v2";
+    let base_url = input_base_url.unwrap_or(default_base_url);
+
+    let url = format!("{}/country/{}/indicator/{}?format=json&per_page=20000", base_url, country, indicator);
+    info!("retrieving data from world bank: {url}");
+
+    let client = Client::new();
+
+    let resp = client.get(&url)
+        .send().await?;
+
+    if !resp.status().is_success() {
+        return Err(format!("Request failed with status: {}", resp.status()).into());
+    }
+
+    let api_response: WorldBankResponse = resp.json().await?;
+    let observations = api_response.1.unwrap_or_default();
+
+    let dates: Vec<String> = observations.iter().map(|o| o.date.clone()).collect();
+    let values: Vec<Option<f64>> = observations.iter().map(|o| o.value).collect();
+
+    let df = df![
+        "date" => dates,
+        "value" => values
+    ]?;
+
+    Ok(df)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_helpers::test_helpers::assert_frame_equal;
+
+    use super::*;
+
+    #[tokio::test]
+    async fn test_get_data_success() {
+        let json_response = r#"
+        [
+            {"page":1,"pages":1,"per_page":20000,"total":3,"sourceid":"2","lastupdated":"2025-04-15"},
+            [
+                {"indicator":{"id":"PA.NUS.PPP","value":"PPP conversion factor"},"country":{"id":"XC","value":"Euro area"},"countryiso3code":"EMU","date":"2024","value":null,"unit":"","obs_status":"","decimal":0},
+                {"indicator":{"id":"PA.NUS.PPP","value":"PPP conversion factor"},"country":{"id":"XC","value":"Euro area"},"countryiso3code":"EMU","date":"2023","value":0.71,"unit":"","obs_status":"","decimal":0},
+                {"indicator":{"id":"PA.NUS.PPP","value":"PPP conversion factor"},"country":{"id":"XC","value":"Euro area"},"countryiso3code":"EMU","date":"2022","value":0.69,"unit":"","obs_status":"","decimal":0}
+            ]
+        ]
+        "#;
+
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/country/EMU/indicator/PA.NUS.PPP?format=json&per_page=20000")
+            .with_status(200)
+            .with_body(json_response)
+            .create();
+
+        let base_url = server.url();
+
+        let df_result = get_data("EMU/PA.NUS.PPP".to_string(), Some(&base_url)).await.expect("Failed to get data");
+
+        let expected_df = df![
+            "date" => &["2024", "2023", "2022"],
+            "value" => &[None, Some(0.71), Some(0.69)]
+        ].unwrap();
+
+        assert_frame_equal(&df_result, &expected_df);
+    }
+
+    #[tokio::test]
+    async fn test_get_data_invalid_identifier() {
+        let result = get_data("PA.NUS.PPP".to_string(), Some("http://localhost")).await;
+        assert!(result.is_err(), "Expected an error for an identifier without a country");
+    }
+
+    #[tokio::test]
+    async fn test_get_data_http_error() {
+        let mut server = mockito::Server::new_async().await;
+
+        server.mock("GET", "/country/EMU/indicator/PA.NUS.PPP?format=json&per_page=20000")
+            .with_status(500)
+            .create();
+
+        let base_url = server.url();
+
+        let result = get_data("EMU/PA.NUS.PPP".to_string(), Some(&base_url)).await;
+        assert!(result.is_err(), "Expected an error on HTTP 500 response");
+    }
+}
@@ -0,0 +1,81 @@
+use std::collections::HashMap;
+use std::fs::{self, File};
+use std::path::Path;
+
+use log::{info, warn};
+use polars::prelude::*;
+
+use crate::model::data_model::DatasetWithConfig;
+
+pub const CACHE_DIRECTORY: &str = "outputs/cache";
+
+/// Stores the extracted datasets as `<cache_directory>/<dataset>.parquet` for the next run.
+pub fn cache_datasets(datasets: &[DatasetWithConfig], cache_directory: &str) {
+    fs::create_dir_all(cache_directory).expect("could not create cache directory");
+
+    for dataset in datasets {
+        let mut dataframe = dataset.dataframe.clone();
+        let file = File::create(cached_dataset_path(cache_directory, dataset.name)).expect("could not create file");
+
+        ParquetWriter::new(file)
+            .with_compression(ParquetCompression::Snappy)
+            .finish(&mut dataframe)
+            .unwrap();
+    }
+
+    info!("cached {} extracted datasets in {cache_directory}", datasets.len());
+}
+
+/// Reads the datasets cached by the previous run. Datasets that were never cached are missing.
+pub fn read_cached_datasets(dataset_names: &[&str], cache_directory: &str) -> HashMap<String, DataFrame> {
+    let mut cached_datasets: HashMap<String, DataFrame> = HashMap::new();
+
+    for dataset_name in dataset_names {
+        let path = cached_dataset_path(cache_directory, dataset_name);
+        if !Path::new(&path).exists() {
+            continue;
+        }
+
+        match File::open(&path).map_err(PolarsError::from).and_then(|file| ParquetReader::new(file).finish()) {
+            Ok(dataframe) => {
+                cached_datasets.insert(dataset_name.to_string(), dataframe);
+            },
+            Err(error) => warn!("could not read cached {dataset_name} from {path}: {error}"),
+        }
+    }
+
+    cached_datasets
+}
+
+fn cached_dataset_path(cache_directory: &str, dataset_name: &str) -> String {
+    format!("{cache_directory}/{dataset_name}.parquet")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::datasets_config::datasets_config::get_all_datasets_configs;
+
+    use super::*;
+
+    #[test]
+    fn test_cache_round_trip() {
+        let cache_directory = tempdir().unwrap();
+        let cache_directory = cache_directory.path().join("cache");
+        let cache_directory = cache_directory.to_str().unwrap();
+        let dataframe = df![
+            "date" => &["2024-01-01", "2024-04-01"],
+            "value" => &[Some(1.5), None]
+        ].unwrap();
+        let dataset_config = get_all_datasets_configs()[1];
+
+        cache_datasets(&[DatasetWithConfig::new(dataset_config.name, dataframe.clone(), dataset_config)], cache_directory);
+
+        let cached_datasets = read_cached_datasets(&[dataset_config.name, "not_cached"], cache_directory);
+
+        assert_eq!(cached_datasets.len(), 1);
+        assert_eq!(cached_datasets[dataset_config.name], dataframe);
+    }
+}
@@ -2,12 +2,21 @@ use std::fs::File;
 
 use polars::prelude::*;
 
-pub fn load(mut result_dataframe: DataFrame) -> () {
-    let mut file = File::create("outputs/csv/result.csv").expect("could not create file");
+pub fn load(result_dataframe: DataFrame) -> () {
+    write_csv(result_dataframe, "outputs/csv/result.csv");
+}
+
+/// Writes an analysis table next to the result, e.g. `statistics` to `outputs/csv/statistics.csv`.
+pub fn load_table(table: DataFrame, name: &str) {
+    write_csv(table, &format!("outputs/csv/{name}.csv"));
+}
+
+fn write_csv(mut dataframe: DataFrame, path: &str) {
+    let mut file = File::create(path).expect("could not create file");
     let _ = CsvWriter::new(&mut file)
         .include_header(true)
         .with_separator(b',')
-        .finish(&mut result_dataframe);
+        .finish(&mut dataframe);
 }
 
 
@@ -40,4 +49,20 @@ mod tests {
         let normalized = contents.replace("\r\n", "\n");
         assert_eq!(normalized, expected);
     }
+
+    #[test]
+    fn test_load_table_writes_csv() {
+        let df = df![
+            "series" => &["us_inflation_perc"],
+            "statistic" => &["pearson"],
+            "value" => &[0.5]
+        ].unwrap();
+
+        fs::create_dir_all("outputs/csv").unwrap();
+
+        load_table(df, "statistics");
+
+        let contents = fs::read_to_string("outputs/csv/statistics.csv").expect("Failed to read statistics.csv");
+        assert_eq!(contents.replace("\r\n", "\n"), "series,statistic,value\nus_inflation_perc,pearson,0.5\n");
+    }
 }
\ No newline at end of file
@@ -1,149 +1,661 @@
-use log::{info};
+use std::error::Error;
+use std::fs;
+use std::ops::Range;
+
+use log::{info, warn};
 use polars::prelude::*;
+use plotters::chart::DualCoordChartContext;
+use plotters::coord::types::{RangedCoordf64, RangedCoordusize};
+use plotters::coord::Shift;
 use plotters::prelude::*;
 
-#[derive(Clone, Copy)]
-pub struct GraphConfiguration {
-    pub col_name_1: &'static str,
-    pub col_name_1_label: &'static str,
-    pub col_name_2: &'static str,
-    pub col_name_2_label: &'static str,
-    pub col_name_sp500: &'static str,
-    pub col_name_sp500_label: &'static str,
-    pub file_name: &'static str,
-    pub caption: &'static str
-}
-
-impl GraphConfiguration {
-    pub fn new(col_name_1: &'static str,
-               col_name_1_label: &'static str,
-               col_name_2: &'static str,
-               col_name_2_label: &'static str,
-               col_name_sp500: &'static str,
-               col_name_sp500_label: &'static str,
-               file_name: &'static str,
-               caption: &'static str
-
-    ) -> Self {
-        GraphConfiguration {
-            col_name_1,
-            col_name_1_label,
-            col_name_2,
-            col_name_2_label,
-            col_name_sp500,
-            col_name_sp500_label,
-            file_name,
-            caption
+use crate::datasets_config::datasets_config::{ChartAxis, ChartConfig, ChartFormat, ChartKind, ChartSeriesConfig, LineStyle};
+use crate::loader::pdf::{write_pdf, PdfBackend, PdfPage};
+use crate::transformer::regression::ols;
+use crate::transformer::stats::{column_values, complete_pairs, pearson};
+
+const GRAPH_DIRECTORY: &str = "outputs/graph";
+
+type Chart<'a, DB> = DualCoordChartContext<'a, DB, Cartesian2d<RangedCoordusize, RangedCoordf64>, Cartesian2d<RangedCoordusize, RangedCoordf64>>;
+
+enum ChartData {
+    Series(SeriesData),
+    Correlations(CorrelationMatrix),
+}
+
+/// The quarters of a chart and the values of each of its series in those quarters.
+pub(super) struct SeriesData {
+    pub(super) quarters: Vec<String>,
+    pub(super) values: Vec<Vec<f64>>,
+    pub(super) recession_flags: Vec<(&'static str, RGBColor, Vec<bool>)>,
+}
+
+/// Pearson correlations between the columns, `None` where too few quarters have both values.
+struct CorrelationMatrix {
+    columns: Vec<String>,
+    coefficients: Vec<Vec<Option<f64>>>,
+}
+
+/// Writes every chart in its configured formats and, with a PDF file name, bundles them into a
+/// PDF with one page per chart.
+pub fn generate_graphs(result: &DataFrame, chart_configs: &[ChartConfig], pdf_file_name: Option<&str>) {
+    fs::create_dir_all(GRAPH_DIRECTORY).expect("could not create graph directory");
+
+    let mut pdf_pages: Vec<PdfPage> = vec![];
+
+    for chart_config in chart_configs {
+        let data = match chart_data(result, chart_config) {
+            Ok(data) => data,
+            Err(error) => {
+                warn!("skipping chart {}: {error}", chart_config.file_name);
+                continue;
+            },
+        };
+
+        for format in chart_config.output.formats {
+            let file_path = format!("{GRAPH_DIRECTORY}/{}.{}", chart_config.file_name, format.extension());
+
+            match write_chart(&data, chart_config, *format, &file_path) {
+                Ok(()) => info!("Plot saved as {file_path}"),
+                Err(error) => warn!("could not write {file_path}: {error}"),
+            }
+        }
+
+        if pdf_file_name.is_some() {
+            match pdf_page(&data, chart_config) {
+                Ok(page) => pdf_pages.push(page),
+                Err(error) => warn!("leaving chart {} out of the PDF: {error}", chart_config.file_name),
+            }
+        }
+    }
+
+    if let Some(pdf_file_name) = pdf_file_name {
+        let file_path = format!("{GRAPH_DIRECTORY}/{pdf_file_name}.pdf");
+
+        match write_pdf(&file_path, &pdf_pages) {
+            Ok(()) => info!("{} charts saved as {file_path}", pdf_pages.len()),
+            Err(error) => warn!("could not write {file_path}: {error}"),
         }
     }
 }
 
-pub fn generate_graphs(result: DataFrame) -> () {
-    let result = result.lazy().with_column((col("sp500_usd") / lit(1000)).alias("sp500_usd_in_thousands")).collect().unwrap();
-    let result = result.lazy().with_column((col("sp500_usd") * lit(10000)).alias("sp500_usd_mult_by_ten_thousand")).collect().unwrap();
-    let result = result.lazy().with_column((col("eu_gdp_usd_millions") / lit(1000)).alias("eu_gdp_usd_billions")).collect().unwrap();
-
-    let inflation_graph_configuration = GraphConfiguration::new("eu_inflation_perc",
-                                                                "EU Inflation in %",
-                                                                "us_inflation_perc",
-                                                                "US Inflation in %",
-                                                                "sp500_usd_in_thousands",
-                                                                "S&P 500 in thousands",
-                                                                "inflation",
-                                                                "Inflation comparison EU vs USA");
-
-    let gdp_graph_configuration = GraphConfiguration::new("eu_gdp_usd_billions",
-                                                          "EU GDP in billions USD",
-                                                          "us_gdp_usd_billions",
-                                                          "US GDP in billions USD",
-                                                          "sp500_usd",
-                                                          "S&P 500 in thousands",
-                                                          "gdp",
-                                                          "GDP comparison EU vs USA");
-
-    let total_debt_graph_configuration = GraphConfiguration::new("eu_government_debt_usd_millions",
-                                                          "EU Government debt in millions USD",
-                                                          "us_total_debt_usd_millions",
-                                                          "US Debt in millions USD",
-                                                          "sp500_usd_mult_by_ten_thousand",
-                                                          "S&P 500 multiplied by 10'000",
-                                                          "debt",
-                                                          "Debt comparison EU vs USA");
-
-    generate_graph(result.clone(), inflation_graph_configuration);
-    generate_graph(result.clone(), gdp_graph_configuration);
-    generate_graph(result.clone(), total_debt_graph_configuration);
-
-}
-
-
-fn generate_graph(result: DataFrame, graph_configuration: GraphConfiguration) -> () {
-    let result = result.sort(["quarter"], SortMultipleOptions::new()).unwrap();
-    let quarters = result.column("quarter").unwrap().str().unwrap().into_no_null_iter().collect::<Vec<_>>();
-    let sp_500 = result.column(graph_configuration.col_name_sp500).unwrap().f64().unwrap().into_no_null_iter().collect::<Vec<_>>();
-    let col_1 = result.column(graph_configuration.col_name_1).unwrap().f64().unwrap().into_no_null_iter().collect::<Vec<_>>();
-    let col_2 = result.column(graph_configuration.col_name_2).unwrap().f64().unwrap().into_no_null_iter().collect::<Vec<_>>();
-
-    // Set up drawing area
-    let all_values = sp_500.iter()
-        .chain(col_1.iter())
-        .chain(col_2.iter())
-        ;
-
-    let (y_min, y_max) = all_values.clone().fold((f64::MAX, f64::MIN), |(min, max), &v| {
-        (min.min(v), max.max(v))
-    });
-    let padding = (y_max - y_min) * 0.1;
-    let y_range = (y_min - padding)..(y_max + padding);
+fn chart_data(result: &DataFrame, chart_config: &ChartConfig) -> Result<ChartData, Box<dyn Error>> {
+    match chart_config.kind {
+        ChartKind::Scatter if chart_config.series.len() != 2 => Err("a scatter chart needs exactly two series".into()),
+        ChartKind::CorrelationHeatmap => Ok(ChartData::Correlations(correlation_matrix(result, chart_config)?)),
+        _ => Ok(ChartData::Series(series_data(result, chart_config)?)),
+    }
+}
 
-    // Plotting
-    let file_name = graph_configuration.file_name;
-    let file_path = format!("outputs/graph/{file_name}.png");
-    let root = BitMapBackend::new(&file_path, (2000, 1500)).into_drawing_area();
-    root.fill(&WHITE).unwrap();
+pub(super) fn series_data(result: &DataFrame, chart_config: &ChartConfig) -> Result<SeriesData, Box<dyn Error>> {
+    // Derived growth rates are null for the first quarters
+    let plotted_columns: Vec<String> = chart_config.series.iter().map(|series| series.column.to_string()).collect();
+    let result = quarters_in_range(&result.drop_nulls(Some(&plotted_columns))?, chart_config)?;
+
+    if result.height() == 0 {
+        return Err("no quarter has a value for every series".into());
+    }
+
+    let quarters = result.column("quarter")?.str()?.into_no_null_iter().map(String::from).collect();
+
+    let values = chart_config.series.iter()
+        .map(|series| Ok(result.column(series.column)?.cast(&DataType::Float64)?.f64()?.into_no_null_iter().collect()))
+        .collect::<Result<Vec<Vec<f64>>, PolarsError>>()?;
+
+    let recession_shadings = [("us_recession_nber", "US recession (NBER)", RGBColor(204, 0, 0)),
+                              ("eu_recession_cepr", "EU recession (CEPR)", RGBColor(0, 0, 204))];
+    let recession_flags = recession_shadings.into_iter()
+        .filter_map(|(column_name, label, color)| {
+            let flags = result.column(column_name).and_then(|flags| flags.cast(&DataType::Int32)).ok()?;
+            let flags = flags.i32().ok()?.into_iter().map(|flag| flag == Some(1)).collect();
+            Some((label, color, flags))
+        })
+        .collect();
+
+    Ok(SeriesData { quarters, values, recession_flags })
+}
+
+fn correlation_matrix(result: &DataFrame, chart_config: &ChartConfig) -> Result<CorrelationMatrix, Box<dyn Error>> {
+    let result = quarters_in_range(result, chart_config)?;
+
+    let columns: Vec<String> = if chart_config.series.is_empty() {
+        result.get_columns()
+            .iter()
+            .filter(|column| column.dtype() == &DataType::Float64)
+            .map(|column| column.name().to_string())
+            .collect()
+    } else {
+        chart_config.series.iter().map(|series| series.column.to_string()).collect()
+    };
+
+    let values = columns.iter()
+        .map(|column| column_values(&result, column).ok_or_else(|| format!("unknown column '{column}'")))
+        .collect::<Result<Vec<Vec<Option<f64>>>, String>>()?;
+
+    if columns.len() < 2 {
+        return Err("a correlation heatmap needs at least two numeric columns".into());
+    }
+
+    let coefficients = values.iter()
+        .map(|row| values.iter().map(|column| pearson(&complete_pairs(row, column))).collect())
+        .collect();
+
+    Ok(CorrelationMatrix { columns, coefficients })
+}
+
+/// Sorts the result by quarter and keeps the quarters within the chart's date range.
+fn quarters_in_range(result: &DataFrame, chart_config: &ChartConfig) -> Result<DataFrame, Box<dyn Error>> {
+    let result = result.sort(["quarter"], SortMultipleOptions::new())?;
+
+    let Some((start, end)) = chart_config.date_range else {
+        return Ok(result);
+    };
+
+    let in_range: BooleanChunked = result.column("quarter")?.str()?
+        .into_iter()
+        .map(|quarter| quarter.is_some_and(|quarter| (start..=end).contains(&quarter)))
+        .collect();
+
+    Ok(result.filter(&in_range)?)
+}
 
-    let mut chart = ChartBuilder::on(&root)
-        .caption(graph_configuration.caption, ("sans-serif", 50))
-        .margin(10)
-        .x_label_area_size(50)
-        .y_label_area_size(70)
-        .build_cartesian_2d(0..quarters.len(), y_range).unwrap();
+fn write_chart(data: &ChartData, chart_config: &ChartConfig, format: ChartFormat, file_path: &str) -> Result<(), Box<dyn Error>> {
+    let pixel_size = chart_config.output.pixel_size();
+
+    match format {
+        ChartFormat::Png => draw_chart(&BitMapBackend::new(file_path, pixel_size).into_drawing_area(), data, chart_config),
+        ChartFormat::Svg => draw_chart(&SVGBackend::new(file_path, pixel_size).into_drawing_area(), data, chart_config),
+    }
+}
+
+/// Renders the chart as an SVG document in memory, for embedding in reports.
+pub(super) fn chart_svg(result: &DataFrame, chart_config: &ChartConfig) -> Result<String, Box<dyn Error>> {
+    let data = chart_data(result, chart_config)?;
+    let mut svg = String::new();
+
+    draw_chart(&SVGBackend::with_string(&mut svg, chart_config.output.pixel_size()).into_drawing_area(), &data, chart_config)?;
+
+    Ok(svg)
+}
+
+/// Draws the chart as vector graphics on a PDF page as large as the chart.
+fn pdf_page(data: &ChartData, chart_config: &ChartConfig) -> Result<PdfPage, Box<dyn Error>> {
+    let mut page = PdfPage::new(chart_config.output.size_inches, chart_config.output.pixel_size());
+
+    draw_chart(&PdfBackend::new(&mut page).into_drawing_area(), data, chart_config)?;
+
+    Ok(page)
+}
+
+fn draw_chart<DB: DrawingBackend>(root: &DrawingArea<DB, Shift>, data: &ChartData, chart_config: &ChartConfig) -> Result<(), Box<dyn Error>>
+where DB::ErrorType: 'static {
+    root.fill(&WHITE)?;
+
+    match (chart_config.kind, data) {
+        (ChartKind::Line, ChartData::Series(data)) => draw_line_chart(root, data, chart_config)?,
+        (ChartKind::AnnualBar, ChartData::Series(data)) => draw_annual_bar_chart(root, data, chart_config)?,
+        (ChartKind::Scatter, ChartData::Series(data)) => draw_scatter_chart(root, data, chart_config)?,
+        (ChartKind::CorrelationHeatmap, ChartData::Correlations(matrix)) => draw_correlation_heatmap(root, matrix, chart_config)?,
+        _ => return Err(format!("no {:?} data for chart {}", chart_config.kind, chart_config.file_name).into()),
+    }
+
+    root.present()?;
+    Ok(())
+}
+
+fn draw_line_chart<DB: DrawingBackend>(root: &DrawingArea<DB, Shift>, data: &SeriesData, chart_config: &ChartConfig) -> Result<(), Box<dyn Error>>
+where DB::ErrorType: 'static {
+    let primary_range = value_range(data, chart_config, ChartAxis::Primary);
+    let secondary_range = value_range(data, chart_config, ChartAxis::Secondary);
+    let has_secondary_axis = secondary_range.is_some();
+    let primary_range = primary_range.or(secondary_range.clone()).ok_or("the chart has no series")?;
+    let secondary_range = secondary_range.unwrap_or(primary_range.clone());
+
+    let scale = scale(chart_config);
+    let scaled = |size: f64| (size * scale).round().max(1.0) as u32;
+
+    let quarters = &data.quarters;
+    let mut chart = ChartBuilder::on(root)
+        .caption(chart_config.title, ("sans-serif", scaled(50.0)))
+        .margin(scaled(10.0))
+        .x_label_area_size(scaled(50.0))
+        .y_label_area_size(scaled(90.0))
+        .right_y_label_area_size(if has_secondary_axis { scaled(90.0) } else { 0 })
+        .build_cartesian_2d(0..quarters.len(), primary_range.clone())?
+        .set_secondary_coord(0..quarters.len(), secondary_range);
 
     chart.configure_mesh()
         .x_labels(quarters.len())
-        .x_label_formatter(&|idx| quarters.get(*idx).unwrap_or(&"").to_string())
+        .x_label_formatter(&|idx| quarters.get(*idx).cloned().unwrap_or_default())
         .x_desc("Quarter")
-        .y_desc("Value")
-        .draw().unwrap();
-
-    // Helper function to draw a series
-    let mut draw_series = |name: &str, data: &Vec<f64>, color: RGBColor| -> Result<(), Box<dyn std::error::Error>> {
-        chart.draw_series(LineSeries::new(
-            (0..).zip(data.iter()).map(|(i, y)| (i, *y)),
-            ShapeStyle::from(&color).stroke_width(2),
-        ))?
-            .label(name)
-            .legend(move |(x, y)| {
-                PathElement::new(
-                    [(x, y), (x + 20, y)],
-                    ShapeStyle::from(&color).stroke_width(3),
-                )
-            });
-        Ok(())
-    };
+        .y_desc(&chart_config.y_labels.0)
+        .label_style(("sans-serif", scaled(12.0)))
+        .axis_desc_style(("sans-serif", scaled(30.0)))
+        .draw()?;
+
+    if has_secondary_axis {
+        chart.configure_secondary_axes()
+            .y_desc(chart_config.y_labels.1.as_deref().unwrap_or_default())
+            .label_style(("sans-serif", scaled(12.0)))
+            .axis_desc_style(("sans-serif", scaled(30.0)))
+            .draw()?;
+    }
 
-    // Draw each line
-    draw_series(graph_configuration.col_name_sp500_label, &sp_500, RGBColor(0, 102, 0)).unwrap();
-    draw_series(graph_configuration.col_name_1_label, &col_1, RGBColor(0, 0, 204)).unwrap();
-    draw_series(graph_configuration.col_name_2_label, &col_2, RGBColor(204, 0, 0)).unwrap();
+    // Shade recession quarters behind the lines
+    let legend_size = scaled(20.0) as i32;
+    for (label, color, flags) in &data.recession_flags {
+        let color = *color;
+        let recession_quarters = flags.iter()
+            .enumerate()
+            .filter(|(_, flag)| **flag)
+            .map(|(i, _)| i);
+
+        chart.draw_series(recession_quarters.map(|i| {
+            Rectangle::new([(i, primary_range.start), (i + 1, primary_range.end)], color.mix(0.12).filled())
+        }))?
+            .label(*label)
+            .legend(move |(x, y)| Rectangle::new([(x, y - legend_size / 2), (x + legend_size, y + legend_size / 2)], color.mix(0.3).filled()));
+    }
+
+    for (series, values) in chart_config.series.iter().zip(&data.values) {
+        draw_line(&mut chart, series, values, has_secondary_axis, scale)?;
+    }
 
     // Draw the legend
     chart.configure_series_labels()
-        .background_style(&WHITE.mix(0.8))
-        .border_style(&BLACK)
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .position(SeriesLabelPosition::UpperLeft)
+        .label_font(("sans-serif", scaled(40.0)))
+        .draw()?;
+
+    Ok(())
+}
+
+
+/// Groups the bars of every series by calendar year. Years missing a quarter are left out.
+fn draw_annual_bar_chart<DB: DrawingBackend>(root: &DrawingArea<DB, Shift>, data: &SeriesData, chart_config: &ChartConfig) -> Result<(), Box<dyn Error>>
+where DB::ErrorType: 'static {
+    let (years, averages) = annual_averages(data);
+    if years.is_empty() {
+        return Err("no calendar year has a value in every quarter".into());
+    }
+
+    let scale = scale(chart_config);
+    let scaled = |size: f64| (size * scale).round().max(1.0) as u32;
+
+    // One slot per series and a free one between the years
+    let slots_per_year = chart_config.series.len() + 1;
+    let (y_min, y_max) = averages.iter()
+        .flatten()
+        .fold((0.0_f64, 0.0_f64), |(min, max), value| (min.min(*value), max.max(*value)));
+    let padding = (y_max - y_min).max(f64::EPSILON) * 0.1;
+
+    let mut chart = ChartBuilder::on(root)
+        .caption(chart_config.title, ("sans-serif", scaled(50.0)))
+        .margin(scaled(10.0))
+        .x_label_area_size(scaled(50.0))
+        .y_label_area_size(scaled(90.0))
+        .build_cartesian_2d(0..years.len() * slots_per_year, (y_min - padding)..(y_max + padding))?;
+
+    chart.configure_mesh()
+        .disable_x_mesh()
+        .x_labels(years.len() * slots_per_year)
+        .x_label_formatter(&|slot| if slot % slots_per_year == 0 { years.get(slot / slots_per_year).cloned().unwrap_or_default() } else { String::new() })
+        .x_desc("Year")
+        .y_desc(&chart_config.y_labels.0)
+        .label_style(("sans-serif", scaled(12.0)))
+        .axis_desc_style(("sans-serif", scaled(30.0)))
+        .draw()?;
+
+    let legend_size = scaled(20.0) as i32;
+    for (index, (series, averages)) in chart_config.series.iter().zip(&averages).enumerate() {
+        let (red, green, blue) = series.color;
+        let color = RGBColor(red, green, blue);
+
+        chart.draw_series(averages.iter().enumerate().map(|(year, average)| {
+            let slot = year * slots_per_year + index;
+            Rectangle::new([(slot, 0.0), (slot + 1, *average)], color.filled())
+        }))?
+            .label(&series.label)
+            .legend(move |(x, y)| Rectangle::new([(x, y - legend_size / 2), (x + legend_size, y + legend_size / 2)], color.filled()));
+    }
+
+    chart.configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
         .position(SeriesLabelPosition::UpperLeft)
-        .label_font(("sans-serif", 40)) // ← Increase font size here
-        .draw().unwrap();
+        .label_font(("sans-serif", scaled(40.0)))
+        .draw()?;
+
+    Ok(())
+}
 
-    info!("Plot saved as {file_name}.png");
+/// Plots the second series against the first with their least-squares line.
+fn draw_scatter_chart<DB: DrawingBackend>(root: &DrawingArea<DB, Shift>, data: &SeriesData, chart_config: &ChartConfig) -> Result<(), Box<dyn Error>>
+where DB::ErrorType: 'static {
+    let [(x_series, x_values), (y_series, y_values)] = [0, 1].map(|index| (&chart_config.series[index], &data.values[index]));
+    let fit = ols(y_values, &x_values.iter().map(|x| vec![*x]).collect::<Vec<_>>(), None)
+        .ok_or("too few quarters to fit a regression line")?;
+    let (intercept, slope) = (fit.coefficients[0], fit.coefficients[1]);
+
+    let scale = scale(chart_config);
+    let scaled = |size: f64| (size * scale).round().max(1.0) as u32;
+    let padded_range = |values: &[f64]| {
+        let (min, max) = values.iter().fold((f64::MAX, f64::MIN), |(min, max), value| (min.min(*value), max.max(*value)));
+        let padding = (max - min).max(f64::EPSILON) * 0.1;
+        (min - padding)..(max + padding)
+    };
+    let x_range = padded_range(x_values);
+
+    let mut chart = ChartBuilder::on(root)
+        .caption(chart_config.title, ("sans-serif", scaled(50.0)))
+        .margin(scaled(10.0))
+        .x_label_area_size(scaled(70.0))
+        .y_label_area_size(scaled(90.0))
+        .build_cartesian_2d(x_range.clone(), padded_range(y_values))?;
+
+    chart.configure_mesh()
+        .x_desc(&x_series.label)
+        .y_desc(&chart_config.y_labels.0)
+        .label_style(("sans-serif", scaled(12.0)))
+        .axis_desc_style(("sans-serif", scaled(30.0)))
+        .draw()?;
+
+    let (red, green, blue) = y_series.color;
+    let color = RGBColor(red, green, blue);
+    let radius = scaled(6.0);
+    chart.draw_series(x_values.iter().zip(y_values.iter()).map(|(x, y)| Circle::new((*x, *y), radius, color.mix(0.6).filled())))?
+        .label(&y_series.label)
+        .legend(move |(x, y)| Circle::new((x + 10, y), radius, color.mix(0.6).filled()));
+
+    let stroke_width = scaled(3.0);
+    let legend_width = scaled(20.0) as i32;
+    chart.draw_series(LineSeries::new([x_range.start, x_range.end].map(|x| (x, intercept + slope * x)), BLACK.stroke_width(stroke_width)))?
+        .label(format!("y = {intercept:.2} + {slope:.2} x, R² = {:.2}, {} quarters", fit.r_squared, fit.observations))
+        .legend(move |(x, y)| PathElement::new([(x, y), (x + legend_width, y)], BLACK.stroke_width(stroke_width)));
+
+    chart.configure_series_labels()
+        .background_style(WHITE.mix(0.8))
+        .border_style(BLACK)
+        .position(SeriesLabelPosition::UpperLeft)
+        .label_font(("sans-serif", scaled(40.0)))
+        .draw()?;
+
+    Ok(())
+}
+
+/// Colors every pair of columns from blue (-1) over white (0) to red (+1); grey where the
+/// correlation is undefined.
+fn draw_correlation_heatmap<DB: DrawingBackend>(root: &DrawingArea<DB, Shift>, matrix: &CorrelationMatrix, chart_config: &ChartConfig) -> Result<(), Box<dyn Error>>
+where DB::ErrorType: 'static {
+    let scale = scale(chart_config);
+    let scaled = |size: f64| (size * scale).round().max(1.0) as u32;
+    let columns = &matrix.columns;
+    let label_size = scaled(14.0);
+    let label_area_size = columns.iter().map(|column| column.len()).max().unwrap_or(0) as u32 * label_size * 6 / 10 + scaled(20.0);
+
+    let mut chart = ChartBuilder::on(root)
+        .caption(format!("{} (blue = -1, red = +1)", chart_config.title), ("sans-serif", scaled(50.0)))
+        .margin(scaled(10.0))
+        .x_label_area_size(label_area_size)
+        .y_label_area_size(label_area_size)
+        .build_cartesian_2d((0..columns.len() - 1).into_segmented(), (0..columns.len() - 1).into_segmented())?;
+
+    let column_label = |value: &SegmentValue<usize>| match value {
+        SegmentValue::CenterOf(index) => columns.get(*index).cloned().unwrap_or_default(),
+        _ => String::new(),
+    };
+
+    chart.configure_mesh()
+        .disable_mesh()
+        .x_labels(columns.len())
+        .y_labels(columns.len())
+        .x_label_formatter(&column_label)
+        .y_label_formatter(&column_label)
+        .x_label_style(("sans-serif", label_size).into_font().transform(FontTransform::Rotate90))
+        .y_label_style(("sans-serif", label_size))
+        .draw()?;
+
+    chart.draw_series(matrix.coefficients.iter().enumerate().flat_map(|(row, coefficients)| {
+        coefficients.iter().enumerate().map(move |(column, coefficient)| {
+            Rectangle::new([(SegmentValue::Exact(column), SegmentValue::Exact(row)),
+                            (SegmentValue::Exact(column + 1), SegmentValue::Exact(row + 1))],
+                           correlation_color(*coefficient).filled())
+        })
+    }))?;
+
+    Ok(())
+}
+
+fn correlation_color(coefficient: Option<f64>) -> RGBColor {
+    let Some(coefficient) = coefficient else {
+        return RGBColor(200, 200, 200);
+    };
+
+    let fade = |strength: f64| (255.0 * (1.0 - strength.clamp(0.0, 1.0))).round() as u8;
+    if coefficient >= 0.0 {
+        RGBColor(255, fade(coefficient), fade(coefficient))
+    } else {
+        RGBColor(fade(-coefficient), fade(-coefficient), 255)
+    }
+}
+
+/// The average of every series per calendar year, for the years with a value in all four quarters.
+fn annual_averages(data: &SeriesData) -> (Vec<String>, Vec<Vec<f64>>) {
+    let mut rows_per_year: Vec<(String, Vec<usize>)> = vec![];
+    for (row, quarter) in data.quarters.iter().enumerate() {
+        let year = quarter.split('-').next().unwrap_or_default();
+        match rows_per_year.last_mut() {
+            Some((last_year, rows)) if last_year == year => rows.push(row),
+            _ => rows_per_year.push((year.to_string(), vec![row])),
+        }
+    }
+    rows_per_year.retain(|(_, rows)| rows.len() == 4);
+
+    let averages = data.values.iter()
+        .map(|values| rows_per_year.iter().map(|(_, rows)| rows.iter().map(|row| values[*row]).sum::<f64>() / 4.0).collect())
+        .collect();
+
+    (rows_per_year.into_iter().map(|(year, _)| year).collect(), averages)
+}
+
+/// Sizes are given for 200 DPI and scaled to the chart's DPI.
+fn scale(chart_config: &ChartConfig) -> f64 {
+    chart_config.output.dpi as f64 / 200.0
+}
+
+/// Draws a series against its axis. On charts with two axes its legend entry names the axis.
+fn draw_line<DB: DrawingBackend>(chart: &mut Chart<'_, DB>,
+                                 series: &ChartSeriesConfig,
+                                 values: &[f64],
+                                 has_secondary_axis: bool,
+                                 scale: f64) -> Result<(), Box<dyn Error>>
+where DB::ErrorType: 'static {
+    let (red, green, blue) = series.color;
+    let color = RGBColor(red, green, blue);
+    let stroke_width = (2.0 * scale).round().max(1.0) as u32;
+    let style = ShapeStyle::from(&color).stroke_width(stroke_width);
+    let (dash, gap) = ((15.0 * scale).round() as u32, (10.0 * scale).round() as u32);
+    let legend_width = (20.0 * scale).round() as i32;
+    let points = values.iter().enumerate().map(|(i, y)| (i, *y));
+
+    let annotation = match (series.axis, series.line_style) {
+        (ChartAxis::Primary, LineStyle::Solid) => chart.draw_series(LineSeries::new(points, style))?,
+        (ChartAxis::Primary, LineStyle::Dashed) => chart.draw_series(DashedLineSeries::new(points, dash, gap, style))?,
+        (ChartAxis::Secondary, LineStyle::Solid) => chart.draw_secondary_series(LineSeries::new(points, style))?,
+        (ChartAxis::Secondary, LineStyle::Dashed) => chart.draw_secondary_series(DashedLineSeries::new(points, dash, gap, style))?,
+    };
+
+    annotation
+        .label(legend_label(series, has_secondary_axis))
+        .legend(move |(x, y)| PathElement::new([(x, y), (x + legend_width, y)], ShapeStyle::from(&color).stroke_width(stroke_width + 1)));
+
+    Ok(())
+}
+
+pub(super) fn legend_label(series: &ChartSeriesConfig, has_secondary_axis: bool) -> String {
+    match (has_secondary_axis, series.axis) {
+        (false, _) => series.label.to_string(),
+        (true, ChartAxis::Primary) => format!("{} (left axis)", series.label),
+        (true, ChartAxis::Secondary) => format!("{} (right axis)", series.label),
+    }
+}
+
+/// The range of the series drawn on `axis`, padded by 10% on both ends.
+pub(super) fn value_range(data: &SeriesData, chart_config: &ChartConfig, axis: ChartAxis) -> Option<Range<f64>> {
+    let (y_min, y_max) = chart_config.series.iter()
+        .zip(&data.values)
+        .filter(|(series, _)| series.axis == axis)
+        .flat_map(|(_, values)| values.iter())
+        .fold(None, |range: Option<(f64, f64)>, &value| match range {
+            Some((min, max)) => Some((min.min(value), max.max(value))),
+            None => Some((value, value)),
+        })?;
+
+    let padding = (y_max - y_min) * 0.1;
+    Some((y_min - padding)..(y_max + padding))
+}
+
+
+#[cfg(test)]
+mod tests {
+    use tempfile::tempdir;
+
+    use crate::datasets_config::datasets_config::ChartOutputConfig;
+
+    use super::*;
+
+    fn chart_config(date_range: Option<(&'static str, &'static str)>) -> ChartConfig {
+        ChartConfig::new("growth",
+                         ChartKind::Line,
+                         "GDP growth",
+                         ("GDP growth in %", Some("S&P 500 in index points")),
+                         vec![ChartSeriesConfig::new("us_gdp_yoy_perc", "US", (204, 0, 0), LineStyle::Solid, ChartAxis::Primary),
+                              ChartSeriesConfig::new("sp500_usd", "S&P 500", (0, 102, 0), LineStyle::Dashed, ChartAxis::Secondary)],
+                         date_range,
+                         ChartOutputConfig::new(&[ChartFormat::Png, ChartFormat::Svg], (4.0, 3.0), 100))
+    }
+
+    fn result() -> DataFrame {
+        df![
+            "quarter" => &["2024-Q3", "2024-Q1", "2024-Q2", "2024-Q4"],
+            "us_gdp_yoy_perc" => &[Some(2.5), None, Some(2.0), Some(3.0)],
+            "sp500_usd" => &[5700.0, 5200.0, 5400.0, 5900.0],
+            "us_recession_nber" => &[0, 0, 1, 0]
+        ].unwrap()
+    }
+
+    fn series_data_of(config: &ChartConfig) -> SeriesData {
+        match chart_data(&result(), config).unwrap() {
+            ChartData::Series(data) => data,
+            ChartData::Correlations(_) => panic!("expected series data"),
+        }
+    }
+
+    #[test]
+    fn test_chart_data_keeps_quarters_with_every_series_in_range() {
+        let data = series_data_of(&chart_config(Some(("2024-Q1", "2024-Q3"))));
+
+        assert_eq!(data.quarters, vec!["2024-Q2", "2024-Q3"]);
+        assert_eq!(data.values, vec![vec![2.0, 2.5], vec![5400.0, 5700.0]]);
+        assert_eq!(data.recession_flags.len(), 1);
+        assert_eq!(data.recession_flags[0].2, vec![true, false]);
+
+        let config = chart_config(None);
+        let data = series_data_of(&config);
+        assert_eq!(value_range(&data, &config, ChartAxis::Secondary), Some(5350.0..5950.0));
+    }
+
+    #[test]
+    fn test_legend_label_names_the_axis_on_dual_axis_charts() {
+        let config = chart_config(None);
+
+        assert_eq!(legend_label(&config.series[0], true), "US (left axis)");
+        assert_eq!(legend_label(&config.series[1], true), "S&P 500 (right axis)");
+        assert_eq!(legend_label(&config.series[1], false), "S&P 500");
+    }
+
+    #[test]
+    fn test_chart_data_fails_on_missing_column() {
+        let mut config = chart_config(None);
+        config.series[0].column = "eu_gdp_yoy_perc";
+
+        assert!(chart_data(&result(), &config).is_err());
+    }
+
+    #[test]
+    fn test_write_chart_as_png_and_svg() {
+        let directory = tempdir().unwrap();
+        let config = chart_config(None);
+        let data = chart_data(&result(), &config).unwrap();
+
+        for format in config.output.formats {
+            let file_path = directory.path().join(format!("growth.{}", format.extension()));
+            write_chart(&data, &config, *format, file_path.to_str().unwrap()).unwrap();
+        }
+
+        let svg = fs::read_to_string(directory.path().join("growth.svg")).unwrap();
+        assert!(svg.contains(r#"width="400" height="300""#));
+        assert!(svg.contains("S&amp;P 500 (right axis)"));
+        assert!(fs::metadata(directory.path().join("growth.png")).unwrap().len() > 0);
+    }
+
+    #[test]
+    fn test_pdf_page_draws_the_chart_as_vectors() {
+        let config = chart_config(None);
+        let page = pdf_page(&chart_data(&result(), &config).unwrap(), &config).unwrap();
+
+        assert_eq!((page.width_inches, page.height_inches, page.pixel_size), (4.0, 3.0, (400, 300)));
+        assert!(page.content.contains("(GDP growth) Tj"));
+        assert!(page.content.contains(" l S Q"));
+    }
+
+    #[test]
+    fn test_annual_averages_skip_incomplete_years() {
+        let data = SeriesData {
+            quarters: ["2023-Q2", "2023-Q3", "2023-Q4", "2024-Q1", "2024-Q2", "2024-Q3", "2024-Q4"].map(String::from).to_vec(),
+            values: vec![vec![9.0, 9.0, 9.0, 1.0, 2.0, 3.0, 4.0]],
+            recession_flags: vec![],
+        };
+
+        assert_eq!(annual_averages(&data), (vec!["2024".to_string()], vec![vec![2.5]]));
+    }
+
+    #[test]
+    fn test_correlation_matrix_of_all_numeric_columns() {
+        let mut config = chart_config(None);
+        config.kind = ChartKind::CorrelationHeatmap;
+        config.series = vec![];
+
+        let ChartData::Correlations(matrix) = chart_data(&result(), &config).unwrap() else {
+            panic!("expected a correlation matrix");
+        };
+
+        assert_eq!(matrix.columns, vec!["us_gdp_yoy_perc", "sp500_usd"]);
+        assert_eq!(matrix.coefficients[0][0], Some(1.0));
+        assert!(matrix.coefficients[0][1].unwrap() > 0.99);
+        assert_eq!(matrix.coefficients[0][1], matrix.coefficients[1][0]);
+        assert_eq!(correlation_color(Some(1.0)), RGBColor(255, 0, 0));
+        assert_eq!(correlation_color(Some(-0.5)), RGBColor(128, 128, 255));
+    }
+
+    #[test]
+    fn test_write_every_chart_kind() {
+        let directory = tempdir().unwrap();
+        let result = df![
+            "quarter" => &["2023-Q1", "2023-Q2", "2023-Q3", "2023-Q4", "2024-Q1", "2024-Q2", "2024-Q3", "2024-Q4"],
+            "us_gdp_yoy_perc" => &[1.0, 1.5, 2.0, 2.5, 2.0, 2.0, 2.5, 3.0],
+            "sp500_usd" => &[4100.0, 4400.0, 4300.0, 4700.0, 5200.0, 5400.0, 5700.0, 5900.0]
+        ].unwrap();
+
+        for kind in [ChartKind::Line, ChartKind::AnnualBar, ChartKind::Scatter, ChartKind::CorrelationHeatmap] {
+            let mut config = chart_config(None);
+            config.kind = kind;
+            let file_path = directory.path().join(format!("{kind:?}.svg"));
+
+            write_chart(&chart_data(&result, &config).unwrap(), &config, ChartFormat::Svg, file_path.to_str().unwrap()).unwrap();
+            assert!(fs::read_to_string(file_path).unwrap().contains("GDP growth"), "{kind:?}");
+        }
+    }
 }
@@ -0,0 +1,418 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fmt::Write;
+use std::fs;
+use std::ops::Range;
+
+use log::{info, warn};
+use plotters::style::RGBColor;
+use polars::prelude::*;
+
+use crate::datasets_config::datasets_config::{ChartAxis, ChartConfig, ChartKind, DifferentialConfig, LineStyle, NumberFormat, Scale};
+use crate::loader::graph::{chart_svg, legend_label, series_data, value_range, SeriesData};
+use crate::loader::tables::format_value;
+use crate::model::data_model::{ColumnMetadata, DataSource};
+use crate::transformer::stats::column_values;
+
+pub const HTML_DIRECTORY: &str = "outputs/html";
+
+const CHART_WIDTH: f64 = 960.0;
+const CHART_HEIGHT: f64 = 480.0;
+
+const STYLE: &str = "body { font-family: sans-serif; margin: 2em auto; max-width: 1000px; color: #222; }
+table { border-collapse: collapse; margin-bottom: 2em; }
+th, td { border-bottom: 1px solid #ddd; padding: 0.3em 0.8em; text-align: left; }
+td.number { text-align: right; font-variant-numeric: tabular-nums; }
+figure { margin: 0 0 3em 0; }
+figure svg { width: 100%; height: auto; }
+.hover { fill: transparent; }
+.hover:hover { fill: rgba(0, 0, 0, 0.08); }
+.legend { list-style: none; padding: 0; }
+.legend li { display: inline-block; margin-right: 1.5em; }
+.legend span { display: inline-block; width: 2em; margin-right: 0.4em; vertical-align: middle; }";
+
+/// Writes the report to `outputs/html/<file name>.html` as a single file that opens offline:
+/// the styles are inline, charts are inline SVG and their tooltips SVG titles, so it needs no
+/// scripts or network access.
+pub fn generate_report(result: &DataFrame,
+                       column_metadata: &[ColumnMetadata],
+                       chart_configs: &[ChartConfig],
+                       differential_configs: &[DifferentialConfig],
+                       data_sources: &[DataSource],
+                       run_id: &str,
+                       file_name: &str) {
+    let file_path = format!("{HTML_DIRECTORY}/{file_name}.html");
+    let html = report_html(result, column_metadata, chart_configs, differential_configs, data_sources, run_id);
+
+    match fs::create_dir_all(HTML_DIRECTORY).and_then(|()| fs::write(&file_path, html)) {
+        Ok(()) => info!("report saved as {file_path}"),
+        Err(error) => warn!("could not write {file_path}: {error}"),
+    }
+}
+
+fn report_html(result: &DataFrame,
+               column_metadata: &[ColumnMetadata],
+               chart_configs: &[ChartConfig],
+               differential_configs: &[DifferentialConfig],
+               data_sources: &[DataSource],
+               run_id: &str) -> String {
+    let mut html = String::new();
+
+    writeln!(html, "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">").unwrap();
+    writeln!(html, "<title>EU vs US economy and S&amp;P 500, run {}</title>\n<style>\n{STYLE}\n</style>\n</head>\n<body>", escape(run_id)).unwrap();
+    writeln!(html, "<h1>EU vs US economy and S&amp;P 500</h1>\n<p>Run {}.</p>", escape(run_id)).unwrap();
+
+    writeln!(html, "<h2>Latest values</h2>").unwrap();
+    html.push_str(&table(&["Column", "Unit", "Quarter", "Value", "YoY change"], &latest_value_rows(result, column_metadata)));
+
+    writeln!(html, "<h2>EU–US differentials</h2>").unwrap();
+    html.push_str(&table(&["Series", "Quarter", "EU", "US", "EU – US", "EU – US a year earlier"],
+                         &differential_rows(result, differential_configs)));
+
+    writeln!(html, "<h2>Charts</h2>").unwrap();
+    for chart_config in chart_configs {
+        match chart_figure(result, chart_config) {
+            Ok(figure) => html.push_str(&figure),
+            Err(error) => warn!("leaving chart {} out of the report: {error}", chart_config.file_name),
+        }
+    }
+
+    writeln!(html, "<h2>Data sources</h2>").unwrap();
+    let source_rows: Vec<Vec<Cell>> = data_sources.iter()
+        .map(|data_source| vec![
+            Cell::Text(data_source.dataset.clone()),
+            Cell::Text(data_source.source.clone()),
+            Cell::Text(data_source.identifier.clone()),
+            Cell::Text(data_source.fetched_at.format("%Y-%m-%d %H:%M:%S %:z").to_string()),
+        ])
+        .collect();
+    html.push_str(&table(&["Dataset", "Source", "Series or file", "Fetched at"], &source_rows));
+
+    writeln!(html, "</body>\n</html>").unwrap();
+    html
+}
+
+enum Cell {
+    Text(String),
+    Number(String),
+}
+
+fn table(headers: &[&str], rows: &[Vec<Cell>]) -> String {
+    let mut html = String::from("<table>\n<tr>");
+    for header in headers {
+        write!(html, "<th>{}</th>", escape(header)).unwrap();
+    }
+    html.push_str("</tr>\n");
+
+    for row in rows {
+        html.push_str("<tr>");
+        for cell in row {
+            match cell {
+                Cell::Text(text) => write!(html, "<td>{}</td>", escape(text)).unwrap(),
+                Cell::Number(number) => write!(html, "<td class=\"number\">{}</td>", escape(number)).unwrap(),
+            }
+        }
+        html.push_str("</tr>\n");
+    }
+
+    html.push_str("</table>\n");
+    html
+}
+
+/// The latest value of every numeric column with its change from the same quarter a year
+/// earlier: in percentage points for percentages, in percent otherwise.
+fn latest_value_rows(result: &DataFrame, column_metadata: &[ColumnMetadata]) -> Vec<Vec<Cell>> {
+    column_metadata.iter()
+        .filter(|metadata| result.column(&metadata.column_name).is_ok_and(|column| column.dtype() == &DataType::Float64))
+        .filter_map(|metadata| {
+            let (quarter, value, year_earlier) = latest_with_year_earlier(result, &metadata.column_name)?;
+            let is_percentage = metadata.unit.starts_with("percent");
+
+            let change = year_earlier.and_then(|year_earlier| match is_percentage {
+                true => Some(format!("{:+.2} pp", value - year_earlier)),
+                false if year_earlier != 0.0 => Some(format!("{:+.2} %", (value / year_earlier - 1.0) * 100.0)),
+                false => None,
+            });
+
+            let unit = match metadata.scale {
+                Scale::Units => metadata.unit.clone(),
+                scale => format!("{} {}", metadata.unit, scale.name()),
+            };
+
+            Some(vec![Cell::Text(metadata.column_name.clone()),
+                      Cell::Text(unit),
+                      Cell::Text(quarter),
+                      Cell::Number(format_number(value)),
+                      Cell::Number(change.unwrap_or_default())])
+        })
+        .collect()
+}
+
+/// The EU minus the US value in the latest quarter both have, and in the same quarter a year earlier.
+fn differential_rows(result: &DataFrame, differential_configs: &[DifferentialConfig]) -> Vec<Vec<Cell>> {
+    let Some(quarters) = quarter_values(result) else {
+        return vec![];
+    };
+
+    differential_configs.iter()
+        .filter_map(|config| {
+            let (eu_values, us_values) = (column_values(result, config.eu_column)?, column_values(result, config.us_column)?);
+
+            let differences: HashMap<&str, (f64, f64)> = quarters.iter()
+                .zip(eu_values.iter().zip(&us_values))
+                .filter_map(|(quarter, (eu, us))| Some((quarter.as_deref()?, ((*eu)?, (*us)?))))
+                .collect();
+
+            let quarter = differences.keys().max()?;
+            let (eu, us) = differences[quarter];
+            let year_earlier = quarter_a_year_earlier(quarter)
+                .and_then(|year_earlier| differences.get(year_earlier.as_str()))
+                .map(|(eu, us)| format_number(eu - us));
+
+            Some(vec![Cell::Text(config.label.to_string()),
+                      Cell::Text(quarter.to_string()),
+                      Cell::Number(format_number(eu)),
+                      Cell::Number(format_number(us)),
+                      Cell::Number(format_number(eu - us)),
+                      Cell::Number(year_earlier.unwrap_or_default())])
+        })
+        .collect()
+}
+
+/// The latest quarter with a value in `column_name`, the value and the value a year earlier.
+fn latest_with_year_earlier(result: &DataFrame, column_name: &str) -> Option<(String, f64, Option<f64>)> {
+    let quarters = quarter_values(result)?;
+
+    let values: HashMap<String, f64> = quarters.into_iter()
+        .zip(column_values(result, column_name)?)
+        .filter_map(|(quarter, value)| Some((quarter?, value?)))
+        .collect();
+
+    let (quarter, value) = values.iter().max_by(|(left, _), (right, _)| left.cmp(right))?;
+    let year_earlier = quarter_a_year_earlier(quarter).and_then(|year_earlier| values.get(&year_earlier).copied());
+
+    Some((quarter.clone(), *value, year_earlier))
+}
+
+fn quarter_values(result: &DataFrame) -> Option<Vec<Option<String>>> {
+    Some(result.column("quarter").ok()?.str().ok()?.into_iter().map(|quarter| quarter.map(String::from)).collect())
+}
+
+fn quarter_a_year_earlier(quarter: &str) -> Option<String> {
+    let (year, quarter) = quarter.split_once("-Q")?;
+    Some(format!("{}-Q{quarter}", year.parse::<i32>().ok()? - 1))
+}
+
+/// Line charts are drawn as interactive SVG with a tooltip per quarter, the other kinds are
+/// embedded as rendered for `outputs/graph`.
+fn chart_figure(result: &DataFrame, chart_config: &ChartConfig) -> Result<String, Box<dyn Error>> {
+    let mut html = format!("<figure>\n<h3>{}</h3>\n", escape(chart_config.title));
+
+    if chart_config.kind == ChartKind::Line {
+        let data = series_data(result, chart_config)?;
+        html.push_str(&line_chart_svg(&data, chart_config)?);
+        html.push_str(&legend(&data, chart_config));
+    } else {
+        html.push_str(&chart_svg(result, chart_config)?);
+    }
+
+    html.push_str("\n</figure>\n");
+    Ok(html)
+}
+
+fn line_chart_svg(data: &SeriesData, chart_config: &ChartConfig) -> Result<String, Box<dyn Error>> {
+    let primary_range = value_range(data, chart_config, ChartAxis::Primary);
+    let secondary_range = value_range(data, chart_config, ChartAxis::Secondary);
+    let has_secondary_axis = secondary_range.is_some();
+    let primary_range = primary_range.or(secondary_range.clone()).ok_or("the chart has no series")?;
+    let secondary_range = secondary_range.unwrap_or(primary_range.clone());
+
+    let (left, right, top, bottom) = (90.0, if has_secondary_axis { 90.0 } else { 30.0 }, 20.0, 50.0);
+    let (plot_width, plot_height) = (CHART_WIDTH - left - right, CHART_HEIGHT - top - bottom);
+    let quarter_width = plot_width / data.quarters.len() as f64;
+    let x = |index: usize| left + (index as f64 + 0.5) * quarter_width;
+    let y = |value: f64, range: &Range<f64>| top + (range.end - value) / (range.end - range.start).max(f64::EPSILON) * plot_height;
+
+    let mut svg = format!("<svg xmlns=\"http://www.w3.org/2000/svg\" viewBox=\"0 0 {CHART_WIDTH} {CHART_HEIGHT}\" font-size=\"12\">\n");
+
+    // Recession quarters are shaded behind the grid
+    for (_, RGBColor(red, green, blue), flags) in &data.recession_flags {
+        for (index, _) in flags.iter().enumerate().filter(|(_, flag)| **flag) {
+            writeln!(svg, "<rect x=\"{:.1}\" y=\"{top}\" width=\"{quarter_width:.1}\" height=\"{plot_height}\" fill=\"rgb({red},{green},{blue})\" fill-opacity=\"0.12\"/>",
+                     left + index as f64 * quarter_width).unwrap();
+        }
+    }
+
+    for tick in 0..=4 {
+        let tick_y = top + plot_height * tick as f64 / 4.0;
+        let value_at = |range: &Range<f64>| range.end - (range.end - range.start) * tick as f64 / 4.0;
+        writeln!(svg, "<line x1=\"{left}\" x2=\"{}\" y1=\"{tick_y:.1}\" y2=\"{tick_y:.1}\" stroke=\"#ddd\"/>", CHART_WIDTH - right).unwrap();
+        writeln!(svg, "<text x=\"{}\" y=\"{:.1}\" text-anchor=\"end\">{}</text>", left - 6.0, tick_y + 4.0, format_number(value_at(&primary_range))).unwrap();
+        if has_secondary_axis {
+            writeln!(svg, "<text x=\"{}\" y=\"{:.1}\">{}</text>", CHART_WIDTH - right + 6.0, tick_y + 4.0, format_number(value_at(&secondary_range))).unwrap();
+        }
+    }
+
+    let label_step = data.quarters.len().div_ceil(8).max(1);
+    for (index, quarter) in data.quarters.iter().enumerate().step_by(label_step) {
+        writeln!(svg, "<text x=\"{:.1}\" y=\"{}\" text-anchor=\"middle\">{}</text>", x(index), top + plot_height + 20.0, escape(quarter)).unwrap();
+    }
+
+    writeln!(svg, "<text transform=\"translate(16 {:.1}) rotate(-90)\" text-anchor=\"middle\">{}</text>",
+             top + plot_height / 2.0, escape(&chart_config.y_labels.0)).unwrap();
+    if let Some(y_label) = chart_config.y_labels.1.as_deref().filter(|_| has_secondary_axis) {
+        writeln!(svg, "<text transform=\"translate({} {:.1}) rotate(90)\" text-anchor=\"middle\">{}</text>",
+                 CHART_WIDTH - 16.0, top + plot_height / 2.0, escape(y_label)).unwrap();
+    }
+
+    for (series, values) in chart_config.series.iter().zip(&data.values) {
+        let range = match series.axis {
+            ChartAxis::Primary => &primary_range,
+            ChartAxis::Secondary => &secondary_range,
+        };
+        let points: Vec<String> = values.iter().enumerate().map(|(index, value)| format!("{:.1},{:.1}", x(index), y(*value, range))).collect();
+        let (red, green, blue) = series.color;
+        let dash = match series.line_style {
+            LineStyle::Solid => "",
+            LineStyle::Dashed => " stroke-dasharray=\"8 5\"",
+        };
+
+        writeln!(svg, "<polyline fill=\"none\" stroke=\"rgb({red},{green},{blue})\" stroke-width=\"2\"{dash} points=\"{}\"/>", points.join(" ")).unwrap();
+    }
+
+    // A transparent band per quarter on top of everything else holds its tooltip
+    for (index, quarter) in data.quarters.iter().enumerate() {
+        let mut tooltip = vec![quarter.clone()];
+        tooltip.extend(chart_config.series.iter()
+            .zip(&data.values)
+            .map(|(series, values)| format!("{}: {}", series.label, format_number(values[index]))));
+        tooltip.extend(data.recession_flags.iter()
+            .filter(|(_, _, flags)| flags[index])
+            .map(|(label, _, _)| label.to_string()));
+
+        writeln!(svg, "<rect class=\"hover\" x=\"{:.1}\" y=\"{top}\" width=\"{quarter_width:.1}\" height=\"{plot_height}\"><title>{}</title></rect>",
+                 left + index as f64 * quarter_width, escape(&tooltip.join("\n"))).unwrap();
+    }
+
+    svg.push_str("</svg>\n");
+    Ok(svg)
+}
+
+fn legend(data: &SeriesData, chart_config: &ChartConfig) -> String {
+    let has_secondary_axis = chart_config.series.iter().any(|series| series.axis == ChartAxis::Secondary);
+    let mut html = String::from("<ul class=\"legend\">");
+
+    for series in &chart_config.series {
+        let (red, green, blue) = series.color;
+        let border = match series.line_style {
+            LineStyle::Solid => "solid",
+            LineStyle::Dashed => "dashed",
+        };
+        write!(html, "<li><span style=\"border-top: 3px {border} rgb({red},{green},{blue})\"></span>{}</li>",
+               escape(&legend_label(series, has_secondary_axis))).unwrap();
+    }
+
+    for (label, RGBColor(red, green, blue), _) in &data.recession_flags {
+        write!(html, "<li><span style=\"height: 1em; background: rgba({red},{green},{blue},0.3)\"></span>{}</li>", escape(label)).unwrap();
+    }
+
+    html.push_str("</ul>");
+    html
+}
+
+/// Two decimals with thousands separators, e.g. `-12,345.68`.
+fn format_number(value: f64) -> String {
+    format_value(value, NumberFormat::Decimal { decimals: 2, thousands_separator: true })
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+
+    use crate::datasets_config::datasets_config::{get_chart_output_config, ChartSeriesConfig};
+
+    use super::*;
+
+    fn result() -> DataFrame {
+        df![
+            "quarter" => &["2023-Q3", "2023-Q4", "2024-Q3", "2024-Q4"],
+            "eu_inflation_perc" => &[Some(5.0), Some(3.0), Some(2.5), None],
+            "us_inflation_perc" => &[Some(3.5), Some(3.25), Some(3.0), Some(2.75)],
+            "us_gdp_usd_billions" => &[27600.0, 28000.0, 29400.0, 29750.0],
+            "us_recession_nber" => &[0, 0, 1, 0]
+        ].unwrap()
+    }
+
+    fn column_metadata() -> Vec<ColumnMetadata> {
+        vec![ColumnMetadata::new("eu_inflation_perc", "eu_inflation", "percent", Scale::Units),
+             ColumnMetadata::new("us_gdp_usd_billions", "us_gdp", "USD", Scale::Billions),
+             ColumnMetadata::new("us_recession_nber", "us_recession_nber", "flag", Scale::Units)]
+    }
+
+    fn chart_config() -> ChartConfig {
+        ChartConfig::new("inflation",
+                         ChartKind::Line,
+                         "Inflation <EU & US>",
+                         ("Inflation in %", None),
+                         vec![ChartSeriesConfig::new("eu_inflation_perc", "EU", (0, 0, 204), LineStyle::Solid, ChartAxis::Primary),
+                              ChartSeriesConfig::new("us_inflation_perc", "US", (204, 0, 0), LineStyle::Dashed, ChartAxis::Primary)],
+                         None,
+                         get_chart_output_config())
+    }
+
+    fn text_cells(rows: &[Vec<Cell>]) -> Vec<Vec<&str>> {
+        rows.iter()
+            .map(|row| row.iter().map(|cell| match cell {
+                Cell::Text(text) | Cell::Number(text) => text.as_str(),
+            }).collect())
+            .collect()
+    }
+
+    #[test]
+    fn test_format_number() {
+        assert_eq!(format_number(1234567.891), "1,234,567.89");
+        assert_eq!(format_number(-12.5), "-12.50");
+        assert_eq!(format_number(-0.001), "0.00");
+        assert_eq!(format_number(999.999), "1,000.00");
+    }
+
+    #[test]
+    fn test_latest_value_rows_with_yoy_change() {
+        let rows = latest_value_rows(&result(), &column_metadata());
+
+        assert_eq!(text_cells(&rows), vec![
+            vec!["eu_inflation_perc", "percent", "2024-Q3", "2.50", "-2.50 pp"],
+            vec!["us_gdp_usd_billions", "USD billions", "2024-Q4", "29,750.00", "+6.25 %"],
+        ]);
+    }
+
+    #[test]
+    fn test_differential_rows_use_latest_common_quarter() {
+        let rows = differential_rows(&result(), &[DifferentialConfig::new("Inflation in %", "eu_inflation_perc", "us_inflation_perc"),
+                                                  DifferentialConfig::new("GDP", "eu_gdp_usd_billions", "us_gdp_usd_billions")]);
+
+        assert_eq!(text_cells(&rows), vec![vec!["Inflation in %", "2024-Q3", "2.50", "3.00", "-0.50", "1.50"]]);
+    }
+
+    #[test]
+    fn test_report_html_is_self_contained() {
+        let data_sources = [DataSource::new("us_gdp", "fred", "GDP", Local.with_ymd_and_hms(2025, 6, 30, 14, 15, 2).unwrap())];
+
+        let html = report_html(&result(), &column_metadata(), &[chart_config()], &[], &data_sources, "20250630T141502");
+
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("<h3>Inflation &lt;EU &amp; US&gt;</h3>"));
+        assert!(html.contains("<title>2024-Q3\nEU: 2.50\nUS: 3.00\nUS recession (NBER)</title>"));
+        assert!(html.contains("stroke-dasharray"));
+        assert!(html.contains("<td>GDP</td><td>2025-06-30 14:15:02"));
+        assert!(!html.contains("<script") && !html.contains("src=") && !html.contains("href="));
+    }
+}
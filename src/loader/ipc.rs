@@ -0,0 +1,60 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::path::Path;
+
+use polars::prelude::*;
+
+use crate::model::data_model::{ColumnMetadata, DataSource};
+
+pub const IPC_DIRECTORY: &str = "outputs/ipc";
+
+/// Writes the result as an Arrow IPC (Feather v2) file with the column metadata and the data
+/// sources as JSON in the `column_metadata` and `sources` schema metadata.
+pub fn write(path: &str, result: &DataFrame, column_metadata: &[ColumnMetadata], data_sources: &[DataSource]) -> Result<(), Box<dyn Error>> {
+    if let Some(directory) = Path::new(path).parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    let schema_metadata = [
+        ("column_metadata".into(), serde_json::to_string(column_metadata)?.into()),
+        ("sources".into(), serde_json::to_string(data_sources)?.into()),
+    ].into_iter().collect();
+
+    let mut writer = IpcWriter::new(File::create(path)?).with_compression(Some(IpcCompression::ZSTD));
+    writer.set_custom_schema_metadata(Arc::new(schema_metadata));
+    writer.finish(&mut result.clone())?;
+
+    Ok(())
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+    use tempfile::tempdir;
+
+    use crate::datasets_config::datasets_config::Scale;
+
+    use super::*;
+
+    #[test]
+    fn test_write_keeps_schema_metadata() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("result.arrow");
+        let result = df![
+            "quarter" => &["2024-Q1", "2024-Q2"],
+            "us_gdp_usd_billions" => &[28000.0, 28600.0]
+        ].unwrap();
+        let column_metadata = [ColumnMetadata::new("us_gdp_usd_billions", "us_gdp", "USD", Scale::Billions)];
+        let data_sources = [DataSource::new("us_gdp", "fred", "GDP", Local.with_ymd_and_hms(2025, 6, 30, 14, 15, 2).unwrap())];
+
+        write(path.to_str().unwrap(), &result, &column_metadata, &data_sources).unwrap();
+
+        let mut reader = IpcReader::new(File::open(&path).unwrap());
+        let schema_metadata = reader.custom_metadata().unwrap().unwrap();
+        assert_eq!(schema_metadata.get("column_metadata").unwrap().as_str(),
+                   r#"[{"column_name":"us_gdp_usd_billions","dataset_name":"us_gdp","unit":"USD","scale":"billions","transformations":[]}]"#);
+        assert!(schema_metadata.get("sources").unwrap().contains(r#""fetched_at":"2025-06-30T14:15:02"#));
+        assert_eq!(reader.finish().unwrap(), result);
+    }
+}
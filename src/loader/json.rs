@@ -0,0 +1,143 @@
+use std::error::Error;
+use std::fs;
+use std::io::{BufWriter, Write};
+
+use polars::prelude::*;
+use serde_json::{json, Map, Number, Value};
+
+use crate::model::data_model::{ColumnMetadata, DataSource};
+
+pub const JSON_DIRECTORY: &str = "outputs/json";
+
+/// The units of the columns and the sources of the data, as embedded in the JSON and Arrow outputs.
+pub fn metadata_json(column_metadata: &[ColumnMetadata], data_sources: &[DataSource]) -> Value {
+    json!({ "column_metadata": column_metadata, "sources": data_sources })
+}
+
+/// Writes `{"metadata": ..., "records": [{"quarter": "2024-Q1", ...}, ...]}`, one record per quarter.
+pub fn write_records(path: &str, result: &DataFrame, metadata: &Value) -> Result<(), Box<dyn Error>> {
+    let records: Vec<Value> = records(result)?.into_iter().map(Value::Object).collect();
+
+    write_json(path, &json!({ "metadata": metadata, "records": records }))
+}
+
+/// Writes `{"metadata": ..., "columns": {"quarter": ["2024-Q1", ...], ...}}`, one array per column.
+pub fn write_columnar(path: &str, result: &DataFrame, metadata: &Value) -> Result<(), Box<dyn Error>> {
+    let columns: Map<String, Value> = result.get_columns()
+        .iter()
+        .map(|column| Ok((column.name().to_string(), Value::Array(json_values(column)?))))
+        .collect::<Result<_, PolarsError>>()?;
+
+    write_json(path, &json!({ "metadata": metadata, "columns": columns }))
+}
+
+/// Writes one record per line and nothing else, so every line can be ingested on its own.
+pub fn write_ndjson(path: &str, result: &DataFrame) -> Result<(), Box<dyn Error>> {
+    create_parent_directory(path)?;
+    let mut writer = BufWriter::new(fs::File::create(path)?);
+
+    for record in records(result)? {
+        serde_json::to_writer(&mut writer, &record)?;
+        writer.write_all(b"\n")?;
+    }
+
+    writer.flush()?;
+    Ok(())
+}
+
+fn write_json(path: &str, value: &Value) -> Result<(), Box<dyn Error>> {
+    create_parent_directory(path)?;
+    serde_json::to_writer(BufWriter::new(fs::File::create(path)?), value)?;
+    Ok(())
+}
+
+fn create_parent_directory(path: &str) -> std::io::Result<()> {
+    match std::path::Path::new(path).parent() {
+        Some(directory) => fs::create_dir_all(directory),
+        None => Ok(()),
+    }
+}
+
+fn records(result: &DataFrame) -> PolarsResult<Vec<Map<String, Value>>> {
+    let columns: Vec<(String, Vec<Value>)> = result.get_columns()
+        .iter()
+        .map(|column| Ok((column.name().to_string(), json_values(column)?)))
+        .collect::<PolarsResult<_>>()?;
+
+    Ok((0..result.height())
+        .map(|row| columns.iter().map(|(name, values)| (name.clone(), values[row].clone())).collect())
+        .collect())
+}
+
+/// Numbers stay numbers, missing and non-finite values become `null`, anything else a string.
+fn json_values(column: &Column) -> PolarsResult<Vec<Value>> {
+    let dtype = column.dtype();
+
+    if dtype.is_integer() {
+        let values = column.cast(&DataType::Int64)?;
+        Ok(values.i64()?.into_iter().map(|value| value.map_or(Value::Null, Value::from)).collect())
+    } else if dtype.is_primitive_numeric() {
+        let values = column.cast(&DataType::Float64)?;
+        Ok(values.f64()?.into_iter().map(|value| value.and_then(Number::from_f64).map_or(Value::Null, Value::Number)).collect())
+    } else if dtype == &DataType::Boolean {
+        Ok(column.bool()?.into_iter().map(|value| value.map_or(Value::Null, Value::Bool)).collect())
+    } else {
+        let values = column.cast(&DataType::String)?;
+        Ok(values.str()?.into_iter().map(|value| value.map_or(Value::Null, Value::from)).collect())
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Local, TimeZone};
+    use tempfile::tempdir;
+
+    use crate::datasets_config::datasets_config::Scale;
+
+    use super::*;
+
+    fn result() -> DataFrame {
+        df![
+            "quarter" => &["2024-Q1", "2024-Q2"],
+            "us_inflation_perc" => &[Some(3.4), None],
+            "us_recession_nber" => &[0, 1]
+        ].unwrap()
+    }
+
+    fn metadata() -> Value {
+        metadata_json(&[ColumnMetadata::new("us_inflation_perc", "us_inflation", "percent", Scale::Units)],
+                      &[DataSource::new("us_inflation", "fred", "CPIAUCSL", Local.with_ymd_and_hms(2025, 6, 30, 14, 15, 2).unwrap())])
+    }
+
+    #[test]
+    fn test_write_records_and_columnar_json_with_metadata() {
+        let directory = tempdir().unwrap();
+        let records_path = directory.path().join("result.json");
+        let columnar_path = directory.path().join("result_columnar.json");
+
+        write_records(records_path.to_str().unwrap(), &result(), &metadata()).unwrap();
+        write_columnar(columnar_path.to_str().unwrap(), &result(), &metadata()).unwrap();
+
+        let records: Value = serde_json::from_str(&fs::read_to_string(records_path).unwrap()).unwrap();
+        assert_eq!(records["records"][1], json!({ "quarter": "2024-Q2", "us_inflation_perc": null, "us_recession_nber": 1 }));
+        assert_eq!(records["metadata"]["column_metadata"][0]["unit"], "percent");
+        assert_eq!(records["metadata"]["sources"][0]["identifier"], "CPIAUCSL");
+
+        let columnar: Value = serde_json::from_str(&fs::read_to_string(columnar_path).unwrap()).unwrap();
+        assert_eq!(columnar["columns"]["us_inflation_perc"], json!([3.4, null]));
+        assert_eq!(columnar["metadata"], metadata());
+    }
+
+    #[test]
+    fn test_write_ndjson_one_record_per_line() {
+        let directory = tempdir().unwrap();
+        let path = directory.path().join("nested").join("result.ndjson");
+
+        write_ndjson(path.to_str().unwrap(), &result()).unwrap();
+
+        assert_eq!(fs::read_to_string(path).unwrap(),
+                   "{\"quarter\":\"2024-Q1\",\"us_inflation_perc\":3.4,\"us_recession_nber\":0}\n\
+                    {\"quarter\":\"2024-Q2\",\"us_inflation_perc\":null,\"us_recession_nber\":1}\n");
+    }
+}
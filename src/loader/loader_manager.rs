@@ -1,27 +1,439 @@
-use polars::prelude::{DataFrame, PlSmallStr};
+use std::collections::HashMap;
+
+use chrono::{DateTime, Local};
+use log::{info, warn};
+use polars::prelude::{df, len, DataFrame, PlSmallStr};
 
 use loader::csv;
 use loader::graph::generate_graphs;
+use loader::html::generate_report;
+use loader::ipc;
+use loader::json;
 use loader::parquet;
+use loader::partitioned;
+use loader::revisions::{diff_results, diff_summary, revision_dataframe};
+use loader::store::{self, StoreLayer};
+use loader::tables::generate_tables;
+use loader::xlsx::generate_workbook;
 
+use crate::datasets_config::datasets_config::{get_all_chart_configs, get_all_datasets_configs, get_all_differential_configs, get_all_recession_configs, get_chart_pdf_file_name, get_html_report_file_name, get_partitioned_dataset_config, get_table_export_config, get_workbook_file_name, OutputFormat, PartitionedDatasetConfig, RecessionSource, Scale};
 use crate::loader;
+use crate::model::data_model::{Anomaly, ColumnMetadata, DataSource, GrangerTest, RegressionCoefficient, RegressionResult, RunContext, Statistic, ValidationCheck};
+
+pub fn load(result: DataFrame,
+            column_metadata: Vec<ColumnMetadata>,
+            statistics: Vec<Statistic>,
+            regressions: Vec<RegressionResult>,
+            granger_tests: Vec<GrangerTest>,
+            run_context: &RunContext) -> () {
+    let run_id = run_context.run_id.as_str();
+    let data_sources = data_sources(run_context.fetched_at);
+    let original_column_names: Vec<String> = column_metadata.iter().map(|metadata| metadata.column_name.clone()).collect();
+    let (result_final_column_names, final_column_metadata) = set_final_column_names(result, column_metadata);
+
+    let final_column_names: HashMap<String, String> = original_column_names.into_iter()
+        .zip(final_column_metadata.iter().map(|metadata| metadata.column_name.clone()))
+        .collect();
 
-pub fn load(result: DataFrame) -> () {
-    let result_final_column_names = set_final_column_names(result);
+    let statistics = statistics_dataframe(&statistics, &final_column_names);
+    let regression = regression_dataframe(&regressions, &final_column_names);
+    let granger = granger_dataframe(&granger_tests, &final_column_names);
 
     csv::load(result_final_column_names.clone());
-    parquet::load(result_final_column_names.clone());
-    generate_graphs(result_final_column_names.clone());
+    csv::load_table(statistics.clone(), "statistics");
+    csv::load_table(regression.clone(), "regression");
+    csv::load_table(granger.clone(), "granger");
+    load_revisions(&result_final_column_names);
+    parquet::load(result_final_column_names.clone(), &final_column_metadata, &data_sources, run_context);
+    load_output_formats(&result_final_column_names, &final_column_metadata, &data_sources, &run_context.output_formats);
+    store_result(&result_final_column_names, run_id);
+    if let Some(partitioned_dataset_config) = get_partitioned_dataset_config() {
+        append_partitioned_result(&result_final_column_names, &partitioned_dataset_config, run_context);
+    }
+    generate_tables(&result_final_column_names, &get_table_export_config());
+    generate_graphs(&result_final_column_names, &get_all_chart_configs(), get_chart_pdf_file_name());
+
+    if let Some(report_file_name) = get_html_report_file_name() {
+        generate_report(&result_final_column_names,
+                        &final_column_metadata,
+                        &get_all_chart_configs(),
+                        &get_all_differential_configs(),
+                        &data_sources,
+                        run_id,
+                        report_file_name);
+    }
+
+    if let Some(workbook_file_name) = get_workbook_file_name() {
+        let mut sheets: Vec<(String, DataFrame)> = raw_datasets(run_id);
+        sheets.extend([("metadata".to_string(), metadata_dataframe(&final_column_metadata)),
+                       ("sources".to_string(), sources_dataframe(&data_sources)),
+                       ("statistics".to_string(), statistics),
+                       ("regression".to_string(), regression),
+                       ("granger".to_string(), granger)]);
+
+        generate_workbook(&result_final_column_names, &final_column_metadata, &sheets, &get_all_chart_configs(), workbook_file_name);
+    }
+}
+
+/// Writes the result in every selected output format, with the column metadata and the data
+/// sources embedded where the format has room for them.
+fn load_output_formats(result: &DataFrame, column_metadata: &[ColumnMetadata], data_sources: &[DataSource], output_formats: &[OutputFormat]) {
+    let metadata = json::metadata_json(column_metadata, data_sources);
+
+    for output_format in output_formats {
+        let (file_path, written) = match output_format {
+            OutputFormat::Json => {
+                let file_path = format!("{}/result.json", json::JSON_DIRECTORY);
+                let written = json::write_records(&file_path, result, &metadata);
+                (file_path, written)
+            },
+            OutputFormat::JsonColumnar => {
+                let file_path = format!("{}/result_columnar.json", json::JSON_DIRECTORY);
+                let written = json::write_columnar(&file_path, result, &metadata);
+                (file_path, written)
+            },
+            OutputFormat::Ndjson => {
+                let file_path = format!("{}/result.ndjson", json::JSON_DIRECTORY);
+                let written = json::write_ndjson(&file_path, result);
+                (file_path, written)
+            },
+            OutputFormat::ArrowIpc => {
+                let file_path = format!("{}/result.arrow", ipc::IPC_DIRECTORY);
+                let written = ipc::write(&file_path, result, column_metadata, data_sources);
+                (file_path, written)
+            },
+        };
+
+        match written {
+            Ok(()) => info!("{} output saved as {file_path}", output_format.name()),
+            Err(error) => warn!("could not write {file_path}: {error}"),
+        }
+    }
+}
+
+/// The extracted datasets of the run, as kept in the store.
+fn raw_datasets(run_id: &str) -> Vec<(String, DataFrame)> {
+    get_all_datasets_configs().into_iter()
+        .filter_map(|config| match store::read_dataset(store::STORE_DIRECTORY, StoreLayer::Raw, config.name, Some(run_id)) {
+            Ok(dataset) => Some((config.name.to_string(), dataset)),
+            Err(error) => {
+                warn!("leaving the raw {} out of the workbook: {error}", config.name);
+                None
+            },
+        })
+        .collect()
+}
+
+/// The configured datasets and recession sources, fetched at `fetched_at`.
+fn data_sources(fetched_at: DateTime<Local>) -> Vec<DataSource> {
+    let datasets = get_all_datasets_configs().into_iter()
+        .map(|config| DataSource::new(config.name, config.source, config.identifier, fetched_at));
+
+    let recessions = get_all_recession_configs().into_iter()
+        .map(|config| match config.source {
+            RecessionSource::Fred(series_id) => DataSource::new(config.column, "fred", series_id, fetched_at),
+            RecessionSource::DateRangeFile { path, region } => DataSource::new(config.column, "csv", &format!("{path} ({region})"), fetched_at),
+        });
+
+    datasets.chain(recessions).collect()
+}
+
+/// Compares the result to the one of the previous run, before it is overwritten, and writes the
+/// differences to `revisions.csv`.
+fn load_revisions(result: &DataFrame) {
+    match parquet::read(parquet::RESULT_PATH) {
+        Ok(previous_result) => {
+            let result_diff = diff_results(&previous_result, result);
+            info!("changes since the previous run: {}", diff_summary(&result_diff));
+            csv::load_table(revision_dataframe(&result_diff.revisions), "revisions");
+        },
+        Err(error) => info!("no previous result to compare with, {error}"),
+    }
+}
+
+/// Keeps the result of the run in the store, next to its raw and processed datasets.
+fn store_result(result: &DataFrame, run_id: &str) {
+    match store::write_dataset(store::STORE_DIRECTORY, StoreLayer::Result, "result", run_id, result) {
+        Ok(()) => info!("stored the result of run {run_id} in {}", store::STORE_DIRECTORY),
+        Err(error) => warn!("could not store the result of run {run_id}: {error}"),
+    }
+}
+
+fn append_partitioned_result(result: &DataFrame, config: &PartitionedDatasetConfig, run_context: &RunContext) {
+    let scenario = run_context.scenario.as_deref().unwrap_or(config.scenario);
+
+    match partitioned::append(result, config.directory, config.partitioning, scenario, run_context) {
+        Ok(files) => info!("appended the result of run {} to {} as {}", run_context.run_id, config.directory, files.join(", ")),
+        Err(error) => {
+            warn!("could not append the result of run {} to {}: {error}", run_context.run_id, config.directory);
+            return;
+        },
+    }
+
+    let rows = partitioned::scan(config.directory)
+        .and_then(|dataset| Ok(dataset.select([len()]).collect()?.column("len")?.get(0)?.to_string()));
+    match rows {
+        Ok(rows) => info!("{} holds {rows} rows", config.directory),
+        Err(error) => warn!("could not scan {}: {error}", config.directory),
+    }
+}
+
+/// Writes the checks of the extracted datasets to `validation.csv`, before any transformation runs.
+pub fn load_validation_report(validation_checks: &[ValidationCheck]) {
+    csv::load_table(validation_dataframe(validation_checks), "validation");
+}
+
+/// Writes the flagged observations of the extracted datasets to `anomalies.csv`.
+pub fn load_anomaly_report(anomalies: &[Anomaly]) {
+    csv::load_table(anomaly_dataframe(anomalies), "anomalies");
+}
+
+/// Renames the joined columns to their output names. Columns built on a renamed column, such as
+/// `eu_gdp_converted_rebased`, keep their suffix. Scaled columns get the scale from their
+/// metadata appended, e.g. `us_gdp_usd` in billions is written as `us_gdp_usd_billions`.
+fn set_final_column_names(mut result: DataFrame, column_metadata: Vec<ColumnMetadata>) -> (DataFrame, Vec<ColumnMetadata>) {
+    let final_base_names: HashMap<&str, &str> = [
+        ("eur_to_usd", "fx_rate_eur_to_usd"),
+        ("us_inflation_usd", "us_inflation_perc"),
+        ("eu_inflation", "eu_inflation_perc"),
+        ("eu_government_debt_converted", "eu_government_debt_usd"),
+        ("eu_gdp_converted", "eu_gdp_usd"),
+        ("eur_per_intl_usd", "ppp_eur_per_intl_usd"),
+        ("eu_government_debt_ppp_converted", "eu_government_debt_intl_usd"),
+        ("eu_gdp_ppp_converted", "eu_gdp_intl_usd"),
+    ].into_iter().collect();
+
+    let mut final_column_metadata: Vec<ColumnMetadata> = vec![];
+
+    for metadata in column_metadata {
+        let base_name = final_base_name(&metadata.column_name, &final_base_names);
+
+        let final_name = if metadata.scale == Scale::Units {
+            base_name
+        } else {
+            format!("{}_{}", base_name, metadata.scale.name())
+        };
+
+        if result.column(&metadata.column_name).is_ok() {
+            result.rename(&metadata.column_name, PlSmallStr::from_str(&final_name)).unwrap();
+        }
+
+        final_column_metadata.push(ColumnMetadata { column_name: final_name, ..metadata });
+    }
+
+    (result, final_column_metadata)
+}
+
+fn final_base_name(column_name: &str, final_base_names: &HashMap<&str, &str>) -> String {
+    final_base_names
+        .iter()
+        .filter_map(|(name, final_name)| {
+            let suffix = column_name.strip_prefix(name)?;
+            (suffix.is_empty() || suffix.starts_with('_')).then(|| (name.len(), format!("{final_name}{suffix}")))
+        })
+        .max()
+        .map(|(_, base_name)| base_name)
+        .unwrap_or_else(|| column_name.to_string())
 }
 
-fn set_final_column_names(mut result: DataFrame) -> DataFrame {
-    let result = result.rename("eur_to_usd", PlSmallStr::from_str("fx_rate_eur_to_usd")).unwrap();
-    let result = result.rename("us_gdp_usd", PlSmallStr::from_str("us_gdp_usd_billions")).unwrap();
-    let result = result.rename("us_total_debt_usd", PlSmallStr::from_str("us_total_debt_usd_millions")).unwrap();
-    let result = result.rename("us_inflation_usd", PlSmallStr::from_str("us_inflation_perc")).unwrap();
-    let result = result.rename("eu_inflation", PlSmallStr::from_str("eu_inflation_perc")).unwrap();
-    let result = result.rename("eu_government_debt_converted", PlSmallStr::from_str("eu_government_debt_usd_millions")).unwrap();
-    let result = result.rename("eu_gdp_converted", PlSmallStr::from_str("eu_gdp_usd_millions")).unwrap();
+/// Lays the statistics out as one row each, referring to columns by their output names.
+fn statistics_dataframe(statistics: &[Statistic], final_column_names: &HashMap<String, String>) -> DataFrame {
+    let final_name = |column_name: &String| final_column_names.get(column_name).unwrap_or(column_name).clone();
 
-    result.clone()
-}
\ No newline at end of file
+    df![
+        "series" => statistics.iter().map(|statistic| final_name(&statistic.series)).collect::<Vec<String>>(),
+        "benchmark" => statistics.iter().map(|statistic| final_name(&statistic.benchmark)).collect::<Vec<String>>(),
+        "statistic" => statistics.iter().map(|statistic| statistic.statistic).collect::<Vec<&str>>(),
+        "lag" => statistics.iter().map(|statistic| statistic.lag).collect::<Vec<i64>>(),
+        "quarter" => statistics.iter().map(|statistic| statistic.quarter.clone()).collect::<Vec<Option<String>>>(),
+        "observations" => statistics.iter().map(|statistic| statistic.observations as u64).collect::<Vec<u64>>(),
+        "value" => statistics.iter().map(|statistic| statistic.value).collect::<Vec<f64>>()
+    ].unwrap()
+}
+
+/// One row per coefficient. `model` tells regressions of the same dependent apart.
+pub fn regression_dataframe(regressions: &[RegressionResult], final_column_names: &HashMap<String, String>) -> DataFrame {
+    let final_name = |column_name: &String| final_column_names.get(column_name).unwrap_or(column_name).clone();
+    let rows: Vec<(&RegressionResult, &RegressionCoefficient)> = regressions.iter()
+        .flat_map(|regression| regression.coefficients.iter().map(move |coefficient| (regression, coefficient)))
+        .collect();
+
+    let model = |regression: &RegressionResult| {
+        let regressors: Vec<String> = regression.coefficients.iter()
+            .filter(|coefficient| coefficient.term != "intercept")
+            .map(|coefficient| final_name(&coefficient.term))
+            .collect();
+        format!("{} ~ {}", final_name(&regression.dependent), regressors.join(" + "))
+    };
+
+    df![
+        "model" => rows.iter().map(|(regression, _)| model(regression)).collect::<Vec<String>>(),
+        "dependent" => rows.iter().map(|(regression, _)| final_name(&regression.dependent)).collect::<Vec<String>>(),
+        "term" => rows.iter().map(|(_, coefficient)| final_name(&coefficient.term)).collect::<Vec<String>>(),
+        "estimate" => rows.iter().map(|(_, coefficient)| coefficient.estimate).collect::<Vec<f64>>(),
+        "standard_error" => rows.iter().map(|(_, coefficient)| coefficient.standard_error).collect::<Vec<f64>>(),
+        "t_statistic" => rows.iter().map(|(_, coefficient)| coefficient.t_statistic).collect::<Vec<f64>>(),
+        "p_value" => rows.iter().map(|(_, coefficient)| coefficient.p_value).collect::<Vec<f64>>(),
+        "r_squared" => rows.iter().map(|(regression, _)| regression.r_squared).collect::<Vec<f64>>(),
+        "adjusted_r_squared" => rows.iter().map(|(regression, _)| regression.adjusted_r_squared).collect::<Vec<f64>>(),
+        "observations" => rows.iter().map(|(regression, _)| regression.observations as u64).collect::<Vec<u64>>(),
+        "covariance" => rows.iter().map(|(regression, _)| regression.covariance.clone()).collect::<Vec<String>>()
+    ].unwrap()
+}
+
+pub fn granger_dataframe(granger_tests: &[GrangerTest], final_column_names: &HashMap<String, String>) -> DataFrame {
+    let final_name = |column_name: &String| final_column_names.get(column_name).unwrap_or(column_name).clone();
+
+    df![
+        "cause" => granger_tests.iter().map(|granger_test| final_name(&granger_test.cause)).collect::<Vec<String>>(),
+        "effect" => granger_tests.iter().map(|granger_test| final_name(&granger_test.effect)).collect::<Vec<String>>(),
+        "lags" => granger_tests.iter().map(|granger_test| granger_test.lags as u64).collect::<Vec<u64>>(),
+        "observations" => granger_tests.iter().map(|granger_test| granger_test.observations as u64).collect::<Vec<u64>>(),
+        "f_statistic" => granger_tests.iter().map(|granger_test| granger_test.f_statistic).collect::<Vec<f64>>(),
+        "p_value" => granger_tests.iter().map(|granger_test| granger_test.p_value).collect::<Vec<f64>>()
+    ].unwrap()
+}
+
+fn metadata_dataframe(column_metadata: &[ColumnMetadata]) -> DataFrame {
+    df![
+        "column" => column_metadata.iter().map(|metadata| metadata.column_name.clone()).collect::<Vec<String>>(),
+        "dataset" => column_metadata.iter().map(|metadata| metadata.dataset_name.clone()).collect::<Vec<String>>(),
+        "unit" => column_metadata.iter().map(|metadata| metadata.unit.clone()).collect::<Vec<String>>(),
+        "scale" => column_metadata.iter().map(|metadata| metadata.scale.name()).collect::<Vec<&str>>(),
+        "transformations" => column_metadata.iter().map(|metadata| metadata.transformations.join(", ")).collect::<Vec<String>>()
+    ].unwrap()
+}
+
+fn sources_dataframe(data_sources: &[DataSource]) -> DataFrame {
+    df![
+        "dataset" => data_sources.iter().map(|data_source| data_source.dataset.clone()).collect::<Vec<String>>(),
+        "source" => data_sources.iter().map(|data_source| data_source.source.clone()).collect::<Vec<String>>(),
+        "identifier" => data_sources.iter().map(|data_source| data_source.identifier.clone()).collect::<Vec<String>>(),
+        "fetched_at" => data_sources.iter().map(|data_source| data_source.fetched_at.to_rfc3339()).collect::<Vec<String>>()
+    ].unwrap()
+}
+
+fn validation_dataframe(validation_checks: &[ValidationCheck]) -> DataFrame {
+    df![
+        "dataset" => validation_checks.iter().map(|check| check.dataset.clone()).collect::<Vec<String>>(),
+        "rule" => validation_checks.iter().map(|check| check.rule).collect::<Vec<&str>>(),
+        "status" => validation_checks.iter().map(|check| check.status.name()).collect::<Vec<&str>>(),
+        "message" => validation_checks.iter().map(|check| check.message.clone()).collect::<Vec<String>>()
+    ].unwrap()
+}
+
+fn anomaly_dataframe(anomalies: &[Anomaly]) -> DataFrame {
+    df![
+        "dataset" => anomalies.iter().map(|anomaly| anomaly.dataset.clone()).collect::<Vec<String>>(),
+        "period" => anomalies.iter().map(|anomaly| anomaly.period.clone()).collect::<Vec<String>>(),
+        "kind" => anomalies.iter().map(|anomaly| anomaly.kind).collect::<Vec<&str>>(),
+        "value" => anomalies.iter().map(|anomaly| anomaly.value).collect::<Vec<f64>>(),
+        "magnitude" => anomalies.iter().map(|anomaly| anomaly.magnitude).collect::<Vec<f64>>()
+    ].unwrap()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use polars::prelude::*;
+
+    use crate::model::data_model::ValidationStatus;
+
+    use super::*;
+
+    #[test]
+    fn test_set_final_column_names() {
+        let df = df![
+            "quarter" => &["2023-Q1"],
+            "eu_gdp_converted" => &[100.0],
+            "eu_inflation" => &[2.0],
+            "sp500_usd" => &[4000.0],
+            "eu_government_debt_converted_trend" => &[90.0],
+            "eu_gdp_ppp_converted_rebased" => &[101.0]
+        ].unwrap();
+
+        let column_metadata = vec![
+            ColumnMetadata::new("eu_gdp_converted", "eu_gdp", "USD", Scale::Billions),
+            ColumnMetadata::new("eu_inflation", "eu_inflation", "percent", Scale::Units),
+            ColumnMetadata::new("sp500_usd", "sp500", "index points", Scale::Units),
+            ColumnMetadata::new("eu_government_debt_converted_trend", "eu_government_debt", "USD", Scale::Billions),
+            ColumnMetadata::new("eu_gdp_ppp_converted_rebased", "eu_gdp", "index 2015=100", Scale::Units),
+        ];
+
+        let (result, final_metadata) = set_final_column_names(df, column_metadata);
+
+        assert_eq!(result.get_column_names(), vec!["quarter",
+                                                   "eu_gdp_usd_billions",
+                                                   "eu_inflation_perc",
+                                                   "sp500_usd",
+                                                   "eu_government_debt_usd_trend_billions",
+                                                   "eu_gdp_intl_usd_rebased"]);
+        assert_eq!(final_metadata[0].column_name, "eu_gdp_usd_billions");
+        assert_eq!(final_metadata[0].unit, "USD");
+        assert_eq!(final_metadata[1].column_name, "eu_inflation_perc");
+        assert_eq!(final_metadata[2].column_name, "sp500_usd");
+    }
+
+    #[test]
+    fn test_statistics_dataframe_uses_final_column_names() {
+        let statistics = vec![
+            Statistic::new("eu_inflation", "sp500_yoy_perc", "pearson", 0, None, 40, 0.25),
+            Statistic::new("eu_inflation", "sp500_yoy_perc", "rolling_pearson", 0, Some("2023-Q4"), 8, -0.5),
+        ];
+        let final_column_names: HashMap<String, String> = [("eu_inflation".to_string(), "eu_inflation_perc".to_string())]
+            .into_iter()
+            .collect();
+
+        let result = statistics_dataframe(&statistics, &final_column_names);
+
+        assert_eq!(result.shape(), (2, 7));
+        assert_eq!(result.column("series").unwrap().str().unwrap().get(0), Some("eu_inflation_perc"));
+        assert_eq!(result.column("benchmark").unwrap().str().unwrap().get(1), Some("sp500_yoy_perc"));
+        assert_eq!(result.column("quarter").unwrap().str().unwrap().get(0), None);
+        assert_eq!(result.column("quarter").unwrap().str().unwrap().get(1), Some("2023-Q4"));
+    }
+
+    #[test]
+    fn test_regression_and_granger_dataframes() {
+        let regressions = vec![RegressionResult::new("sp500_yoy_perc", "newey_west(4)", 40, 0.3, 0.25, vec![
+            RegressionCoefficient::new("intercept", 1.0, 0.5, 2.0, 0.05),
+            RegressionCoefficient::new("eu_inflation", -2.0, 1.0, -2.0, 0.05),
+        ])];
+        let granger_tests = vec![GrangerTest::new("eu_inflation", "sp500_log_return_perc", 2, 38, 1.5, 0.24)];
+        let final_column_names: HashMap<String, String> = [("eu_inflation".to_string(), "eu_inflation_perc".to_string())]
+            .into_iter()
+            .collect();
+
+        let regression = regression_dataframe(&regressions, &final_column_names);
+        assert_eq!(regression.shape(), (2, 11));
+        assert_eq!(regression.column("model").unwrap().str().unwrap().get(0), Some("sp500_yoy_perc ~ eu_inflation_perc"));
+        assert_eq!(regression.column("term").unwrap().str().unwrap().get(1), Some("eu_inflation_perc"));
+
+        let granger = granger_dataframe(&granger_tests, &final_column_names);
+        assert_eq!(granger.shape(), (1, 6));
+        assert_eq!(granger.column("cause").unwrap().str().unwrap().get(0), Some("eu_inflation_perc"));
+    }
+
+    #[test]
+    fn test_validation_dataframe() {
+        let validation_checks = vec![
+            ValidationCheck::new("eu_gdp", "schema", ValidationStatus::Passed, "quarter is text and value is float"),
+            ValidationCheck::new("eu_gdp", "freshness", ValidationStatus::Warning, "last observation 2024-Q1 is 300 days old, maximum 200"),
+        ];
+
+        let result = validation_dataframe(&validation_checks);
+
+        assert_eq!(result.get_column_names(), vec!["dataset", "rule", "status", "message"]);
+        assert_eq!(result.column("status").unwrap().str().unwrap().get(1), Some("warning"));
+    }
+
+    #[test]
+    fn test_anomaly_dataframe() {
+        let anomalies = vec![Anomaly::new("us_gdp", "2024-01-01", "scale_change", 29000000.0, 1000.0)];
+
+        let result = anomaly_dataframe(&anomalies);
+
+        assert_eq!(result.get_column_names(), vec!["dataset", "period", "kind", "value", "magnitude"]);
+        assert_eq!(result.column("magnitude").unwrap().f64().unwrap().get(0), Some(1000.0));
+    }
+}
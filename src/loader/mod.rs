@@ -1,4 +1,14 @@
-mod parquet;
+pub mod parquet;
 pub mod csv;
+pub mod cache;
+pub mod revisions;
+pub mod store;
+pub mod partitioned;
 pub mod loader_manager;
-mod graph;
\ No newline at end of file
+mod graph;
+mod html;
+mod ipc;
+mod json;
+mod pdf;
+mod tables;
+mod xlsx;
\ No newline at end of file
@@ -1,12 +1,101 @@
+use std::error::Error;
 use std::fs::File;
 
+use chrono::{DateTime, Local};
 use polars::prelude::*;
+use polars_parquet::write::KeyValue;
+use serde::Serialize;
 
-pub fn load(mut result_dataframe: DataFrame) -> () {
-    let file = File::create("outputs/parquet/result.parquet").expect("could not create file");
-    let _ = ParquetWriter::new(file)
+use crate::datasets_config::datasets_config::{config_hash, Scale};
+use crate::model::data_model::{ColumnMetadata, DataSource, RunContext};
+
+pub const RESULT_PATH: &str = "outputs/parquet/result.parquet";
+
+/// Where a column of the result comes from and what was done to it. Columns computed from
+/// other columns, such as derived and expression series, have no source of their own.
+#[derive(Debug, PartialEq, Serialize)]
+struct ColumnProvenance<'a> {
+    column_name: &'a str,
+    dataset_name: &'a str,
+    source: Option<&'a str>,
+    identifier: Option<&'a str>,
+    unit: &'a str,
+    scale: Scale,
+    transformations: &'a [String],
+}
+
+/// The run that wrote the result.
+#[derive(Debug, PartialEq, Serialize)]
+struct RunProvenance<'a> {
+    tool: &'static str,
+    version: &'static str,
+    run_id: &'a str,
+    fetched_at: DateTime<Local>,
+    config_hash: String,
+}
+
+/// Writes the result as Snappy-compressed Parquet. The footer's key-value metadata holds, as
+/// JSON, the `column_metadata`, the `provenance` of every column (source, series identifier,
+/// unit, scale and transformations) and the `run` (tool version, run id, fetch time and hash
+/// of the configuration).
+pub fn load(mut result_dataframe: DataFrame,
+            column_metadata: &[ColumnMetadata],
+            data_sources: &[DataSource],
+            run_context: &RunContext) -> () {
+    let file = File::create(RESULT_PATH).expect("could not create file");
+
+    let run = RunProvenance {
+        tool: env!("CARGO_PKG_NAME"),
+        version: env!("CARGO_PKG_VERSION"),
+        run_id: &run_context.run_id,
+        fetched_at: run_context.fetched_at,
+        config_hash: config_hash(),
+    };
+
+    let key_value_metadata = [
+        ("column_metadata", serde_json::to_string(column_metadata).unwrap()),
+        ("provenance", serde_json::to_string(&column_provenance(column_metadata, data_sources)).unwrap()),
+        ("run", serde_json::to_string(&run).unwrap()),
+    ].into_iter()
+        .map(|(key, value)| KeyValue { key: key.to_string(), value: Some(value) })
+        .collect();
+
+    result_dataframe.rechunk_mut();
+
+    let mut writer = ParquetWriter::new(file)
         .with_compression(ParquetCompression::Snappy)
-        .finish(&mut result_dataframe);
+        .batched(result_dataframe.schema())
+        .unwrap();
+
+    writer.write_batch(&result_dataframe).unwrap();
+    writer.get_writer().lock().unwrap().end(Some(key_value_metadata)).unwrap();
+}
+
+/// Columns are matched to the data source of their dataset, recession flags to the source of their column.
+fn column_provenance<'a>(column_metadata: &'a [ColumnMetadata], data_sources: &'a [DataSource]) -> Vec<ColumnProvenance<'a>> {
+    column_metadata.iter()
+        .map(|metadata| {
+            let data_source = data_sources.iter()
+                .find(|data_source| data_source.dataset == metadata.dataset_name)
+                .or_else(|| data_sources.iter().find(|data_source| data_source.dataset == metadata.column_name));
+
+            ColumnProvenance {
+                column_name: &metadata.column_name,
+                dataset_name: &metadata.dataset_name,
+                source: data_source.map(|data_source| data_source.source.as_str()),
+                identifier: data_source.map(|data_source| data_source.identifier.as_str()),
+                unit: &metadata.unit,
+                scale: metadata.scale,
+                transformations: &metadata.transformations,
+            }
+        })
+        .collect()
+}
+
+/// Reads a result written by `load`, such as the previous run's or an archived snapshot.
+pub fn read(path: &str) -> Result<DataFrame, Box<dyn Error>> {
+    let file = File::open(path).map_err(|error| format!("could not open {path}: {error}"))?;
+    Ok(ParquetReader::new(file).finish()?)
 }
 
 
@@ -15,8 +104,33 @@ mod tests {
     use std::fs;
     use std::path::Path;
 
+    use chrono::TimeZone;
+
     use super::*;
 
+    fn fetched_at() -> DateTime<Local> {
+        Local.with_ymd_and_hms(2025, 6, 30, 14, 15, 2).unwrap()
+    }
+
+    #[test]
+    fn test_column_provenance() {
+        let mut converted = ColumnMetadata::new("eu_gdp_usd_billions", "eu_gdp", "USD", Scale::Billions);
+        converted.transformations.push("converted from EUR at the quarterly average EUR/USD market rate".to_string());
+        let column_metadata = vec![converted,
+                                   ColumnMetadata::new("debt_to_gdp", "derived", "percent", Scale::Units),
+                                   ColumnMetadata::new("us_recession_nber", "recession", "recession indicator (1 = recession)", Scale::Units)];
+        let data_sources = vec![DataSource::new("eu_gdp", "ecb", "MNA/Q.Y.I9.W2.S1.S1.B.B1GQ._Z._Z._Z.EUR.LR.N", fetched_at()),
+                                DataSource::new("us_recession_nber", "fred", "USREC", fetched_at())];
+
+        let provenance = column_provenance(&column_metadata, &data_sources);
+
+        assert_eq!(provenance[0].source, Some("ecb"));
+        assert_eq!(provenance[0].identifier, Some("MNA/Q.Y.I9.W2.S1.S1.B.B1GQ._Z._Z._Z.EUR.LR.N"));
+        assert_eq!(provenance[0].transformations, ["converted from EUR at the quarterly average EUR/USD market rate"]);
+        assert_eq!((provenance[1].source, provenance[1].identifier), (None, None));
+        assert_eq!(provenance[2].identifier, Some("USREC"));
+    }
+
     #[test]
     fn test_load_writes_parquet_correctly() {
         let df = df![
@@ -24,16 +138,40 @@ mod tests {
             "financial_metric" => &["dummy_metric_1", "dummy_metric_2"]
         ].unwrap();
 
+        let column_metadata = vec![ColumnMetadata::new("financial_metric", "dummy_dataset", "USD", Scale::Billions)];
+
         fs::create_dir_all("outputs/parquet").unwrap();
 
-        load(df.clone());
+        let data_sources = vec![DataSource::new("dummy_dataset", "fred", "DUMMY", fetched_at())];
+
+        load(df.clone(), &column_metadata, &data_sources, &RunContext::new("20250630T141502", fetched_at(), vec![], None));
 
         let path = "outputs/parquet/result.parquet";
         assert!(Path::new(path).exists(), "Parquet file was not created");
 
         let file = File::open(path).expect("Failed to open result.parquet");
-        let read_df = ParquetReader::new(file).finish().expect("Failed to read Parquet");
+        let mut reader = ParquetReader::new(file);
+
+        let key_value_metadata = reader.get_metadata().unwrap().key_value_metadata().clone().unwrap();
+        let column_metadata_entry = key_value_metadata.iter().find(|kv| kv.key == "column_metadata").unwrap();
+        assert_eq!(
+            column_metadata_entry.value.as_deref(),
+            Some(r#"[{"column_name":"financial_metric","dataset_name":"dummy_dataset","unit":"USD","scale":"billions","transformations":[]}]"#)
+        );
+
+        let value = |key: &str| key_value_metadata.iter().find(|kv| kv.key == key).unwrap().value.clone().unwrap();
+        assert_eq!(
+            value("provenance"),
+            r#"[{"column_name":"financial_metric","dataset_name":"dummy_dataset","source":"fred","identifier":"DUMMY","unit":"USD","scale":"billions","transformations":[]}]"#
+        );
+        let run: serde_json::Value = serde_json::from_str(&value("run")).unwrap();
+        assert_eq!(run["tool"], "financial_engine");
+        assert_eq!(run["version"], env!("CARGO_PKG_VERSION"));
+        assert_eq!(run["run_id"], "20250630T141502");
+        assert_eq!(run["config_hash"], config_hash());
+
+        let read_df = reader.finish().expect("Failed to read Parquet");
 
         assert!(df.equals(&read_df));
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,303 @@
+use std::collections::BTreeSet;
+use std::error::Error;
+use std::fs::{self, File};
+use std::path::Path;
+
+use chrono::{DateTime, Local};
+use polars::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::datasets_config::datasets_config::{config_hash, DatasetPartitioning};
+use crate::model::data_model::RunContext;
+
+pub const DATA_DIRECTORY: &str = "result";
+pub const MANIFEST_FILE_NAME: &str = "manifest.json";
+
+/// What the partitioned dataset holds, kept next to the data so that it is not scanned with it.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct Manifest {
+    partitioning: String,
+    partition_columns: Vec<String>,
+    runs: Vec<ManifestRun>,
+}
+
+/// A run appended to the dataset. Files are relative to the dataset directory.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+struct ManifestRun {
+    run_id: String,
+    scenario: String,
+    fetched_at: DateTime<Local>,
+    config_hash: String,
+    rows: usize,
+    columns: Vec<String>,
+    files: Vec<String>,
+}
+
+/// Appends the result of a run to the Hive-partitioned dataset in `directory`, with a `run_id`
+/// column, and records the run in the manifest. Appending the same run and scenario again
+/// replaces it, files included. Returns the files written, relative to the dataset directory.
+/// The whole dataset reads with [`scan`].
+pub fn append(result: &DataFrame,
+              directory: &str,
+              partitioning: DatasetPartitioning,
+              scenario: &str,
+              run_context: &RunContext) -> Result<Vec<String>, Box<dyn Error>> {
+    if !is_valid_scenario(scenario) {
+        return Err(format!("invalid scenario name '{scenario}'").into());
+    }
+
+    let dataset_directory = Path::new(directory);
+    let manifest_path = dataset_directory.join(MANIFEST_FILE_NAME);
+    let mut manifest = match fs::read_to_string(&manifest_path) {
+        Ok(manifest) => serde_json::from_str::<Manifest>(&manifest)?,
+        Err(error) if error.kind() == std::io::ErrorKind::NotFound => Manifest {
+            partitioning: partitioning.name().to_string(),
+            partition_columns: vec!["scenario".to_string(), partitioning.name().to_string()],
+            runs: vec![],
+        },
+        Err(error) => return Err(error.into()),
+    };
+
+    if DatasetPartitioning::from_name(&manifest.partitioning) != Some(partitioning) {
+        return Err(format!("{directory} is partitioned by {}, not {}", manifest.partitioning, partitioning.name()).into());
+    }
+
+    let mut result = result.clone();
+    result.with_column(Column::new("run_id".into(), vec![run_context.run_id.as_str(); result.height()]))?;
+
+    let partitions = match partitioning {
+        DatasetPartitioning::RunDate => vec![(run_context.fetched_at.format("%Y-%m-%d").to_string(), result.clone())],
+        DatasetPartitioning::Year => year_partitions(&result)?,
+    };
+
+    let mut files: Vec<String> = vec![];
+    for (partition, mut dataframe) in partitions {
+        let file = format!("{DATA_DIRECTORY}/scenario={scenario}/{}={partition}/{}.parquet", partitioning.name(), run_context.run_id);
+        let path = dataset_directory.join(&file);
+        if let Some(partition_directory) = path.parent() {
+            fs::create_dir_all(partition_directory)?;
+        }
+
+        ParquetWriter::new(File::create(&path)?)
+            .with_compression(ParquetCompression::Snappy)
+            .finish(&mut dataframe)?;
+        files.push(file);
+    }
+
+    // Files of the run appended before that this one did not overwrite, e.g. years it no longer has
+    let replaced_run = |run: &ManifestRun| run.run_id == run_context.run_id && run.scenario == scenario;
+    let orphaned_files: Vec<String> = manifest.runs.iter()
+        .filter(|run| replaced_run(run))
+        .flat_map(|run| run.files.iter().filter(|file| !files.contains(file)).cloned())
+        .collect();
+    manifest.runs.retain(|run| !replaced_run(run));
+    manifest.runs.push(ManifestRun {
+        run_id: run_context.run_id.clone(),
+        scenario: scenario.to_string(),
+        fetched_at: run_context.fetched_at,
+        config_hash: config_hash(),
+        rows: result.height(),
+        columns: result.get_column_names().iter().map(|name| name.to_string()).collect(),
+        files: files.clone(),
+    });
+    manifest.runs.sort_by(|a, b| (&a.run_id, &a.scenario).cmp(&(&b.run_id, &b.scenario)));
+
+    // Written next to the manifest and renamed, so that readers never see half a manifest
+    let temporary_path = dataset_directory.join(format!("{MANIFEST_FILE_NAME}.tmp"));
+    fs::write(&temporary_path, serde_json::to_string_pretty(&manifest)?)?;
+    fs::rename(&temporary_path, &manifest_path)?;
+
+    for file in orphaned_files {
+        fs::remove_file(dataset_directory.join(file))
+            .or_else(|error| if error.kind() == std::io::ErrorKind::NotFound { Ok(()) } else { Err(error) })?;
+    }
+
+    Ok(files)
+}
+
+/// A scenario names a partition directory, so it cannot be empty or contain a path separator or `=`.
+pub fn is_valid_scenario(scenario: &str) -> bool {
+    !scenario.is_empty() && !scenario.contains(['/', '\\', '='])
+}
+
+/// Scans the whole dataset in `directory` lazily, with the `scenario` and partition columns
+/// from the directory names. Polars takes the schema of a scan from its first file, so runs
+/// with other columns (e.g. a scenario with only the PPP conversion) are scanned with the
+/// columns of every run, null in the rows of runs without them.
+pub fn scan(directory: &str) -> Result<LazyFrame, Box<dyn Error>> {
+    let dataset_directory = Path::new(directory);
+    let manifest: Manifest = serde_json::from_str(&fs::read_to_string(dataset_directory.join(MANIFEST_FILE_NAME))?)?;
+
+    let mut schema = Schema::default();
+    for file in manifest.runs.iter().flat_map(|run| &run.files) {
+        let file_schema = ParquetReader::new(File::open(dataset_directory.join(file))?).schema()?;
+        schema.merge_from_ref(&Schema::from_arrow_schema(&file_schema));
+    }
+
+    let scan_arguments = ScanArgsParquet {
+        schema: Some(Arc::new(schema)),
+        allow_missing_columns: true,
+        // Row group statistics are looked up for every column of the schema, which panics on
+        // the files without some of them
+        use_statistics: false,
+        ..ScanArgsParquet::default()
+    };
+
+    Ok(LazyFrame::scan_parquet(dataset_directory.join(DATA_DIRECTORY), scan_arguments)?)
+}
+
+/// The rows of the result by the year of their quarter, e.g. `2024` for `2024-Q1`. Fails on a
+/// missing or malformed quarter rather than leaving its row out.
+fn year_partitions(result: &DataFrame) -> Result<Vec<(String, DataFrame)>, Box<dyn Error>> {
+    let years: Vec<&str> = result.column("quarter")?.str()?
+        .into_iter()
+        .enumerate()
+        .map(|(row, quarter)| {
+            let quarter = quarter.ok_or_else(|| format!("quarter missing in row {row}"))?;
+            match quarter.split_once("-Q") {
+                Some((year, number)) if year.len() == 4 && year.parse::<u16>().is_ok() && matches!(number, "1" | "2" | "3" | "4") => Ok(year),
+                _ => Err(format!("quarter '{quarter}' in row {row} is not like 2024-Q1")),
+            }
+        })
+        .collect::<Result<_, String>>()?;
+
+    years.iter()
+        .collect::<BTreeSet<&&str>>()
+        .into_iter()
+        .map(|year| {
+            let rows: BooleanChunked = years.iter().map(|row| Some(row == year)).collect();
+            Ok((year.to_string(), result.filter(&rows)?))
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use tempfile::tempdir;
+
+    use super::*;
+
+    fn result() -> DataFrame {
+        df![
+            "quarter" => &["2023-Q4", "2024-Q1", "2024-Q2"],
+            "us_gdp_usd_billions" => &[27900.0, 28000.0, 28600.0]
+        ].unwrap()
+    }
+
+    fn run_context(run_id: &str, day: u32) -> RunContext {
+        RunContext::new(run_id, Local.with_ymd_and_hms(2025, 6, day, 14, 15, 2).unwrap(), vec![], None)
+    }
+
+    fn read_manifest(directory: &str) -> Manifest {
+        serde_json::from_str(&fs::read_to_string(Path::new(directory).join(MANIFEST_FILE_NAME)).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_append_runs_by_run_date_and_scan() {
+        let directory = tempdir().unwrap();
+        let directory = directory.path().to_str().unwrap();
+        let run_date = DatasetPartitioning::RunDate;
+
+        let files = append(&result(), directory, run_date, "baseline", &run_context("20250629T141502", 29)).unwrap();
+        assert_eq!(files, vec!["result/scenario=baseline/run_date=2025-06-29/20250629T141502.parquet"]);
+        append(&result(), directory, run_date, "baseline", &run_context("20250630T141502", 30)).unwrap();
+        append(&result(), directory, run_date, "ppp_only", &run_context("20250630T141502", 30)).unwrap();
+        append(&result(), directory, run_date, "baseline", &run_context("20250630T141502", 30)).unwrap();
+
+        let scanned = scan(directory).unwrap()
+            .filter(col("scenario").eq(lit("baseline")))
+            .select([col("run_date"), col("run_id"), col("quarter")])
+            .collect()
+            .unwrap();
+        assert_eq!(scanned.height(), 6);
+        assert_eq!(scanned.column("run_date").unwrap().dtype(), &DataType::Date);
+
+        let manifest = read_manifest(directory);
+        assert_eq!(manifest.partition_columns, vec!["scenario", "run_date"]);
+        assert_eq!(manifest.runs.iter().map(|run| (run.run_id.as_str(), run.scenario.as_str())).collect::<Vec<_>>(),
+                   vec![("20250629T141502", "baseline"), ("20250630T141502", "baseline"), ("20250630T141502", "ppp_only")]);
+        assert_eq!(manifest.runs[0].columns, vec!["quarter", "us_gdp_usd_billions", "run_id"]);
+        assert_eq!(manifest.runs[0].rows, 3);
+    }
+
+    #[test]
+    fn test_scan_runs_with_other_columns() {
+        let directory = tempdir().unwrap();
+        let directory = directory.path().to_str().unwrap();
+        let baseline = df!["quarter" => &["2024-Q1"], "eu_gdp_usd_billions" => &[4700.0]].unwrap();
+        let ppp_only = df!["quarter" => &["2024-Q1"], "eu_gdp_ppp_billions" => &[6500.0]].unwrap();
+
+        append(&baseline, directory, DatasetPartitioning::RunDate, "baseline", &run_context("20250629T141502", 29)).unwrap();
+        append(&ppp_only, directory, DatasetPartitioning::RunDate, "ppp_only", &run_context("20250630T141502", 30)).unwrap();
+
+        let scanned = scan(directory).unwrap()
+            .select([col("scenario"), col("eu_gdp_usd_billions"), col("eu_gdp_ppp_billions")])
+            .sort(["scenario"], SortMultipleOptions::default())
+            .collect()
+            .unwrap();
+        assert_eq!(scanned.column("eu_gdp_usd_billions").unwrap().f64().unwrap().into_iter().collect::<Vec<_>>(), vec![Some(4700.0), None]);
+        assert_eq!(scanned.column("eu_gdp_ppp_billions").unwrap().f64().unwrap().into_iter().collect::<Vec<_>>(), vec![None, Some(6500.0)]);
+
+        let ppp_only = scan(directory).unwrap()
+            .filter(col("scenario").eq(lit("ppp_only")))
+            .select([col("eu_gdp_ppp_billions")])
+            .collect()
+            .unwrap();
+        assert_eq!(ppp_only.height(), 1);
+    }
+
+    #[test]
+    fn test_append_by_year() {
+        let directory = tempdir().unwrap();
+        let directory = directory.path().to_str().unwrap();
+        let files = append(&result(), directory, DatasetPartitioning::Year, "baseline", &run_context("20250630T141502", 30)).unwrap();
+
+        assert_eq!(files, vec!["result/scenario=baseline/year=2023/20250630T141502.parquet",
+                               "result/scenario=baseline/year=2024/20250630T141502.parquet"]);
+        let year_2024 = ParquetReader::new(File::open(Path::new(directory).join(&files[1])).unwrap()).finish().unwrap();
+        assert_eq!(year_2024.column("quarter").unwrap().str().unwrap().into_no_null_iter().collect::<Vec<_>>(), vec!["2024-Q1", "2024-Q2"]);
+    }
+
+    #[test]
+    fn test_append_again_removes_files_of_the_replaced_run() {
+        let directory = tempdir().unwrap();
+        let directory = directory.path().to_str().unwrap();
+        let context = run_context("20250630T141502", 30);
+        append(&result(), directory, DatasetPartitioning::Year, "baseline", &context).unwrap();
+
+        let files = append(&result().slice(1, 2), directory, DatasetPartitioning::Year, "baseline", &context).unwrap();
+
+        assert_eq!(files, vec!["result/scenario=baseline/year=2024/20250630T141502.parquet"]);
+        assert!(!Path::new(directory).join("result/scenario=baseline/year=2023/20250630T141502.parquet").exists());
+        assert_eq!(read_manifest(directory).runs.len(), 1);
+        assert_eq!(scan(directory).unwrap().collect().unwrap().height(), 2);
+    }
+
+    #[test]
+    fn test_append_by_year_rejects_missing_and_malformed_quarters() {
+        let directory = tempdir().unwrap();
+        let directory = directory.path().to_str().unwrap();
+        let context = run_context("20250630T141502", 30);
+        let missing = df!["quarter" => &[Some("2024-Q1"), None], "us_gdp_usd_billions" => &[28000.0, 28600.0]].unwrap();
+        let malformed = df!["quarter" => &["2024-Q1", "2024Q2"], "us_gdp_usd_billions" => &[28000.0, 28600.0]].unwrap();
+
+        let error = append(&missing, directory, DatasetPartitioning::Year, "baseline", &context).unwrap_err();
+        assert_eq!(error.to_string(), "quarter missing in row 1");
+        let error = append(&malformed, directory, DatasetPartitioning::Year, "baseline", &context).unwrap_err();
+        assert_eq!(error.to_string(), "quarter '2024Q2' in row 1 is not like 2024-Q1");
+        assert!(!Path::new(directory).join(DATA_DIRECTORY).exists());
+    }
+
+    #[test]
+    fn test_append_rejects_other_partitioning_and_invalid_scenario() {
+        let directory = tempdir().unwrap();
+        let directory = directory.path().to_str().unwrap();
+        append(&result(), directory, DatasetPartitioning::Year, "baseline", &run_context("20250630T141502", 30)).unwrap();
+
+        let error = append(&result(), directory, DatasetPartitioning::RunDate, "baseline", &run_context("20250630T141502", 30));
+        assert_eq!(error.unwrap_err().to_string(), format!("{directory} is partitioned by year, not run_date"));
+        assert!(append(&result(), directory, DatasetPartitioning::Year, "a=b", &run_context("20250630T141502", 30)).is_err());
+    }
+}
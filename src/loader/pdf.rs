@@ -0,0 +1,356 @@
+use std::convert::Infallible;
+use std::error::Error;
+use std::fmt::Write as _;
+use std::fs;
+use std::io::Write;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use plotters_backend::text_anchor::{HPos, VPos};
+use plotters_backend::{BackendColor, BackendCoord, BackendStyle, BackendTextStyle, DrawingBackend, DrawingErrorKind, FontTransform};
+
+const POINTS_PER_INCH: f64 = 72.0;
+
+/// Advance widths of the printable ASCII characters in Helvetica, in thousandths of the font size.
+const HELVETICA_WIDTHS: [u16; 95] = [
+    278, 278, 355, 556, 556, 889, 667, 191, 333, 333, 389, 584, 278, 333, 278, 278,
+    556, 556, 556, 556, 556, 556, 556, 556, 556, 556, 278, 278, 584, 584, 584, 556,
+    1015, 667, 667, 722, 722, 667, 611, 778, 722, 278, 500, 667, 556, 833, 722, 778,
+    667, 778, 722, 667, 611, 722, 667, 944, 667, 667, 611, 278, 278, 278, 469, 556,
+    333, 556, 556, 500, 556, 556, 278, 556, 556, 222, 222, 500, 222, 833, 556, 556,
+    556, 556, 333, 500, 278, 556, 500, 722, 500, 500, 500, 334, 260, 334, 584,
+];
+
+/// A page of vector graphics, drawn in pixels through a [`PdfBackend`] and scaled to its size
+/// in inches, so that the pixel size only sets the line widths and font sizes relative to it.
+pub struct PdfPage {
+    pub width_inches: f64,
+    pub height_inches: f64,
+    pub pixel_size: (u32, u32),
+    pub(super) content: String,
+    opacities: Vec<f64>,
+}
+
+impl PdfPage {
+    pub fn new(size_inches: (f64, f64), pixel_size: (u32, u32)) -> Self {
+        PdfPage {
+            width_inches: size_inches.0,
+            height_inches: size_inches.1,
+            pixel_size,
+            content: String::new(),
+            opacities: vec![],
+        }
+    }
+}
+
+/// Draws plotters charts on a [`PdfPage`] as PDF paths and Helvetica text, with the origin at
+/// the top left like the other backends.
+pub struct PdfBackend<'a> {
+    page: &'a mut PdfPage,
+}
+
+impl<'a> PdfBackend<'a> {
+    pub fn new(page: &'a mut PdfPage) -> Self {
+        PdfBackend { page }
+    }
+
+    /// Paints the path with the color, stroking it `stroke_width` wide or filling it without one.
+    fn paint(&mut self, color: BackendColor, stroke_width: Option<u32>, path: &str) {
+        if color.alpha == 0.0 {
+            return;
+        }
+
+        let (red, green, blue) = pdf_color(color);
+        let content = self.begin(color);
+        let _ = match stroke_width {
+            Some(stroke_width) => writeln!(content, "{red} {green} {blue} RG {stroke_width} w {path} S Q"),
+            None => writeln!(content, "{red} {green} {blue} rg {path} f Q"),
+        };
+    }
+
+    /// Saves the graphics state and sets the opacity of the color, to be restored with `Q`.
+    fn begin(&mut self, color: BackendColor) -> &mut String {
+        self.page.content.push_str("q ");
+        if color.alpha < 1.0 {
+            let index = opacity_index(&mut self.page.opacities, color.alpha);
+            let _ = write!(self.page.content, "/GS{index} gs ");
+        }
+        &mut self.page.content
+    }
+}
+
+impl DrawingBackend for PdfBackend<'_> {
+    type ErrorType = Infallible;
+
+    fn get_size(&self) -> (u32, u32) {
+        self.page.pixel_size
+    }
+
+    fn ensure_prepared(&mut self) -> Result<(), DrawingErrorKind<Infallible>> {
+        Ok(())
+    }
+
+    fn present(&mut self) -> Result<(), DrawingErrorKind<Infallible>> {
+        Ok(())
+    }
+
+    fn draw_pixel(&mut self, point: BackendCoord, color: BackendColor) -> Result<(), DrawingErrorKind<Infallible>> {
+        self.paint(color, None, &format!("{} {} 1 1 re", point.0, point.1));
+        Ok(())
+    }
+
+    fn draw_line<S: BackendStyle>(&mut self, from: BackendCoord, to: BackendCoord, style: &S) -> Result<(), DrawingErrorKind<Infallible>> {
+        self.paint(style.color(), Some(style.stroke_width()), &format!("{} {} m {} {} l", from.0, from.1, to.0, to.1));
+        Ok(())
+    }
+
+    fn draw_rect<S: BackendStyle>(&mut self,
+                                  upper_left: BackendCoord,
+                                  bottom_right: BackendCoord,
+                                  style: &S,
+                                  fill: bool) -> Result<(), DrawingErrorKind<Infallible>> {
+        let rectangle = format!("{} {} {} {} re", upper_left.0, upper_left.1, bottom_right.0 - upper_left.0, bottom_right.1 - upper_left.1);
+        self.paint(style.color(), (!fill).then(|| style.stroke_width()), &rectangle);
+        Ok(())
+    }
+
+    fn draw_path<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(&mut self, path: I, style: &S) -> Result<(), DrawingErrorKind<Infallible>> {
+        if let Some(path) = polyline(path) {
+            self.paint(style.color(), Some(style.stroke_width()), &path);
+        }
+        Ok(())
+    }
+
+    fn draw_circle<S: BackendStyle>(&mut self,
+                                    center: BackendCoord,
+                                    radius: u32,
+                                    style: &S,
+                                    fill: bool) -> Result<(), DrawingErrorKind<Infallible>> {
+        // Four cubic Béziers, each a quarter of the circle
+        let (x, y, r) = (center.0 as f64, center.1 as f64, radius as f64);
+        let k = 0.5523 * r;
+        let circle = format!("{} {y} m {} {} {} {} {x} {} c {} {} {} {} {} {y} c {} {} {} {} {x} {} c {} {} {} {} {} {y} c h",
+                             x + r,
+                             x + r, y + k, x + k, y + r, y + r,
+                             x - k, y + r, x - r, y + k, x - r,
+                             x - r, y - k, x - k, y - r, y - r,
+                             x + k, y - r, x + r, y - k, x + r);
+        self.paint(style.color(), (!fill).then(|| style.stroke_width()), &circle);
+        Ok(())
+    }
+
+    fn fill_polygon<S: BackendStyle, I: IntoIterator<Item = BackendCoord>>(&mut self, vertices: I, style: &S) -> Result<(), DrawingErrorKind<Infallible>> {
+        if let Some(polygon) = polyline(vertices) {
+            self.paint(style.color(), None, &format!("{polygon} h"));
+        }
+        Ok(())
+    }
+
+    fn draw_text<S: BackendTextStyle>(&mut self, text: &str, style: &S, pos: BackendCoord) -> Result<(), DrawingErrorKind<Infallible>> {
+        let color = style.color();
+        if color.alpha == 0.0 {
+            return Ok(());
+        }
+
+        // Sized and anchored like the SVG backend: the font size excludes the line gap, and the
+        // baseline sits 0.76 em below a top anchor
+        let font_size = style.size() / 1.24;
+        let width = text_width(text, font_size);
+        let along = match style.anchor().h_pos {
+            HPos::Left => 0.0,
+            HPos::Center => -width / 2.0,
+            HPos::Right => -width,
+        };
+        let across = font_size * match style.anchor().v_pos {
+            VPos::Top => 0.76,
+            VPos::Center => 0.26,
+            VPos::Bottom => -0.26,
+        };
+        let (cos, sin) = match style.transform() {
+            FontTransform::None => (1.0, 0.0),
+            FontTransform::Rotate90 => (0.0, 1.0),
+            FontTransform::Rotate180 => (-1.0, 0.0),
+            FontTransform::Rotate270 => (0.0, -1.0),
+        };
+        let x = pos.0 as f64 + along * cos - across * sin;
+        let y = pos.1 as f64 + along * sin + across * cos;
+
+        // The page is flipped to put the origin at the top left, so the text matrix flips the
+        // glyphs back before rotating them
+        let (red, green, blue) = pdf_color(color);
+        let content = self.begin(color);
+        let _ = writeln!(content, "{red} {green} {blue} rg BT /F1 {font_size:.2} Tf {cos} {sin} {sin} {} {x:.2} {y:.2} Tm ({}) Tj ET Q",
+                         -cos, pdf_string(text));
+
+        Ok(())
+    }
+
+    fn estimate_text_size<S: BackendTextStyle>(&self, text: &str, style: &S) -> Result<(u32, u32), DrawingErrorKind<Infallible>> {
+        Ok((text_width(text, style.size() / 1.24).round() as u32, style.size().round() as u32))
+    }
+}
+
+fn pdf_color(color: BackendColor) -> (String, String, String) {
+    let (red, green, blue) = color.rgb;
+    let channel = |value: u8| format!("{:.3}", value as f64 / 255.0);
+    (channel(red), channel(green), channel(blue))
+}
+
+/// The index of the graphics state with the opacity, added to the page resources when new.
+fn opacity_index(opacities: &mut Vec<f64>, opacity: f64) -> usize {
+    opacities.iter().position(|existing| *existing == opacity).unwrap_or_else(|| {
+        opacities.push(opacity);
+        opacities.len() - 1
+    })
+}
+
+fn polyline<I: IntoIterator<Item = BackendCoord>>(points: I) -> Option<String> {
+    let mut points = points.into_iter();
+    let (x, y) = points.next()?;
+    Some(points.fold(format!("{x} {y} m"), |path, (x, y)| format!("{path} {x} {y} l")))
+}
+
+fn text_width(text: &str, font_size: f64) -> f64 {
+    let thousandths: f64 = text.chars()
+        .map(|character| match character {
+            ' '..='~' => HELVETICA_WIDTHS[character as usize - 32] as f64,
+            _ => 556.0,
+        })
+        .sum();
+    thousandths * font_size / 1000.0
+}
+
+/// A literal string in WinAnsiEncoding, which matches Latin-1 above 0xA0. Characters outside it
+/// become `?`.
+fn pdf_string(text: &str) -> String {
+    text.chars()
+        .map(|character| match character {
+            '(' | ')' | '\\' => format!("\\{character}"),
+            ' '..='~' => character.to_string(),
+            '\u{a0}'..='\u{ff}' => format!("\\{:o}", character as u32),
+            _ => "?".to_string(),
+        })
+        .collect()
+}
+
+/// Writes the pages to a PDF file, each content stream compressed with Flate.
+pub fn write_pdf(path: &str, pages: &[PdfPage]) -> Result<(), Box<dyn Error>> {
+    fs::write(path, pdf_bytes(pages)?)?;
+    Ok(())
+}
+
+fn pdf_bytes(pages: &[PdfPage]) -> Result<Vec<u8>, Box<dyn Error>> {
+    // Objects 1 to 3 are the catalog, the page tree and the font, every page takes two more:
+    // the page and its content stream.
+    let page_ids: Vec<usize> = (0..pages.len()).map(|index| 4 + 2 * index).collect();
+
+    let mut objects: Vec<Vec<u8>> = vec![
+        b"<< /Type /Catalog /Pages 2 0 R >>".to_vec(),
+        format!("<< /Type /Pages /Kids [{}] /Count {} >>",
+                page_ids.iter().map(|id| format!("{id} 0 R")).collect::<Vec<_>>().join(" "),
+                pages.len()).into_bytes(),
+        b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec(),
+    ];
+
+    for (page, page_id) in pages.iter().zip(&page_ids) {
+        let (width, height) = (page.width_inches * POINTS_PER_INCH, page.height_inches * POINTS_PER_INCH);
+        let (pixel_width, pixel_height) = page.pixel_size;
+        let graphics_states: String = page.opacities.iter()
+            .enumerate()
+            .map(|(index, opacity)| format!("/GS{index} << /ca {opacity} /CA {opacity} >> "))
+            .collect();
+
+        objects.push(format!("<< /Type /Page /Parent 2 0 R /MediaBox [0 0 {width:.2} {height:.2}] \
+                              /Resources << /Font << /F1 3 0 R >> /ExtGState << {graphics_states}>> >> /Contents {} 0 R >>",
+                             page_id + 1).into_bytes());
+
+        // Pixels to points, with the origin moved to the top left
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        write!(encoder, "{:.6} 0 0 {:.6} 0 {height:.2} cm\n{}",
+               width / pixel_width as f64, -height / pixel_height as f64, page.content)?;
+        objects.push(stream("/Filter /FlateDecode ".to_string(), encoder.finish()?));
+    }
+
+    let mut bytes: Vec<u8> = b"%PDF-1.4\n".to_vec();
+    let mut offsets: Vec<usize> = vec![];
+
+    for (index, object) in objects.iter().enumerate() {
+        offsets.push(bytes.len());
+        bytes.extend(format!("{} 0 obj\n", index + 1).into_bytes());
+        bytes.extend(object);
+        bytes.extend(b"\nendobj\n");
+    }
+
+    let xref_offset = bytes.len();
+    bytes.extend(format!("xref\n0 {}\n0000000000 65535 f \n", objects.len() + 1).into_bytes());
+    for offset in offsets {
+        bytes.extend(format!("{offset:010} 00000 n \n").into_bytes());
+    }
+    bytes.extend(format!("trailer\n<< /Size {} /Root 1 0 R >>\nstartxref\n{xref_offset}\n%%EOF\n", objects.len() + 1).into_bytes());
+
+    Ok(bytes)
+}
+
+fn stream(dictionary: String, data: Vec<u8>) -> Vec<u8> {
+    let mut bytes = format!("<< {dictionary}/Length {} >>\nstream\n", data.len()).into_bytes();
+    bytes.extend(data);
+    bytes.extend(b"\nendstream");
+    bytes
+}
+
+
+#[cfg(test)]
+mod tests {
+    use plotters::prelude::*;
+    use plotters::style::text_anchor::{HPos, Pos, VPos};
+
+    use super::{pdf_bytes, PdfBackend, PdfPage};
+
+    fn page(width_inches: f64) -> PdfPage {
+        PdfPage::new((width_inches, 2.0), (200, 100))
+    }
+
+    #[test]
+    fn test_pdf_bytes_lists_every_page_with_valid_offsets() {
+        let bytes = pdf_bytes(&[page(4.0), page(3.0)]).unwrap();
+        let text = String::from_utf8_lossy(&bytes);
+
+        assert!(text.starts_with("%PDF-1.4\n"));
+        assert!(text.ends_with("%%EOF\n"));
+        assert!(text.contains("/Kids [4 0 R 6 0 R] /Count 2"));
+        assert!(text.contains("/MediaBox [0 0 288.00 144.00]"));
+        assert!(text.contains("/MediaBox [0 0 216.00 144.00]"));
+        assert!(text.contains("/BaseFont /Helvetica"));
+
+        let xref_offset: usize = text.lines().rev().nth(1).unwrap().parse().unwrap();
+        let xref = std::str::from_utf8(&bytes[xref_offset..]).unwrap();
+        assert!(xref.starts_with("xref\n0 8\n"));
+
+        let offsets: Vec<usize> = xref.lines()
+            .skip(3)
+            .take(7)
+            .map(|line| line[..10].parse().unwrap())
+            .collect();
+        for (index, offset) in offsets.into_iter().enumerate() {
+            assert!(bytes[offset..].starts_with(format!("{} 0 obj\n", index + 1).as_bytes()));
+        }
+    }
+
+    #[test]
+    fn test_pdf_backend_draws_paths_and_text() {
+        let mut page = page(4.0);
+        let root = PdfBackend::new(&mut page).into_drawing_area();
+        root.draw(&PathElement::new([(10, 20), (30, 40), (50, 20)], RED.stroke_width(3))).unwrap();
+        root.draw(&Rectangle::new([(0, 0), (20, 10)], BLUE.mix(0.5).filled())).unwrap();
+        root.draw(&Text::new("S&P (500)", (100, 50), TextStyle::from(("sans-serif", 12.4).into_font()).pos(Pos::new(HPos::Center, VPos::Top)))).unwrap();
+        drop(root);
+
+        assert_eq!(page.content.lines().collect::<Vec<_>>(), vec![
+            "q 1.000 0.000 0.000 RG 3 w 10 20 m 30 40 l 50 20 l S Q",
+            "q /GS0 gs 0.000 0.000 1.000 rg 0 0 20 10 re f Q",
+            "q 0.000 0.000 0.000 rg BT /F1 10.00 Tf 1 0 0 -1 76.94 57.60 Tm (S&P \\(500\\)) Tj ET Q",
+        ]);
+        assert_eq!(page.opacities, vec![0.5]);
+        assert!(String::from_utf8_lossy(&pdf_bytes(&[page]).unwrap()).contains("/GS0 << /ca 0.5 /CA 0.5 >>"));
+    }
+}
@@ -0,0 +1,209 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use polars::prelude::*;
+
+use crate::model::data_model::{ResultDiff, Revision};
+
+const RELATIVE_TOLERANCE: f64 = 1e-9;
+
+/// Compares two results quarter by quarter. Every value of a new or dropped quarter is listed,
+/// values of quarters in both results only when they changed. Columns that exist on one side
+/// only are named but their values are not listed.
+pub fn diff_results(previous: &DataFrame, current: &DataFrame) -> ResultDiff {
+    let previous_values = numeric_values(previous);
+    let current_values = numeric_values(current);
+
+    let previous_quarters = quarters(previous);
+    let current_quarters = quarters(current);
+
+    let previous_columns: BTreeSet<&String> = previous_values.keys().collect();
+    let current_columns: BTreeSet<&String> = current_values.keys().collect();
+    let shared_columns: Vec<&String> = current_columns.intersection(&previous_columns).copied().collect();
+
+    let mut revisions: Vec<Revision> = vec![];
+    let all_quarters: BTreeSet<&String> = previous_quarters.keys().chain(current_quarters.keys()).collect();
+
+    for quarter in &all_quarters {
+        match (previous_quarters.get(*quarter), current_quarters.get(*quarter)) {
+            (Some(previous_row), Some(current_row)) => {
+                for column in &shared_columns {
+                    let old_value = previous_values[*column][*previous_row];
+                    let new_value = current_values[*column][*current_row];
+
+                    if changed(old_value, new_value) {
+                        revisions.push(Revision::new(column, quarter, "revised", old_value, new_value));
+                    }
+                }
+            },
+            (None, Some(current_row)) => {
+                for column in &current_columns {
+                    if let Some(new_value) = current_values[*column][*current_row] {
+                        revisions.push(Revision::new(column, quarter, "new_quarter", None, Some(new_value)));
+                    }
+                }
+            },
+            (Some(previous_row), None) => {
+                for column in &previous_columns {
+                    if let Some(old_value) = previous_values[*column][*previous_row] {
+                        revisions.push(Revision::new(column, quarter, "dropped_quarter", Some(old_value), None));
+                    }
+                }
+            },
+            (None, None) => {},
+        }
+    }
+
+    let only_in = |quarters: &BTreeMap<String, usize>, other: &BTreeMap<String, usize>| -> Vec<String> {
+        quarters.keys().filter(|quarter| !other.contains_key(*quarter)).cloned().collect()
+    };
+
+    ResultDiff {
+        new_quarters: only_in(&current_quarters, &previous_quarters),
+        dropped_quarters: only_in(&previous_quarters, &current_quarters),
+        new_columns: current_columns.difference(&previous_columns).map(|column| column.to_string()).collect(),
+        dropped_columns: previous_columns.difference(&current_columns).map(|column| column.to_string()).collect(),
+        revisions,
+    }
+}
+
+/// One line describing the diff, e.g. for the log.
+pub fn diff_summary(result_diff: &ResultDiff) -> String {
+    let revised: Vec<&Revision> = result_diff.revisions.iter().filter(|revision| revision.change == "revised").collect();
+
+    let mut revised_per_column: HashMap<&str, usize> = HashMap::new();
+    for revision in &revised {
+        *revised_per_column.entry(revision.column.as_str()).or_default() += 1;
+    }
+    let mut most_revised: Vec<(&str, usize)> = revised_per_column.iter().map(|(column, count)| (*column, *count)).collect();
+    most_revised.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(b.0)));
+
+    let list = |values: &[String]| if values.is_empty() { String::new() } else { format!(" ({})", values.join(", ")) };
+
+    let mut summary = format!("{} new quarters{}, {} dropped quarters{}, {} revised values in {} columns",
+                              result_diff.new_quarters.len(),
+                              list(&result_diff.new_quarters),
+                              result_diff.dropped_quarters.len(),
+                              list(&result_diff.dropped_quarters),
+                              revised.len(),
+                              revised_per_column.len());
+
+    if !most_revised.is_empty() {
+        let most_revised: Vec<String> = most_revised.iter().take(5).map(|(column, count)| format!("{column} ({count})")).collect();
+        summary.push_str(&format!(", most revised: {}", most_revised.join(", ")));
+    }
+    if !result_diff.new_columns.is_empty() {
+        summary.push_str(&format!(", new columns: {}", result_diff.new_columns.join(", ")));
+    }
+    if !result_diff.dropped_columns.is_empty() {
+        summary.push_str(&format!(", dropped columns: {}", result_diff.dropped_columns.join(", ")));
+    }
+
+    summary
+}
+
+/// Lays the revisions out as one row per column and quarter with old value, new value and delta.
+pub fn revision_dataframe(revisions: &[Revision]) -> DataFrame {
+    df![
+        "quarter" => revisions.iter().map(|revision| revision.quarter.clone()).collect::<Vec<String>>(),
+        "column" => revisions.iter().map(|revision| revision.column.clone()).collect::<Vec<String>>(),
+        "change" => revisions.iter().map(|revision| revision.change).collect::<Vec<&str>>(),
+        "old_value" => revisions.iter().map(|revision| revision.old_value).collect::<Vec<Option<f64>>>(),
+        "new_value" => revisions.iter().map(|revision| revision.new_value).collect::<Vec<Option<f64>>>(),
+        "delta" => revisions.iter()
+            .map(|revision| Some(revision.new_value? - revision.old_value?))
+            .collect::<Vec<Option<f64>>>()
+    ].unwrap()
+}
+
+fn changed(old_value: Option<f64>, new_value: Option<f64>) -> bool {
+    match (old_value, new_value) {
+        (Some(old_value), Some(new_value)) => (new_value - old_value).abs() > RELATIVE_TOLERANCE * old_value.abs().max(1.0),
+        (None, None) => false,
+        _ => true,
+    }
+}
+
+fn quarters(result: &DataFrame) -> BTreeMap<String, usize> {
+    result.column("quarter").unwrap().str().unwrap()
+        .into_iter()
+        .enumerate()
+        .filter_map(|(row, quarter)| Some((quarter?.to_string(), row)))
+        .collect()
+}
+
+fn numeric_values(result: &DataFrame) -> BTreeMap<String, Vec<Option<f64>>> {
+    result.get_columns()
+        .iter()
+        .filter(|column| column.dtype().is_primitive_numeric())
+        .map(|column| {
+            let values = column.cast(&DataType::Float64).unwrap();
+            (column.name().to_string(), values.f64().unwrap().into_iter().collect())
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn previous() -> DataFrame {
+        df![
+            "quarter" => &["2024-Q1", "2024-Q2", "2024-Q3"],
+            "us_gdp_usd_billions" => &[Some(100.0), Some(101.0), Some(102.0)],
+            "eu_inflation_perc" => &[Some(2.0), None, Some(2.5)],
+            "us_recession_nber" => &[0, 0, 0]
+        ].unwrap()
+    }
+
+    fn current() -> DataFrame {
+        df![
+            "quarter" => &["2024-Q2", "2024-Q3", "2024-Q4"],
+            "us_gdp_usd_billions" => &[Some(101.0), Some(102.5), Some(103.0)],
+            "eu_inflation_perc" => &[Some(2.2), Some(2.5), None],
+            "sp500_usd" => &[5000.0, 5500.0, 5800.0]
+        ].unwrap()
+    }
+
+    #[test]
+    fn test_diff_results() {
+        let result_diff = diff_results(&previous(), &current());
+
+        assert_eq!(result_diff.new_quarters, vec!["2024-Q4"]);
+        assert_eq!(result_diff.dropped_quarters, vec!["2024-Q1"]);
+        assert_eq!(result_diff.new_columns, vec!["sp500_usd"]);
+        assert_eq!(result_diff.dropped_columns, vec!["us_recession_nber"]);
+        assert_eq!(result_diff.revisions, vec![
+            Revision::new("eu_inflation_perc", "2024-Q1", "dropped_quarter", Some(2.0), None),
+            Revision::new("us_gdp_usd_billions", "2024-Q1", "dropped_quarter", Some(100.0), None),
+            Revision::new("us_recession_nber", "2024-Q1", "dropped_quarter", Some(0.0), None),
+            Revision::new("eu_inflation_perc", "2024-Q2", "revised", None, Some(2.2)),
+            Revision::new("us_gdp_usd_billions", "2024-Q3", "revised", Some(102.0), Some(102.5)),
+            Revision::new("sp500_usd", "2024-Q4", "new_quarter", None, Some(5800.0)),
+            Revision::new("us_gdp_usd_billions", "2024-Q4", "new_quarter", None, Some(103.0)),
+        ]);
+    }
+
+    #[test]
+    fn test_diff_results_of_identical_results_is_empty() {
+        let result_diff = diff_results(&previous(), &previous());
+
+        assert!(result_diff.revisions.is_empty());
+        assert_eq!(diff_summary(&result_diff), "0 new quarters, 0 dropped quarters, 0 revised values in 0 columns");
+    }
+
+    #[test]
+    fn test_diff_summary_and_revision_dataframe() {
+        let result_diff = diff_results(&previous(), &current());
+
+        assert_eq!(diff_summary(&result_diff),
+                   "1 new quarters (2024-Q4), 1 dropped quarters (2024-Q1), 2 revised values in 2 columns, \
+                    most revised: eu_inflation_perc (1), us_gdp_usd_billions (1), \
+                    new columns: sp500_usd, dropped columns: us_recession_nber");
+
+        let revisions = revision_dataframe(&result_diff.revisions);
+        assert_eq!(revisions.shape(), (7, 6));
+        assert_eq!(revisions.column("delta").unwrap().f64().unwrap().get(3), None);
+        assert_eq!(revisions.column("delta").unwrap().f64().unwrap().get(4), Some(0.5));
+    }
+}
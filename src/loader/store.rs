@@ -0,0 +1,261 @@
+use std::error::Error;
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+
+use chrono::{DateTime, Local};
+use log::{info, warn};
+use polars::prelude::*;
+
+use crate::model::data_model::DatasetWithConfig;
+
+pub const STORE_DIRECTORY: &str = "outputs/store";
+
+const RUN_PARTITION_PREFIX: &str = "run_id=";
+
+/// The stages of a run kept in the store: the extracted datasets, the quarterly datasets after
+/// normalization and currency conversion, and the joined result.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum StoreLayer {
+    Raw,
+    Processed,
+    Result,
+}
+
+impl StoreLayer {
+    pub fn name(&self) -> &'static str {
+        match self {
+            StoreLayer::Raw => "raw",
+            StoreLayer::Processed => "processed",
+            StoreLayer::Result => "result",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        [StoreLayer::Raw, StoreLayer::Processed, StoreLayer::Result].into_iter().find(|layer| layer.name() == name)
+    }
+}
+
+/// Run ids sort chronologically, e.g. `20250630T141502`.
+pub fn new_run_id(started_at: DateTime<Local>) -> String {
+    started_at.format("%Y%m%dT%H%M%S").to_string()
+}
+
+/// Writes `<store_directory>/<layer>/<dataset>/run_id=<run_id>/data.parquet`.
+pub fn write_dataset(store_directory: &str,
+                     layer: StoreLayer,
+                     dataset: &str,
+                     run_id: &str,
+                     dataframe: &DataFrame) -> Result<(), Box<dyn Error>> {
+    let partition = dataset_directory(store_directory, layer, dataset).join(format!("{RUN_PARTITION_PREFIX}{run_id}"));
+    fs::create_dir_all(&partition)?;
+
+    let mut dataframe = dataframe.clone();
+    ParquetWriter::new(File::create(partition.join("data.parquet"))?)
+        .with_compression(ParquetCompression::Snappy)
+        .finish(&mut dataframe)?;
+
+    Ok(())
+}
+
+/// Stores the extracted datasets of a run with a `fetched_at` column holding when each row was
+/// fetched. Rows unchanged since the latest stored run of their dataset, such as those merged
+/// from the cache in update mode, keep the time they were first fetched; only new and revised
+/// rows get `fetched_at`. Datasets that cannot be written are skipped with a warning.
+pub fn store_raw_datasets(store_directory: &str, run_id: &str, datasets: &[DatasetWithConfig], fetched_at: DateTime<Local>) {
+    let raw_datasets: Vec<(&str, DataFrame)> = datasets.iter()
+        .map(|dataset| {
+            let previous = read_dataset(store_directory, StoreLayer::Raw, dataset.name, None).ok();
+            (dataset.name, with_fetch_timestamps(&dataset.dataframe, previous.as_ref(), fetched_at))
+        })
+        .collect();
+
+    store_dataframes(store_directory, StoreLayer::Raw, run_id, &raw_datasets);
+}
+
+/// Stores the quarterly datasets of a run, as they are before being joined.
+pub fn store_processed_datasets(store_directory: &str, run_id: &str, datasets: &[DatasetWithConfig]) {
+    let processed_datasets: Vec<(&str, DataFrame)> = datasets.iter()
+        .map(|dataset| (dataset.name, dataset.dataframe.clone()))
+        .collect();
+
+    store_dataframes(store_directory, StoreLayer::Processed, run_id, &processed_datasets);
+}
+
+/// Reads a stored dataset at `run_id`, or at the latest run holding it when `run_id` is `None`.
+pub fn read_dataset(store_directory: &str,
+                    layer: StoreLayer,
+                    dataset: &str,
+                    run_id: Option<&str>) -> Result<DataFrame, Box<dyn Error>> {
+    let runs = list_runs(store_directory, layer, dataset);
+
+    let run_id = match run_id {
+        Some(run_id) if runs.iter().any(|run| run == run_id) => run_id,
+        Some(run_id) => return Err(format!("no {} {dataset} stored for run {run_id}, stored runs: {}", layer.name(), runs.join(", ")).into()),
+        None => runs.last().ok_or(format!("no {} {dataset} stored in {store_directory}", layer.name()))?,
+    };
+
+    let path = dataset_directory(store_directory, layer, dataset)
+        .join(format!("{RUN_PARTITION_PREFIX}{run_id}"))
+        .join("data.parquet");
+
+    Ok(ParquetReader::new(File::open(path)?).finish()?)
+}
+
+/// The runs holding a stored dataset, oldest first.
+pub fn list_runs(store_directory: &str, layer: StoreLayer, dataset: &str) -> Vec<String> {
+    let mut runs: Vec<String> = sub_directories(&dataset_directory(store_directory, layer, dataset))
+        .into_iter()
+        .filter_map(|name| name.strip_prefix(RUN_PARTITION_PREFIX).map(|run_id| run_id.to_string()))
+        .collect();
+    runs.sort();
+    runs
+}
+
+/// The datasets stored in a layer, in alphabetical order.
+pub fn list_datasets(store_directory: &str, layer: StoreLayer) -> Vec<String> {
+    let mut datasets = sub_directories(&Path::new(store_directory).join(layer.name()));
+    datasets.sort();
+    datasets
+}
+
+fn store_dataframes(store_directory: &str, layer: StoreLayer, run_id: &str, dataframes: &[(&str, DataFrame)]) {
+    for (dataset, dataframe) in dataframes {
+        if let Err(error) = write_dataset(store_directory, layer, dataset, run_id, dataframe) {
+            warn!("could not store {} {dataset} of run {run_id}: {error}", layer.name());
+        }
+    }
+
+    info!("stored {} {} datasets of run {run_id} in {store_directory}", dataframes.len(), layer.name());
+}
+
+/// Adds `fetched_at`, taken from the identical row of `previous` where there is one.
+fn with_fetch_timestamps(dataframe: &DataFrame, previous: Option<&DataFrame>, fetched_at: DateTime<Local>) -> DataFrame {
+    let fetched_at = fetched_at.to_rfc3339();
+
+    let stamped = previous.and_then(|previous| {
+        let mut columns = dataframe.get_column_names_owned();
+        let keys: Vec<Expr> = columns.iter().map(|column| col(column.clone())).collect();
+        columns.push("fetched_at".into());
+        let previous = previous.select(columns).ok()?.unique_stable(None, UniqueKeepStrategy::First, None).ok()?;
+
+        let join_args = JoinArgs { join_nulls: true, maintain_order: MaintainOrderJoin::Left, ..JoinArgs::new(JoinType::Left) };
+        dataframe.clone().lazy()
+            .join(previous.lazy(), keys.clone(), keys, join_args)
+            .with_column(col("fetched_at").fill_null(lit(fetched_at.as_str())))
+            .collect()
+            .ok()
+            .filter(|stamped| stamped.height() == dataframe.height())
+    });
+
+    stamped.unwrap_or_else(|| {
+        let mut dataframe = dataframe.clone();
+        let fetched_at = Column::new("fetched_at".into(), vec![fetched_at.as_str(); dataframe.height()]);
+        dataframe.with_column(fetched_at).unwrap();
+        dataframe
+    })
+}
+
+fn dataset_directory(store_directory: &str, layer: StoreLayer, dataset: &str) -> PathBuf {
+    Path::new(store_directory).join(layer.name()).join(dataset)
+}
+
+fn sub_directories(directory: &Path) -> Vec<String> {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return vec![];
+    };
+
+    entries.filter_map(|entry| entry.ok())
+        .filter(|entry| entry.path().is_dir())
+        .filter_map(|entry| entry.file_name().into_string().ok())
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use chrono::TimeZone;
+    use tempfile::tempdir;
+
+    use crate::datasets_config::datasets_config::get_all_datasets_configs;
+
+    use super::*;
+
+    fn dataframe(value: f64) -> DataFrame {
+        df![
+            "quarter" => &["2024-Q1", "2024-Q2"],
+            "value" => &[1.0, value]
+        ].unwrap()
+    }
+
+    #[test]
+    fn test_new_run_id() {
+        let started_at = Local.with_ymd_and_hms(2025, 6, 30, 14, 15, 2).unwrap();
+
+        assert_eq!(new_run_id(started_at), "20250630T141502");
+    }
+
+    #[test]
+    fn test_read_dataset_at_run_or_latest_run() {
+        let store_directory = tempdir().unwrap();
+        let store_directory = store_directory.path().to_str().unwrap();
+
+        write_dataset(store_directory, StoreLayer::Result, "result", "20250331T080000", &dataframe(2.0)).unwrap();
+        write_dataset(store_directory, StoreLayer::Result, "result", "20250630T080000", &dataframe(2.5)).unwrap();
+
+        assert_eq!(list_runs(store_directory, StoreLayer::Result, "result"), vec!["20250331T080000", "20250630T080000"]);
+        assert_eq!(list_datasets(store_directory, StoreLayer::Result), vec!["result"]);
+        assert_eq!(read_dataset(store_directory, StoreLayer::Result, "result", Some("20250331T080000")).unwrap(), dataframe(2.0));
+        assert_eq!(read_dataset(store_directory, StoreLayer::Result, "result", None).unwrap(), dataframe(2.5));
+
+        let error = read_dataset(store_directory, StoreLayer::Result, "result", Some("20240101T000000")).unwrap_err();
+        assert_eq!(error.to_string(), "no result result stored for run 20240101T000000, stored runs: 20250331T080000, 20250630T080000");
+        assert!(read_dataset(store_directory, StoreLayer::Raw, "result", None).is_err());
+    }
+
+    #[test]
+    fn test_store_raw_datasets_adds_fetch_timestamp() {
+        let store_directory = tempdir().unwrap();
+        let store_directory = store_directory.path().to_str().unwrap();
+        let dataset_config = get_all_datasets_configs()[1];
+        let fetched_at = Local.with_ymd_and_hms(2025, 6, 30, 14, 15, 2).unwrap();
+
+        store_raw_datasets(store_directory,
+                           "20250630T141502",
+                           &[DatasetWithConfig::new(dataset_config.name, dataframe(2.0), dataset_config)],
+                           fetched_at);
+
+        let raw = read_dataset(store_directory, StoreLayer::Raw, dataset_config.name, None).unwrap();
+        assert_eq!(raw.get_column_names(), vec!["quarter", "value", "fetched_at"]);
+        assert_eq!(raw.column("fetched_at").unwrap().str().unwrap().get(1), Some(fetched_at.to_rfc3339().as_str()));
+    }
+
+    #[test]
+    fn test_store_raw_datasets_keeps_fetch_timestamp_of_unchanged_rows() {
+        let store_directory = tempdir().unwrap();
+        let store_directory = store_directory.path().to_str().unwrap();
+        let dataset_config = get_all_datasets_configs()[1];
+        let first_fetched_at = Local.with_ymd_and_hms(2025, 3, 31, 8, 0, 0).unwrap();
+        let fetched_at = Local.with_ymd_and_hms(2025, 6, 30, 14, 15, 2).unwrap();
+        let dataset = |dataframe: DataFrame| [DatasetWithConfig::new(dataset_config.name, dataframe, dataset_config)];
+
+        store_raw_datasets(store_directory,
+                           "20250331T080000",
+                           &dataset(df!["quarter" => &["2024-Q1", "2024-Q2", "2024-Q3"], "value" => &[Some(1.0), Some(2.0), None]].unwrap()),
+                           first_fetched_at);
+        store_raw_datasets(store_directory,
+                           "20250630T141502",
+                           &dataset(df!["quarter" => &["2024-Q1", "2024-Q2", "2024-Q3", "2024-Q4"], "value" => &[Some(1.0), Some(2.5), None, Some(4.0)]].unwrap()),
+                           fetched_at);
+
+        let raw = read_dataset(store_directory, StoreLayer::Raw, dataset_config.name, None).unwrap();
+        assert_eq!(raw.column("quarter").unwrap().str().unwrap().into_no_null_iter().collect::<Vec<_>>(), vec!["2024-Q1", "2024-Q2", "2024-Q3", "2024-Q4"]);
+        assert_eq!(raw.column("fetched_at").unwrap().str().unwrap().into_no_null_iter().collect::<Vec<_>>(),
+                   vec![first_fetched_at.to_rfc3339(), fetched_at.to_rfc3339(), first_fetched_at.to_rfc3339(), fetched_at.to_rfc3339()]);
+    }
+
+    #[test]
+    fn test_store_layer_from_name() {
+        assert_eq!(StoreLayer::from_name("processed"), Some(StoreLayer::Processed));
+        assert_eq!(StoreLayer::from_name("joined"), None);
+    }
+}
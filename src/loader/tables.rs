@@ -0,0 +1,264 @@
+use std::fs;
+
+use log::{info, warn};
+use polars::prelude::*;
+
+use crate::datasets_config::datasets_config::{NumberFormat, TableExportConfig};
+use crate::transformer::stats::column_values;
+
+pub const TABLE_DIRECTORY: &str = "outputs/tables";
+
+const DEFAULT_FORMAT: NumberFormat = NumberFormat::Decimal { decimals: 2, thousands_separator: true };
+
+/// A column to export, under its header.
+struct ExportedColumn {
+    column: String,
+    header: String,
+    format: NumberFormat,
+}
+
+/// A table of formatted cells. The first column holds labels, the others numbers.
+struct Table {
+    headers: Vec<String>,
+    rows: Vec<Vec<String>>,
+}
+
+/// Writes the latest quarters of the result and summary statistics of the exported columns as
+/// Markdown to `outputs/tables/<file name>.md` and as LaTeX `tabular`s to `<file name>.tex`.
+/// Columns missing from the result are skipped with a warning.
+pub fn generate_tables(result: &DataFrame, table_export_config: &TableExportConfig) {
+    let columns = exported_columns(result, table_export_config);
+    let latest = latest_quarters_table(result, &columns, table_export_config.quarters);
+    let summary = summary_statistics_table(result, &columns);
+
+    let latest_title = format!("Latest {} quarters", latest.rows.len());
+    let markdown = format!("## {latest_title}\n\n{}\n## Summary statistics\n\n{}", markdown_table(&latest), markdown_table(&summary));
+    let latex = format!("% {latest_title}, requires the booktabs package\n{}\n% Summary statistics\n{}", latex_table(&latest), latex_table(&summary));
+
+    if let Err(error) = fs::create_dir_all(TABLE_DIRECTORY) {
+        warn!("could not create {TABLE_DIRECTORY}: {error}");
+        return;
+    }
+
+    for (extension, content) in [("md", markdown), ("tex", latex)] {
+        let file_path = format!("{TABLE_DIRECTORY}/{}.{extension}", table_export_config.file_name);
+
+        match fs::write(&file_path, content) {
+            Ok(()) => info!("tables saved as {file_path}"),
+            Err(error) => warn!("could not write {file_path}: {error}"),
+        }
+    }
+}
+
+/// Formats `value`, e.g. `12,345.68` with two decimals and thousands separators, or `2.5%`
+/// as a percentage with one decimal.
+pub fn format_value(value: f64, format: NumberFormat) -> String {
+    let (decimals, thousands_separator, suffix) = match format {
+        NumberFormat::Decimal { decimals, thousands_separator } => (decimals, thousands_separator, ""),
+        NumberFormat::Percent { decimals } => (decimals, false, "%"),
+    };
+
+    let formatted = format!("{:.*}", decimals, value.abs());
+    let (integer, fraction) = match formatted.split_once('.') {
+        Some((integer, fraction)) => (integer, format!(".{fraction}")),
+        None => (formatted.as_str(), String::new()),
+    };
+
+    let mut grouped = String::new();
+    for (index, digit) in integer.chars().enumerate() {
+        if thousands_separator && index > 0 && (integer.len() - index) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(digit);
+    }
+
+    // Negative values that round to zero are written without a sign
+    let sign = if value < 0.0 && formatted.chars().any(|digit| digit != '0' && digit != '.') { "-" } else { "" };
+    format!("{sign}{grouped}{fraction}{suffix}")
+}
+
+fn exported_columns(result: &DataFrame, table_export_config: &TableExportConfig) -> Vec<ExportedColumn> {
+    if table_export_config.columns.is_empty() {
+        return result.get_columns()
+            .iter()
+            .filter(|column| column.dtype() == &DataType::Float64)
+            .map(|column| ExportedColumn { column: column.name().to_string(), header: column.name().to_string(), format: DEFAULT_FORMAT })
+            .collect();
+    }
+
+    table_export_config.columns.iter()
+        .filter(|column| {
+            let exists = result.column(column.column).is_ok();
+            if !exists {
+                warn!("leaving {} out of the exported tables, it is not in the result", column.column);
+            }
+            exists
+        })
+        .map(|column| ExportedColumn { column: column.column.to_string(), header: column.header.to_string(), format: column.format })
+        .collect()
+}
+
+fn latest_quarters_table(result: &DataFrame, columns: &[ExportedColumn], quarters: usize) -> Table {
+    let latest = result.sort(["quarter"], SortMultipleOptions::new())
+        .map(|result| result.tail(Some(quarters)))
+        .unwrap_or_else(|_| DataFrame::empty());
+
+    let quarter_labels: Vec<String> = latest.column("quarter")
+        .and_then(|quarters| quarters.str().cloned())
+        .map(|quarters| quarters.into_iter().map(|quarter| quarter.unwrap_or_default().to_string()).collect())
+        .unwrap_or_default();
+
+    let values: Vec<Vec<Option<f64>>> = columns.iter()
+        .map(|column| column_values(&latest, &column.column).unwrap_or_default())
+        .collect();
+
+    let rows = quarter_labels.into_iter()
+        .enumerate()
+        .map(|(row, quarter)| {
+            let mut cells = vec![quarter];
+            cells.extend(columns.iter().zip(&values).map(|(column, values)| {
+                values.get(row).copied().flatten().map(|value| format_value(value, column.format)).unwrap_or_default()
+            }));
+            cells
+        })
+        .collect();
+
+    let mut headers = vec!["Quarter".to_string()];
+    headers.extend(columns.iter().map(|column| column.header.clone()));
+
+    Table { headers, rows }
+}
+
+/// Observations, mean, sample standard deviation, minimum and maximum of every column over all quarters.
+fn summary_statistics_table(result: &DataFrame, columns: &[ExportedColumn]) -> Table {
+    let rows = columns.iter()
+        .map(|column| {
+            let values: Vec<f64> = column_values(result, &column.column).unwrap_or_default().into_iter().flatten().collect();
+            let count = values.len() as f64;
+            let mean = values.iter().sum::<f64>() / count;
+            let standard_deviation = (values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / (count - 1.0)).sqrt();
+            let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+            let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+
+            let mut cells = vec![column.header.clone(), values.len().to_string()];
+            cells.extend([mean, standard_deviation, min, max].map(|statistic| {
+                if statistic.is_finite() { format_value(statistic, column.format) } else { String::new() }
+            }));
+            cells
+        })
+        .collect();
+
+    Table {
+        headers: ["Series", "Observations", "Mean", "Std. dev.", "Min", "Max"].map(String::from).to_vec(),
+        rows,
+    }
+}
+
+fn markdown_table(table: &Table) -> String {
+    let escape = |cell: &String| cell.replace('|', "\\|");
+    let row = |cells: &[String]| format!("| {} |\n", cells.iter().map(escape).collect::<Vec<_>>().join(" | "));
+
+    let alignment: Vec<String> = (0..table.headers.len())
+        .map(|index| if index == 0 { ":---".to_string() } else { "---:".to_string() })
+        .collect();
+
+    let mut markdown = row(&table.headers);
+    markdown.push_str(&row(&alignment));
+    for cells in &table.rows {
+        markdown.push_str(&row(cells));
+    }
+    markdown
+}
+
+fn latex_table(table: &Table) -> String {
+    let row = |cells: &[String]| format!("{} \\\\\n", cells.iter().map(|cell| escape_latex(cell)).collect::<Vec<_>>().join(" & "));
+
+    let mut latex = format!("\\begin{{tabular}}{{l{}}}\n\\toprule\n", "r".repeat(table.headers.len().saturating_sub(1)));
+    latex.push_str(&row(&table.headers));
+    latex.push_str("\\midrule\n");
+    for cells in &table.rows {
+        latex.push_str(&row(cells));
+    }
+    latex.push_str("\\bottomrule\n\\end{tabular}\n");
+    latex
+}
+
+fn escape_latex(text: &str) -> String {
+    text.chars()
+        .map(|character| match character {
+            '&' | '%' | '$' | '#' | '_' | '{' | '}' => format!("\\{character}"),
+            '~' => "\\textasciitilde{}".to_string(),
+            '^' => "\\textasciicircum{}".to_string(),
+            '\\' => "\\textbackslash{}".to_string(),
+            '|' => "\\textbar{}".to_string(),
+            _ => character.to_string(),
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::datasets_config::datasets_config::TableColumnConfig;
+
+    use super::*;
+
+    fn result() -> DataFrame {
+        df![
+            "quarter" => &["2024-Q3", "2024-Q1", "2024-Q2", "2024-Q4"],
+            "sp500_usd" => &[5700.0, 5200.0, 5400.0, 5900.0],
+            "us_inflation_perc" => &[Some(3.0), Some(3.4), None, Some(2.6)]
+        ].unwrap()
+    }
+
+    fn table_export_config() -> TableExportConfig {
+        TableExportConfig::new("summary", 2, vec![
+            TableColumnConfig::new("us_inflation_perc", "US inflation", NumberFormat::Percent { decimals: 1 }),
+            TableColumnConfig::new("eu_inflation_perc", "EU inflation", NumberFormat::Percent { decimals: 1 }),
+            TableColumnConfig::new("sp500_usd", "S&P 500", NumberFormat::Decimal { decimals: 0, thousands_separator: true }),
+        ])
+    }
+
+    #[test]
+    fn test_format_value() {
+        assert_eq!(format_value(1234567.891, NumberFormat::Decimal { decimals: 2, thousands_separator: true }), "1,234,567.89");
+        assert_eq!(format_value(1234567.891, NumberFormat::Decimal { decimals: 0, thousands_separator: false }), "1234568");
+        assert_eq!(format_value(-999.96, NumberFormat::Decimal { decimals: 1, thousands_separator: true }), "-1,000.0");
+        assert_eq!(format_value(-0.01, NumberFormat::Percent { decimals: 1 }), "0.0%");
+        assert_eq!(format_value(2.5, NumberFormat::Percent { decimals: 2 }), "2.50%");
+    }
+
+    #[test]
+    fn test_tables_keep_configured_columns_in_order() {
+        let result = result();
+        let columns = exported_columns(&result, &table_export_config());
+
+        let latest = latest_quarters_table(&result, &columns, 2);
+        assert_eq!(latest.headers, vec!["Quarter", "US inflation", "S&P 500"]);
+        assert_eq!(latest.rows, vec![vec!["2024-Q3", "3.0%", "5,700"], vec!["2024-Q4", "2.6%", "5,900"]]);
+
+        let summary = summary_statistics_table(&result, &columns);
+        assert_eq!(summary.rows[0], vec!["US inflation", "3", "3.0%", "0.4%", "2.6%", "3.4%"]);
+        assert_eq!(summary.rows[1][..3], ["S&P 500", "4", "5,550"]);
+    }
+
+    #[test]
+    fn test_all_numeric_columns_without_configured_columns() {
+        let columns = exported_columns(&result(), &TableExportConfig::new("summary", 4, vec![]));
+
+        assert_eq!(columns.iter().map(|column| column.column.as_str()).collect::<Vec<_>>(), vec!["sp500_usd", "us_inflation_perc"]);
+        assert!(columns.iter().all(|column| column.format == DEFAULT_FORMAT));
+    }
+
+    #[test]
+    fn test_markdown_and_latex_tables() {
+        let table = Table {
+            headers: vec!["Quarter".to_string(), "S&P 500 | YoY".to_string()],
+            rows: vec![vec!["2024-Q4".to_string(), "23.3%".to_string()]],
+        };
+
+        assert_eq!(markdown_table(&table), "| Quarter | S&P 500 \\| YoY |\n| :--- | ---: |\n| 2024-Q4 | 23.3% |\n");
+        assert_eq!(latex_table(&table),
+                   "\\begin{tabular}{lr}\n\\toprule\nQuarter & S\\&P 500 \\textbar{} YoY \\\\\n\\midrule\n2024-Q4 & 23.3\\% \\\\\n\\bottomrule\n\\end{tabular}\n");
+    }
+}
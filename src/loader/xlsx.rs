@@ -0,0 +1,243 @@
+use std::collections::HashMap;
+use std::error::Error;
+use std::fs;
+
+use log::{info, warn};
+use polars::prelude::*;
+use rust_xlsxwriter::{Chart, ChartFormat, ChartLine, ChartLineDashType, Color, Format, FormatBorder, Workbook};
+
+use crate::datasets_config::datasets_config::{ChartAxis, ChartConfig, ChartKind, LineStyle, Scale};
+use crate::model::data_model::ColumnMetadata;
+
+pub const XLSX_DIRECTORY: &str = "outputs/xlsx";
+
+const RESULT_SHEET: &str = "result";
+const CHART_SHEET: &str = "charts";
+const CHART_SIZE: (u32, u32) = (960, 480);
+const CHART_ROWS: u32 = 26;
+
+/// Writes `outputs/xlsx/<file name>.xlsx`: the result with number formats by unit, then every
+/// other sheet as given, and a sheet of native Excel line charts drawn from the result sheet.
+/// Every sheet has a bold header and frozen panes.
+pub fn generate_workbook(result: &DataFrame,
+                         column_metadata: &[ColumnMetadata],
+                         sheets: &[(String, DataFrame)],
+                         chart_configs: &[ChartConfig],
+                         file_name: &str) {
+    let file_path = format!("{XLSX_DIRECTORY}/{file_name}.xlsx");
+
+    let saved = workbook(result, column_metadata, sheets, chart_configs)
+        .and_then(|mut workbook| {
+            fs::create_dir_all(XLSX_DIRECTORY)?;
+            Ok(workbook.save(&file_path)?)
+        });
+
+    match saved {
+        Ok(()) => info!("workbook saved as {file_path}"),
+        Err(error) => warn!("could not write {file_path}: {error}"),
+    }
+}
+
+fn workbook(result: &DataFrame,
+            column_metadata: &[ColumnMetadata],
+            sheets: &[(String, DataFrame)],
+            chart_configs: &[ChartConfig]) -> Result<Workbook, Box<dyn Error>> {
+    let result = result.sort(["quarter"], SortMultipleOptions::new())?;
+    let mut workbook = Workbook::new();
+
+    let number_formats: HashMap<&str, &str> = column_metadata.iter()
+        .map(|metadata| (metadata.column_name.as_str(), number_format(metadata)))
+        .collect();
+    write_sheet(&mut workbook, RESULT_SHEET, &result, &number_formats)?;
+
+    for (name, dataframe) in sheets {
+        write_sheet(&mut workbook, name, dataframe, &HashMap::new())?;
+    }
+
+    let charts: Vec<Chart> = chart_configs.iter()
+        .filter(|config| config.kind == ChartKind::Line)
+        .filter_map(|config| line_chart(&result, config)
+            .inspect_err(|error| warn!("leaving chart {} out of the workbook: {error}", config.file_name))
+            .ok())
+        .collect();
+
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name(CHART_SHEET)?;
+    for (index, chart) in charts.iter().enumerate() {
+        worksheet.insert_chart(index as u32 * CHART_ROWS, 0, chart)?;
+    }
+
+    Ok(workbook)
+}
+
+/// Excel number format of a result column: percentages keep their value and get a percent
+/// sign, scaled monetary columns such as billions of USD are shown with one decimal.
+fn number_format(metadata: &ColumnMetadata) -> &'static str {
+    if metadata.unit.starts_with("percent") {
+        "0.00\"%\""
+    } else if metadata.scale != Scale::Units {
+        "#,##0.0"
+    } else {
+        "#,##0.00"
+    }
+}
+
+fn write_sheet(workbook: &mut Workbook,
+               name: &str,
+               dataframe: &DataFrame,
+               number_formats: &HashMap<&str, &str>) -> Result<(), Box<dyn Error>> {
+    let header_format = Format::new()
+        .set_bold()
+        .set_background_color(Color::RGB(0xDDEBF7))
+        .set_border_bottom(FormatBorder::Thin);
+
+    let worksheet = workbook.add_worksheet();
+    worksheet.set_name(name)?;
+
+    for (column_index, column) in dataframe.get_columns().iter().enumerate() {
+        let column_index = column_index as u16;
+        worksheet.write_string_with_format(0, column_index, column.name().as_str(), &header_format)?;
+
+        if column.dtype().is_primitive_numeric() {
+            let number_format = match column.dtype().is_integer() {
+                true => "0",
+                false => number_formats.get(column.name().as_str()).copied().unwrap_or("General"),
+            };
+            let format = Format::new().set_num_format(number_format);
+            let values = column.cast(&DataType::Float64)?;
+
+            for (row, value) in values.f64()?.into_iter().enumerate() {
+                if let Some(value) = value {
+                    worksheet.write_number_with_format(row as u32 + 1, column_index, value, &format)?;
+                }
+            }
+        } else {
+            let values = column.cast(&DataType::String)?;
+
+            for (row, value) in values.str()?.into_iter().enumerate() {
+                if let Some(value) = value {
+                    worksheet.write_string(row as u32 + 1, column_index, value)?;
+                }
+            }
+        }
+    }
+
+    worksheet.set_freeze_panes(1, 1)?;
+    worksheet.autofit();
+    Ok(())
+}
+
+/// A line chart of the configured series, read from the columns of the result sheet.
+fn line_chart(result: &DataFrame, chart_config: &ChartConfig) -> Result<Chart, Box<dyn Error>> {
+    let (first_row, last_row) = chart_rows(result, chart_config)?;
+    let quarter_column = column_index(result, "quarter")?;
+
+    let mut chart = Chart::new_line();
+    chart.title().set_name(chart_config.title);
+    chart.x_axis().set_name("Quarter");
+    chart.y_axis().set_name(chart_config.y_labels.0.as_str());
+    if let Some(y_label) = chart_config.y_labels.1.as_deref() {
+        chart.y2_axis().set_name(y_label);
+    }
+    chart.set_width(CHART_SIZE.0).set_height(CHART_SIZE.1);
+
+    for series in &chart_config.series {
+        let column = column_index(result, series.column)?;
+        let (red, green, blue) = series.color;
+        let mut line = ChartLine::new();
+        line.set_color(Color::RGB(((red as u32) << 16) | ((green as u32) << 8) | blue as u32));
+        if series.line_style == LineStyle::Dashed {
+            line.set_dash_type(ChartLineDashType::Dash);
+        }
+
+        chart.add_series()
+            .set_name(series.label.as_str())
+            .set_categories((RESULT_SHEET, first_row, quarter_column, last_row, quarter_column))
+            .set_values((RESULT_SHEET, first_row, column, last_row, column))
+            .set_secondary_axis(series.axis == ChartAxis::Secondary)
+            .set_format(ChartFormat::new().set_line(&line));
+    }
+
+    Ok(chart)
+}
+
+/// The first and last row of the result sheet within the chart's range of quarters, the header being row 0.
+fn chart_rows(result: &DataFrame, chart_config: &ChartConfig) -> Result<(u32, u32), Box<dyn Error>> {
+    let rows: Vec<u32> = result.column("quarter")?.str()?
+        .into_iter()
+        .enumerate()
+        .filter(|(_, quarter)| match (quarter, chart_config.date_range) {
+            (Some(quarter), Some((start, end))) => (start..=end).contains(quarter),
+            (quarter, None) => quarter.is_some(),
+            (None, _) => false,
+        })
+        .map(|(row, _)| row as u32 + 1)
+        .collect();
+
+    match (rows.first(), rows.last()) {
+        (Some(first_row), Some(last_row)) => Ok((*first_row, *last_row)),
+        _ => Err("no quarter in the chart's range".into()),
+    }
+}
+
+fn column_index(result: &DataFrame, column_name: &str) -> Result<u16, Box<dyn Error>> {
+    let index = result.get_column_index(column_name).ok_or(format!("unknown column '{column_name}'"))?;
+    Ok(index as u16)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::datasets_config::datasets_config::{get_chart_output_config, ChartSeriesConfig};
+
+    use super::*;
+
+    fn result() -> DataFrame {
+        df![
+            "quarter" => &["2024-Q3", "2024-Q1", "2024-Q2", "2024-Q4"],
+            "us_inflation_perc" => &[Some(3.0), Some(3.4), None, Some(2.6)],
+            "sp500_usd" => &[5700.0, 5200.0, 5400.0, 5900.0]
+        ].unwrap()
+    }
+
+    fn chart_config(date_range: Option<(&'static str, &'static str)>) -> ChartConfig {
+        ChartConfig::new("inflation",
+                         ChartKind::Line,
+                         "Inflation",
+                         ("Inflation in %", Some("S&P 500 in index points")),
+                         vec![ChartSeriesConfig::new("us_inflation_perc", "US", (204, 0, 0), LineStyle::Solid, ChartAxis::Primary),
+                              ChartSeriesConfig::new("sp500_usd", "S&P 500", (0, 102, 0), LineStyle::Dashed, ChartAxis::Secondary)],
+                         date_range,
+                         get_chart_output_config())
+    }
+
+    #[test]
+    fn test_number_format_by_unit() {
+        assert_eq!(number_format(&ColumnMetadata::new("us_inflation_perc", "us_inflation", "percent", Scale::Units)), "0.00\"%\"");
+        assert_eq!(number_format(&ColumnMetadata::new("us_gdp_usd_billions", "us_gdp", "USD", Scale::Billions)), "#,##0.0");
+        assert_eq!(number_format(&ColumnMetadata::new("sp500_usd", "sp500", "index points", Scale::Units)), "#,##0.00");
+    }
+
+    #[test]
+    fn test_chart_rows_within_date_range() {
+        let result = result().sort(["quarter"], SortMultipleOptions::new()).unwrap();
+
+        assert_eq!(chart_rows(&result, &chart_config(None)).unwrap(), (1, 4));
+        assert_eq!(chart_rows(&result, &chart_config(Some(("2024-Q2", "2024-Q3")))).unwrap(), (2, 3));
+        assert!(chart_rows(&result, &chart_config(Some(("2025-Q1", "2025-Q4")))).is_err());
+    }
+
+    #[test]
+    fn test_workbook_with_sheets_and_charts() {
+        let metadata = [ColumnMetadata::new("us_inflation_perc", "us_inflation", "percent", Scale::Units)];
+        let sheets = [("us_inflation".to_string(), df!["date" => &["2024-01-01"], "value" => &[3.1]].unwrap())];
+        let mut missing_column = chart_config(None);
+        missing_column.series[0].column = "eu_inflation_perc";
+
+        let mut workbook = workbook(&result(), &metadata, &sheets, &[chart_config(None), missing_column]).unwrap();
+
+        let names: Vec<String> = workbook.worksheets().iter().map(|worksheet| worksheet.name()).collect();
+        assert_eq!(names, vec!["result", "us_inflation", "charts"]);
+        assert!(workbook.save_to_buffer().unwrap().starts_with(b"PK"));
+    }
+}
@@ -1,15 +1,42 @@
+use std::collections::HashMap;
+use std::env;
+use std::path::Path;
+use std::process;
 use std::time::Instant;
 
+use chrono::Local;
+
+use cli::{parse_command, run_analysis_command, Command, USAGE};
+
 use extractor::extractor_manager::extract_data;
-use loader::loader_manager::load;
-use log::{info};
+use extractor::incremental::{affected_quarters, merge_with_cache, start_periods};
+use extractor::recession::extract_recessions;
+use loader::cache::{cache_datasets, read_cached_datasets, CACHE_DIRECTORY};
+use loader::parquet::RESULT_PATH;
+use loader::store::{new_run_id, store_processed_datasets, store_raw_datasets, STORE_DIRECTORY};
+use loader::loader_manager::{load, load_anomaly_report, load_validation_report};
+use log::{error, info};
 use model::data_model::AllDatasets;
-use transformer::normalize::{convert_eu_to_usd, join_all_datasets, normalize_data, rename_columns};
+use transformer::derived::derive_series;
+use transformer::expression::evaluate_expression_series;
+use transformer::filters::apply_cycle_filters;
+use transformer::rebase::rebase_series;
+use transformer::recession::annotate_recessions;
+use transformer::seasonal::{record_seasonal_adjustments, seasonally_adjust};
+use transformer::regression::run_configured_tests;
+use transformer::stats::compute_statistics;
+use transformer::normalize::{convert_eu_currency, expand_annual_datasets, join_all_datasets, normalize_data, rename_columns};
+use transformer::units::{collect_column_metadata, harmonize_units};
+use validator::anomaly::{datasets_above_threshold, detect_anomalies};
+use validator::validation::validate_datasets;
 
-use crate::model::data_model::DatasetWithConfig;
+use crate::datasets_config::datasets_config::{get_all_cycle_filter_configs, get_all_datasets_configs, get_all_derived_series_configs, get_all_granger_causality_configs, get_all_recession_configs, get_all_recession_rule_configs, get_all_regression_configs, get_all_expression_series_configs, get_all_validation_configs, get_anomaly_config, get_eu_conversion_methods, get_incremental_config, get_output_formats, get_rebase_config, get_statistics_config, get_target_monetary_scale};
+use crate::model::data_model::{DatasetWithConfig, RunContext, ValidationStatus};
 
+mod cli;
 mod extractor;
 mod transformer;
+mod validator;
 mod loader;
 mod tests;
 mod datasets_config;
@@ -21,19 +48,110 @@ async fn main() {
     env_logger::init();
     info!("Logger is working!");
 
+    let args: Vec<String> = env::args().skip(1).collect();
+    let command = parse_command(&args).unwrap_or_else(|error| {
+        eprintln!("{error}\n\n{USAGE}");
+        process::exit(2);
+    });
+
+    let (update, output_formats, scenario) = match command {
+        Command::Run { output_formats, scenario } => (false, output_formats, scenario),
+        Command::Update { output_formats, scenario } => (true, output_formats, scenario),
+        command => {
+            if let Err(error) = run_analysis_command(&command) {
+                eprintln!("{error}");
+                process::exit(1);
+            }
+            return;
+        },
+    };
+
     let start = Instant::now();
+    let run_id = new_run_id(Local::now());
+
+    let dataset_names: Vec<&str> = get_all_datasets_configs().iter().map(|config| config.name).collect();
+    let cached_datasets = read_cached_datasets(&dataset_names, CACHE_DIRECTORY);
+
+    let start_periods = if update {
+        start_periods(&get_all_datasets_configs(), &cached_datasets, &get_all_validation_configs(), &get_incremental_config())
+    } else {
+        HashMap::new()
+    };
+
+    let fetched_datasets: AllDatasets = extract_data(&start_periods).await;
+    let fetched_at = Local::now();
+    let run_context = RunContext::new(&run_id, fetched_at, output_formats.unwrap_or_else(get_output_formats), scenario);
+
+    let unmerged_datasets = fetched_datasets.clone().items();
+    let (all_datasets, updated_periods) = merge_with_cache(fetched_datasets, &cached_datasets, &start_periods, &get_all_validation_configs());
+
+    if update {
+        if updated_periods.len() == dataset_names.len() && updated_periods.values().all(Vec::is_empty) && Path::new(RESULT_PATH).exists() {
+            info!("no new or revised observations since the last run, keeping the previous result");
+            return;
+        }
+        info!("quarters affected by new or revised observations: {:?}", affected_quarters(&updated_periods));
+    }
+
+    let extracted_datasets = all_datasets.clone().items();
+
+    let validation_checks = validate_datasets(&extracted_datasets, &get_all_validation_configs(), Local::now().date_naive());
+    load_validation_report(&validation_checks);
+    if validation_checks.iter().any(|check| check.status == ValidationStatus::Error) {
+        error!("extracted data failed validation, see outputs/csv/validation.csv");
+        process::exit(1);
+    }
+
+    let anomaly_config = get_anomaly_config();
+    let anomalies = detect_anomalies(&extracted_datasets, &unmerged_datasets, &cached_datasets, &get_all_validation_configs(), &anomaly_config);
+    load_anomaly_report(&anomalies);
+    let anomalous_datasets = datasets_above_threshold(&anomalies, &anomaly_config);
+    if !anomalous_datasets.is_empty() {
+        error!("too many anomalies in {}, see outputs/csv/anomalies.csv", anomalous_datasets.join(", "));
+        process::exit(1);
+    }
 
-    let all_datasets: AllDatasets = extract_data().await;
+    cache_datasets(&extracted_datasets, CACHE_DIRECTORY);
+    store_raw_datasets(STORE_DIRECTORY, &run_id, &extracted_datasets, fetched_at);
+
+    let recession_periods = extract_recessions(&get_all_recession_configs()).await;
 
     let all_normalized_datasets: Vec<DatasetWithConfig> = normalize_data(all_datasets);
 
     let renamed_datasets = rename_columns(all_normalized_datasets);
 
-    let converted_datasets = convert_eu_to_usd(renamed_datasets);
+    let quarterly_datasets = expand_annual_datasets(renamed_datasets);
+
+    let harmonized_datasets = harmonize_units(quarterly_datasets, get_target_monetary_scale());
+
+    let adjusted_datasets = seasonally_adjust(harmonized_datasets);
+
+    let converted_datasets = convert_eu_currency(adjusted_datasets, &get_eu_conversion_methods());
+    store_processed_datasets(STORE_DIRECTORY, &run_id, &converted_datasets);
+
+    let mut column_metadata = collect_column_metadata(&converted_datasets);
+    record_seasonal_adjustments(&mut column_metadata, &get_all_datasets_configs());
+
+    let joined_dataframe = join_all_datasets(converted_datasets);
+
+    let derived_dataframe = derive_series(joined_dataframe, &mut column_metadata, &get_all_derived_series_configs());
+
+    let filtered_dataframe = apply_cycle_filters(derived_dataframe, &mut column_metadata, &get_all_cycle_filter_configs());
+
+    let expression_dataframe = evaluate_expression_series(filtered_dataframe, &mut column_metadata, &get_all_expression_series_configs())
+        .expect("invalid expression series configuration");
+
+    let rebased_dataframe = rebase_series(expression_dataframe, &mut column_metadata, &get_rebase_config());
+
+    let result_dataframe = annotate_recessions(rebased_dataframe, &mut column_metadata, &recession_periods, &get_all_recession_rule_configs());
+
+    let statistics = compute_statistics(&result_dataframe, &get_statistics_config());
 
-    let result_dataframe = join_all_datasets(converted_datasets);
+    let (regressions, granger_tests) = run_configured_tests(&result_dataframe,
+                                                            &get_all_regression_configs(),
+                                                            &get_all_granger_causality_configs());
 
-    load(result_dataframe);
+    load(result_dataframe, column_metadata, statistics, regressions, granger_tests, &run_context);
 
 
     let duration = start.elapsed();
@@ -1,12 +1,16 @@
+use chrono::{DateTime, Local};
 use polars::frame::DataFrame;
+use serde::Serialize;
 
-use crate::datasets_config::datasets_config::DatasetConfig;
+use crate::datasets_config::datasets_config::{DatasetConfig, OutputFormat, Scale};
 
 #[derive(Clone)]
 pub struct DatasetWithConfig {
     pub name: &'static str,
     pub dataframe: DataFrame,
     pub dataset_config: DatasetConfig,
+    /// What was done to the data after extraction, copied to the metadata of every column.
+    pub transformations: Vec<String>,
 }
 
 impl DatasetWithConfig {
@@ -17,10 +21,17 @@ impl DatasetWithConfig {
             name,
             dataframe,
             dataset_config,
+            transformations: vec![],
         }
     }
+
+    pub fn with_transformation(mut self, transformation: &str) -> Self {
+        self.transformations.push(transformation.to_string());
+        self
+    }
 }
 
+#[derive(Clone)]
 pub struct AllDatasets {
     pub fx_rates_df: DatasetWithConfig,
     pub fred_sp500_df: DatasetWithConfig,
@@ -30,6 +41,10 @@ pub struct AllDatasets {
     pub ecb_government_debt_df: DatasetWithConfig,
     pub ecb_gdp_df: DatasetWithConfig,
     pub ecb_inflation_df: DatasetWithConfig,
+    pub world_bank_eu_ppp_df: DatasetWithConfig,
+    pub fred_us_gdp_deflator_df: DatasetWithConfig,
+    pub fred_us_population_df: DatasetWithConfig,
+    pub world_bank_eu_population_df: DatasetWithConfig,
 }
 
 impl AllDatasets {
@@ -43,8 +58,21 @@ impl AllDatasets {
             self.ecb_government_debt_df,
             self.ecb_gdp_df,
             self.ecb_inflation_df,
+            self.world_bank_eu_ppp_df,
+            self.fred_us_gdp_deflator_df,
+            self.fred_us_population_df,
+            self.world_bank_eu_population_df,
         ]
     }
+
+    /// Inverse of `items`, the datasets must be in the same order.
+    pub fn from_items(items: Vec<DatasetWithConfig>) -> Self {
+        let mut items = items.into_iter();
+        let mut next = || items.next().expect("missing dataset");
+
+        AllDatasets::new(next(), next(), next(), next(), next(), next(),
+                         next(), next(), next(), next(), next(), next())
+    }
 }
 
 impl AllDatasets {
@@ -55,7 +83,11 @@ impl AllDatasets {
                fred_us_inflation_df: DatasetWithConfig,
                ecb_government_debt_df: DatasetWithConfig,
                ecb_gdp_df: DatasetWithConfig,
-               ecb_inflation_df: DatasetWithConfig) -> Self {
+               ecb_inflation_df: DatasetWithConfig,
+               world_bank_eu_ppp_df: DatasetWithConfig,
+               fred_us_gdp_deflator_df: DatasetWithConfig,
+               fred_us_population_df: DatasetWithConfig,
+               world_bank_eu_population_df: DatasetWithConfig) -> Self {
         AllDatasets {
             fx_rates_df,
             fred_sp500_df,
@@ -65,10 +97,328 @@ impl AllDatasets {
             ecb_government_debt_df,
             ecb_gdp_df,
             ecb_inflation_df,
+            world_bank_eu_ppp_df,
+            fred_us_gdp_deflator_df,
+            fred_us_population_df,
+            world_bank_eu_population_df,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct ColumnMetadata {
+    pub column_name: String,
+    pub dataset_name: String,
+    pub unit: String,
+    pub scale: Scale,
+    pub transformations: Vec<String>,
+}
+
+impl ColumnMetadata {
+    pub fn new(column_name: &str,
+               dataset_name: &str,
+               unit: &str,
+               scale: Scale) -> Self {
+        ColumnMetadata {
+            column_name: column_name.to_string(),
+            dataset_name: dataset_name.to_string(),
+            unit: unit.to_string(),
+            scale,
+            transformations: vec![],
         }
     }
 }
 
+/// A single statistic comparing `series` to `benchmark`. Cross-correlations set `lag` to the
+/// number of quarters `series` leads the benchmark by, rolling correlations set `quarter` to the
+/// last quarter of their window.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Statistic {
+    pub series: String,
+    pub benchmark: String,
+    pub statistic: &'static str,
+    pub lag: i64,
+    pub quarter: Option<String>,
+    pub observations: usize,
+    pub value: f64,
+}
+
+impl Statistic {
+    pub fn new(series: &str,
+               benchmark: &str,
+               statistic: &'static str,
+               lag: i64,
+               quarter: Option<&str>,
+               observations: usize,
+               value: f64) -> Self {
+        Statistic {
+            series: series.to_string(),
+            benchmark: benchmark.to_string(),
+            statistic,
+            lag,
+            quarter: quarter.map(|quarter| quarter.to_string()),
+            observations,
+            value,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegressionCoefficient {
+    pub term: String,
+    pub estimate: f64,
+    pub standard_error: f64,
+    pub t_statistic: f64,
+    pub p_value: f64,
+}
+
+impl RegressionCoefficient {
+    pub fn new(term: &str,
+               estimate: f64,
+               standard_error: f64,
+               t_statistic: f64,
+               p_value: f64) -> Self {
+        RegressionCoefficient {
+            term: term.to_string(),
+            estimate,
+            standard_error,
+            t_statistic,
+            p_value,
+        }
+    }
+}
+
+/// An OLS fit of `dependent`, with `covariance` naming how the standard errors were estimated.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RegressionResult {
+    pub dependent: String,
+    pub covariance: String,
+    pub observations: usize,
+    pub r_squared: f64,
+    pub adjusted_r_squared: f64,
+    pub coefficients: Vec<RegressionCoefficient>,
+}
+
+impl RegressionResult {
+    pub fn new(dependent: &str,
+               covariance: &str,
+               observations: usize,
+               r_squared: f64,
+               adjusted_r_squared: f64,
+               coefficients: Vec<RegressionCoefficient>) -> Self {
+        RegressionResult {
+            dependent: dependent.to_string(),
+            covariance: covariance.to_string(),
+            observations,
+            r_squared,
+            adjusted_r_squared,
+            coefficients,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct GrangerTest {
+    pub cause: String,
+    pub effect: String,
+    pub lags: usize,
+    pub observations: usize,
+    pub f_statistic: f64,
+    pub p_value: f64,
+}
+
+impl GrangerTest {
+    pub fn new(cause: &str,
+               effect: &str,
+               lags: usize,
+               observations: usize,
+               f_statistic: f64,
+               p_value: f64) -> Self {
+        GrangerTest {
+            cause: cause.to_string(),
+            effect: effect.to_string(),
+            lags,
+            observations,
+            f_statistic,
+            p_value,
+        }
+    }
+}
+
+/// The quarters in recession according to `source`, to be flagged in `column_name`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RecessionPeriods {
+    pub column_name: String,
+    pub source: String,
+    pub quarters: Vec<String>,
+}
+
+impl RecessionPeriods {
+    pub fn new(column_name: &str,
+               source: &str,
+               quarters: Vec<String>) -> Self {
+        RecessionPeriods {
+            column_name: column_name.to_string(),
+            source: source.to_string(),
+            quarters,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ValidationStatus {
+    Passed,
+    Warning,
+    Error,
+}
+
+impl ValidationStatus {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ValidationStatus::Passed => "passed",
+            ValidationStatus::Warning => "warning",
+            ValidationStatus::Error => "error",
+        }
+    }
+}
+
+/// The outcome of one validation `rule` on an extracted dataset.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ValidationCheck {
+    pub dataset: String,
+    pub rule: &'static str,
+    pub status: ValidationStatus,
+    pub message: String,
+}
+
+impl ValidationCheck {
+    pub fn new(dataset: &str,
+               rule: &'static str,
+               status: ValidationStatus,
+               message: &str) -> Self {
+        ValidationCheck {
+            dataset: dataset.to_string(),
+            rule,
+            status,
+            message: message.to_string(),
+        }
+    }
+}
+
+/// An observation flagged by anomaly detection. `magnitude` is the outlier score for outliers,
+/// the median ratio to the cached values for scale changes and the median difference for level shifts.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Anomaly {
+    pub dataset: String,
+    pub period: String,
+    pub kind: &'static str,
+    pub value: f64,
+    pub magnitude: f64,
+}
+
+impl Anomaly {
+    pub fn new(dataset: &str,
+               period: &str,
+               kind: &'static str,
+               value: f64,
+               magnitude: f64) -> Self {
+        Anomaly {
+            dataset: dataset.to_string(),
+            period: period.to_string(),
+            kind,
+            value,
+            magnitude,
+        }
+    }
+}
+
+/// A changed value of `column` in `quarter` compared to an earlier result. Values missing on
+/// one side are `None`.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Revision {
+    pub column: String,
+    pub quarter: String,
+    pub change: &'static str,
+    pub old_value: Option<f64>,
+    pub new_value: Option<f64>,
+}
+
+impl Revision {
+    pub fn new(column: &str,
+               quarter: &str,
+               change: &'static str,
+               old_value: Option<f64>,
+               new_value: Option<f64>) -> Self {
+        Revision {
+            column: column.to_string(),
+            quarter: quarter.to_string(),
+            change,
+            old_value,
+            new_value,
+        }
+    }
+}
+
+/// How a result differs from an earlier one: added and removed quarters and columns, and the
+/// individual values that were added, dropped or revised.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ResultDiff {
+    pub new_quarters: Vec<String>,
+    pub dropped_quarters: Vec<String>,
+    pub new_columns: Vec<String>,
+    pub dropped_columns: Vec<String>,
+    pub revisions: Vec<Revision>,
+}
+
+/// Where a dataset of the run was fetched from, e.g. FRED series `GDP`, and when it was last
+/// fetched; in update mode only its recent observations are. The raw store keeps when each
+/// observation was first fetched.
+#[derive(Clone, Debug, PartialEq, Serialize)]
+pub struct DataSource {
+    pub dataset: String,
+    pub source: String,
+    pub identifier: String,
+    pub fetched_at: DateTime<Local>,
+}
+
+impl DataSource {
+    pub fn new(dataset: &str,
+               source: &str,
+               identifier: &str,
+               fetched_at: DateTime<Local>) -> Self {
+        DataSource {
+            dataset: dataset.to_string(),
+            source: source.to_string(),
+            identifier: identifier.to_string(),
+            fetched_at,
+        }
+    }
+}
+
+/// A run of the pipeline: its id, when its data was fetched and the formats its result is
+/// written in besides CSV and Parquet.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RunContext {
+    pub run_id: String,
+    pub fetched_at: DateTime<Local>,
+    pub output_formats: Vec<OutputFormat>,
+    /// Replaces the scenario of the partitioned dataset config when set.
+    pub scenario: Option<String>,
+}
+
+impl RunContext {
+    pub fn new(run_id: &str,
+               fetched_at: DateTime<Local>,
+               output_formats: Vec<OutputFormat>,
+               scenario: Option<String>) -> Self {
+        RunContext {
+            run_id: run_id.to_string(),
+            fetched_at,
+            output_formats,
+            scenario,
+        }
+    }
+}
 
 #[cfg(test)]
 mod tests {
@@ -81,7 +431,7 @@ mod tests {
     }
 
     fn mock_dataset_config() -> DatasetConfig {
-        DatasetConfig::new("test_dataset", "test_source", "test_identifier", false, None)
+        DatasetConfig::new("test_dataset", "test_source", "test_identifier", false, None, None, None)
     }
 
     #[test]
@@ -100,16 +450,28 @@ mod tests {
         let dataset = DatasetWithConfig::new("test", mock_dataframe(), mock_dataset_config());
 
         let all_datasets = AllDatasets::new(
+            dataset.clone(), dataset.clone(), dataset.clone(), dataset.clone(),
             dataset.clone(), dataset.clone(), dataset.clone(), dataset.clone(),
             dataset.clone(), dataset.clone(), dataset.clone(), dataset.clone()
         );
 
         let items = all_datasets.items();
 
-        assert_eq!(items.len(), 8);
+        assert_eq!(items.len(), 12);
 
         for ds in &items {
             assert_eq!(ds.name, "test");
         }
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_column_metadata_creation() {
+        let metadata = ColumnMetadata::new("us_gdp_usd", "us_gdp", "USD", Scale::Billions);
+
+        assert_eq!(metadata.column_name, "us_gdp_usd");
+        assert_eq!(metadata.dataset_name, "us_gdp");
+        assert_eq!(metadata.unit, "USD");
+        assert_eq!(metadata.scale, Scale::Billions);
+        assert!(metadata.transformations.is_empty());
+    }
+}
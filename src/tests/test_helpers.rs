@@ -18,4 +18,19 @@ pub mod test_helpers {
         let sort_columns: Vec<String> = sorted_df.get_column_names().iter().map(|s| s.to_string()).collect();
         sorted_df.sort(&sort_columns, SortMultipleOptions::new()).unwrap()
     }
+
+    pub fn assert_close(actual: f64, expected: f64, tolerance: f64) {
+        assert!((actual - expected).abs() < tolerance, "{actual} != {expected}");
+    }
+
+    /// Deterministic noise in [-0.5, 0.5) from a linear congruential generator.
+    pub fn noise(count: usize, seed: u64) -> Vec<f64> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                (state >> 11) as f64 / (1u64 << 53) as f64 - 0.5
+            })
+            .collect()
+    }
 }
\ No newline at end of file
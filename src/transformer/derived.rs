@@ -0,0 +1,209 @@
+use log::{info, warn};
+use polars::prelude::*;
+
+use crate::datasets_config::datasets_config::{DerivedSeriesConfig, DerivedSeriesOperation, Scale};
+use crate::model::data_model::ColumnMetadata;
+
+/// Adds the configured derived series to the joined dataset, in order, so that a derived series
+/// can build on the ones before it. Series whose input columns are missing are skipped.
+pub fn derive_series(result: DataFrame,
+                     column_metadata: &mut Vec<ColumnMetadata>,
+                     derived_series_configs: &[DerivedSeriesConfig]) -> DataFrame {
+    let mut result = result.sort(["quarter"], SortMultipleOptions::new()).unwrap();
+
+    for derived_series_config in derived_series_configs {
+        let name = derived_series_config.name;
+
+        let missing_columns: Vec<&str> = input_columns(&derived_series_config.operation)
+            .into_iter()
+            .filter(|column_name| result.column(column_name).is_err())
+            .collect();
+
+        if !missing_columns.is_empty() {
+            warn!("skipping derived series {name}, missing columns: {missing_columns:?}");
+            continue;
+        }
+
+        let expression = derived_series_expr(derived_series_config, column_metadata).alias(name);
+        result = result.lazy().with_column(expression).collect().unwrap();
+
+        let unit_config = derived_series_config.unit_config;
+        column_metadata.push(ColumnMetadata::new(name, "derived", unit_config.unit, unit_config.scale));
+        info!("derived series {name} added");
+    }
+
+    result
+}
+
+fn input_columns(operation: &DerivedSeriesOperation) -> Vec<&'static str> {
+    match *operation {
+        DerivedSeriesOperation::Ratio { numerator, denominator } => vec![numerator, denominator],
+        DerivedSeriesOperation::Deflate { nominal, price_index } => vec![nominal, price_index],
+        DerivedSeriesOperation::PerCapita { value, population } => vec![value, population],
+        DerivedSeriesOperation::QuarterOnQuarterGrowth(column)
+        | DerivedSeriesOperation::YearOnYearGrowth(column)
+        | DerivedSeriesOperation::AnnualizedGrowth(column)
+        | DerivedSeriesOperation::LogDifference(column) => vec![column],
+    }
+}
+
+/// Levels are brought to their base unit using the scale from the column metadata and expressed
+/// in the scale of the derived series. Growth rates are scale-free.
+fn derived_series_expr(derived_series_config: &DerivedSeriesConfig, column_metadata: &[ColumnMetadata]) -> Expr {
+    let scaled = |column_name: &str| col(column_name) * lit(scale_of(column_name, column_metadata).multiplier());
+    let output_scale = lit(derived_series_config.unit_config.scale.multiplier());
+
+    let expression = match derived_series_config.operation {
+        DerivedSeriesOperation::Ratio { numerator, denominator } => {
+            scaled(numerator) / scaled(denominator) / output_scale
+        },
+        DerivedSeriesOperation::Deflate { nominal, price_index } => {
+            scaled(nominal) / col(price_index) * lit(100.0) / output_scale
+        },
+        DerivedSeriesOperation::PerCapita { value, population } => {
+            scaled(value) / scaled(population) / output_scale
+        },
+        DerivedSeriesOperation::QuarterOnQuarterGrowth(column) => growth(column, 1),
+        DerivedSeriesOperation::YearOnYearGrowth(column) => growth(column, 4),
+        DerivedSeriesOperation::AnnualizedGrowth(column) => {
+            ((col(column) / col(column).shift(lit(1))).pow(4.0) - lit(1.0)) * lit(100.0)
+        },
+        DerivedSeriesOperation::LogDifference(column) => {
+            (col(column).log(std::f64::consts::E) - col(column).shift(lit(1)).log(std::f64::consts::E)) * lit(100.0)
+        },
+    };
+
+    expression * lit(derived_series_config.multiplier)
+}
+
+fn growth(column: &str, periods: i64) -> Expr {
+    (col(column) / col(column).shift(lit(periods)) - lit(1.0)) * lit(100.0)
+}
+
+fn scale_of(column_name: &str, column_metadata: &[ColumnMetadata]) -> Scale {
+    column_metadata
+        .iter()
+        .find(|metadata| metadata.column_name == column_name)
+        .map(|metadata| metadata.scale)
+        .unwrap_or(Scale::Units)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::datasets_config::datasets_config::UnitConfig;
+
+    use super::*;
+
+    fn joined_dataframe() -> DataFrame {
+        df![
+            "quarter" => &["2023-Q2", "2023-Q1", "2023-Q3", "2023-Q4", "2024-Q1"],
+            "gdp" => &[110.0, 100.0, 121.0, 133.1, 150.0],
+            "debt" => &[220.0, 200.0, 242.0, 266.2, 300.0],
+            "deflator" => &[110.0, 100.0, 110.0, 121.0, 125.0],
+            "population" => &[2.0, 2.0, 2.0, 2.0, 2.0]
+        ].unwrap()
+    }
+
+    fn column_metadata() -> Vec<ColumnMetadata> {
+        vec![
+            ColumnMetadata::new("gdp", "gdp", "USD", Scale::Billions),
+            ColumnMetadata::new("debt", "debt", "USD", Scale::Billions),
+            ColumnMetadata::new("deflator", "deflator", "index", Scale::Units),
+            ColumnMetadata::new("population", "population", "persons", Scale::Millions),
+        ]
+    }
+
+    fn values(df: &DataFrame, column_name: &str) -> Vec<Option<f64>> {
+        df.column(column_name).unwrap().f64().unwrap().into_iter().collect()
+    }
+
+    fn assert_close(actual: Option<f64>, expected: f64) {
+        let actual = actual.expect("expected a value");
+        assert!((actual - expected).abs() < 1e-6, "{actual} != {expected}");
+    }
+
+    #[test]
+    fn test_derive_series_levels() {
+        let percent = UnitConfig::new("percent", Scale::Units, false);
+        let configs = vec![
+            DerivedSeriesConfig::new("debt_to_gdp",
+                                     DerivedSeriesOperation::Ratio { numerator: "debt", denominator: "gdp" },
+                                     100.0,
+                                     percent),
+            DerivedSeriesConfig::new("gdp_real",
+                                     DerivedSeriesOperation::Deflate { nominal: "gdp", price_index: "deflator" },
+                                     1.0,
+                                     UnitConfig::new("USD", Scale::Billions, true)),
+            DerivedSeriesConfig::new("gdp_per_capita",
+                                     DerivedSeriesOperation::PerCapita { value: "gdp", population: "population" },
+                                     1.0,
+                                     UnitConfig::new("USD", Scale::Units, true)),
+        ];
+
+        let mut metadata = column_metadata();
+        let result = derive_series(joined_dataframe(), &mut metadata, &configs);
+
+        assert_close(values(&result, "debt_to_gdp")[0], 200.0);
+        assert_close(values(&result, "gdp_real")[1], 100.0);
+        assert_close(values(&result, "gdp_real")[2], 110.0);
+        // 100 billion USD over 2 million persons
+        assert_close(values(&result, "gdp_per_capita")[0], 50_000.0);
+
+        assert_eq!(metadata.len(), 7);
+        assert_eq!(metadata[4], ColumnMetadata::new("debt_to_gdp", "derived", "percent", Scale::Units));
+    }
+
+    #[test]
+    fn test_derive_series_growth_rates() {
+        let percent = UnitConfig::new("percent", Scale::Units, false);
+        let configs = vec![
+            DerivedSeriesConfig::new("gdp_qoq", DerivedSeriesOperation::QuarterOnQuarterGrowth("gdp"), 1.0, percent),
+            DerivedSeriesConfig::new("gdp_yoy", DerivedSeriesOperation::YearOnYearGrowth("gdp"), 1.0, percent),
+            DerivedSeriesConfig::new("gdp_annualized", DerivedSeriesOperation::AnnualizedGrowth("gdp"), 1.0, percent),
+            DerivedSeriesConfig::new("gdp_log_diff", DerivedSeriesOperation::LogDifference("gdp"), 1.0, percent),
+        ];
+
+        let mut metadata = column_metadata();
+        let result = derive_series(joined_dataframe(), &mut metadata, &configs);
+
+        let quarters = result.column("quarter").unwrap().str().unwrap().into_no_null_iter().collect::<Vec<_>>();
+        assert_eq!(quarters, vec!["2023-Q1", "2023-Q2", "2023-Q3", "2023-Q4", "2024-Q1"]);
+
+        let qoq = values(&result, "gdp_qoq");
+        assert_eq!(qoq[0], None);
+        assert_close(qoq[1], 10.0);
+        assert_close(qoq[3], 10.0);
+
+        let yoy = values(&result, "gdp_yoy");
+        assert_eq!(yoy[3], None);
+        assert_close(yoy[4], 50.0);
+
+        assert_close(values(&result, "gdp_annualized")[1], 46.41);
+        assert_close(values(&result, "gdp_log_diff")[1], 100.0 * 1.1_f64.ln());
+    }
+
+    #[test]
+    fn test_derive_series_chains_and_skips_missing_columns() {
+        let percent = UnitConfig::new("percent", Scale::Units, false);
+        let configs = vec![
+            DerivedSeriesConfig::new("missing_ratio",
+                                     DerivedSeriesOperation::Ratio { numerator: "debt", denominator: "not_there" },
+                                     1.0,
+                                     percent),
+            DerivedSeriesConfig::new("gdp_real",
+                                     DerivedSeriesOperation::Deflate { nominal: "gdp", price_index: "deflator" },
+                                     1.0,
+                                     UnitConfig::new("USD", Scale::Billions, true)),
+            DerivedSeriesConfig::new("gdp_real_qoq", DerivedSeriesOperation::QuarterOnQuarterGrowth("gdp_real"), 1.0, percent),
+        ];
+
+        let mut metadata = column_metadata();
+        let result = derive_series(joined_dataframe(), &mut metadata, &configs);
+
+        assert!(result.column("missing_ratio").is_err());
+        assert_close(values(&result, "gdp_real_qoq")[1], 0.0);
+        assert_close(values(&result, "gdp_real_qoq")[2], 10.0);
+        assert_eq!(metadata.len(), 6);
+    }
+}
@@ -0,0 +1,470 @@
+use std::collections::HashSet;
+use std::error::Error;
+use std::fmt;
+
+use log::{info};
+use polars::prelude::*;
+
+use crate::datasets_config::datasets_config::ExpressionSeriesConfig;
+use crate::model::data_model::ColumnMetadata;
+
+/// Arithmetic (`+ - * / ^`), parentheses, numbers, column references and the functions
+/// `lag(x, n)`, `lead(x, n)`, `rolling_mean(x, n)`, `pct_change(x[, n])` and `log(x)`.
+#[derive(Clone, Debug, PartialEq)]
+pub enum ExpressionNode {
+    Number(f64),
+    Column(String),
+    Negate(Box<ExpressionNode>),
+    Binary(Box<ExpressionNode>, BinaryOperator, Box<ExpressionNode>),
+    Function(ExpressionFunction, Box<ExpressionNode>),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BinaryOperator {
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Power,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ExpressionFunction {
+    Lag(i64),
+    Lead(i64),
+    RollingMean(usize),
+    PctChange(i64),
+    Log,
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ExpressionError {
+    UnexpectedCharacter(char, usize),
+    UnexpectedToken(String),
+    UnexpectedEnd,
+    UnknownFunction(String),
+    InvalidArguments(String),
+    UnknownColumn(String),
+    DuplicateColumn(String),
+}
+
+impl fmt::Display for ExpressionError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ExpressionError::UnexpectedCharacter(c, position) => write!(f, "unexpected character '{c}' at position {position}"),
+            ExpressionError::UnexpectedToken(token) => write!(f, "unexpected token '{token}'"),
+            ExpressionError::UnexpectedEnd => write!(f, "unexpected end of expression"),
+            ExpressionError::UnknownFunction(name) => write!(f, "unknown function '{name}'"),
+            ExpressionError::InvalidArguments(message) => write!(f, "invalid arguments: {message}"),
+            ExpressionError::UnknownColumn(name) => write!(f, "unknown column '{name}'"),
+            ExpressionError::DuplicateColumn(name) => write!(f, "column '{name}' already exists"),
+        }
+    }
+}
+
+impl Error for ExpressionError {}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Number(f64),
+    Identifier(String),
+    Operator(char),
+    LeftParenthesis,
+    RightParenthesis,
+    Comma,
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, ExpressionError> {
+    let mut tokens: Vec<Token> = vec![];
+    let chars: Vec<char> = expression.chars().collect();
+    let mut position = 0;
+
+    while position < chars.len() {
+        let c = chars[position];
+
+        if c.is_whitespace() {
+            position += 1;
+        } else if c.is_ascii_digit() || c == '.' {
+            let start = position;
+            while position < chars.len() && (chars[position].is_ascii_digit() || chars[position] == '.') {
+                position += 1;
+            }
+            let literal: String = chars[start..position].iter().collect();
+            let number = literal.parse::<f64>().map_err(|_| ExpressionError::UnexpectedToken(literal))?;
+            tokens.push(Token::Number(number));
+        } else if c.is_alphabetic() || c == '_' {
+            let start = position;
+            while position < chars.len() && (chars[position].is_alphanumeric() || chars[position] == '_') {
+                position += 1;
+            }
+            tokens.push(Token::Identifier(chars[start..position].iter().collect()));
+        } else {
+            let token = match c {
+                '+' | '-' | '*' | '/' | '^' => Token::Operator(c),
+                '(' => Token::LeftParenthesis,
+                ')' => Token::RightParenthesis,
+                ',' => Token::Comma,
+                _ => return Err(ExpressionError::UnexpectedCharacter(c, position)),
+            };
+            tokens.push(token);
+            position += 1;
+        }
+    }
+
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, expected: Token) -> Result<(), ExpressionError> {
+        match self.next() {
+            Some(token) if token == expected => Ok(()),
+            Some(token) => Err(ExpressionError::UnexpectedToken(format!("{token:?}"))),
+            None => Err(ExpressionError::UnexpectedEnd),
+        }
+    }
+
+    // expression := term (('+' | '-') term)*
+    fn expression(&mut self) -> Result<ExpressionNode, ExpressionError> {
+        let mut node = self.term()?;
+
+        while let Some(Token::Operator(operator @ ('+' | '-'))) = self.peek().cloned() {
+            self.next();
+            let operator = if operator == '+' { BinaryOperator::Add } else { BinaryOperator::Subtract };
+            node = ExpressionNode::Binary(Box::new(node), operator, Box::new(self.term()?));
+        }
+
+        Ok(node)
+    }
+
+    // term := unary (('*' | '/') unary)*
+    fn term(&mut self) -> Result<ExpressionNode, ExpressionError> {
+        let mut node = self.unary()?;
+
+        while let Some(Token::Operator(operator @ ('*' | '/'))) = self.peek().cloned() {
+            self.next();
+            let operator = if operator == '*' { BinaryOperator::Multiply } else { BinaryOperator::Divide };
+            node = ExpressionNode::Binary(Box::new(node), operator, Box::new(self.unary()?));
+        }
+
+        Ok(node)
+    }
+
+    // unary := '-' unary | power
+    fn unary(&mut self) -> Result<ExpressionNode, ExpressionError> {
+        if let Some(Token::Operator('-')) = self.peek() {
+            self.next();
+            return Ok(ExpressionNode::Negate(Box::new(self.unary()?)));
+        }
+
+        self.power()
+    }
+
+    // power := primary ('^' unary)?
+    fn power(&mut self) -> Result<ExpressionNode, ExpressionError> {
+        let base = self.primary()?;
+
+        if let Some(Token::Operator('^')) = self.peek() {
+            self.next();
+            return Ok(ExpressionNode::Binary(Box::new(base), BinaryOperator::Power, Box::new(self.unary()?)));
+        }
+
+        Ok(base)
+    }
+
+    // primary := number | column | function '(' arguments ')' | '(' expression ')'
+    fn primary(&mut self) -> Result<ExpressionNode, ExpressionError> {
+        match self.next() {
+            Some(Token::Number(number)) => Ok(ExpressionNode::Number(number)),
+            Some(Token::Identifier(name)) => {
+                if let Some(Token::LeftParenthesis) = self.peek() {
+                    self.next();
+                    self.function(name)
+                } else {
+                    Ok(ExpressionNode::Column(name))
+                }
+            },
+            Some(Token::LeftParenthesis) => {
+                let node = self.expression()?;
+                self.expect(Token::RightParenthesis)?;
+                Ok(node)
+            },
+            Some(token) => Err(ExpressionError::UnexpectedToken(format!("{token:?}"))),
+            None => Err(ExpressionError::UnexpectedEnd),
+        }
+    }
+
+    fn function(&mut self, name: String) -> Result<ExpressionNode, ExpressionError> {
+        let argument = self.expression()?;
+
+        let mut periods: Vec<f64> = vec![];
+        while let Some(Token::Comma) = self.peek() {
+            self.next();
+            match self.next() {
+                Some(Token::Number(number)) => periods.push(number),
+                Some(token) => return Err(ExpressionError::InvalidArguments(format!("{name} expects a number of periods, got {token:?}"))),
+                None => return Err(ExpressionError::UnexpectedEnd),
+            }
+        }
+        self.expect(Token::RightParenthesis)?;
+
+        let period = |default: Option<f64>| -> Result<i64, ExpressionError> {
+            let value = match (periods.as_slice(), default) {
+                ([value], _) => *value,
+                ([], Some(value)) => value,
+                _ => return Err(ExpressionError::InvalidArguments(format!("{name} expects a column and a number of periods"))),
+            };
+
+            if value.fract() != 0.0 || value < 1.0 {
+                return Err(ExpressionError::InvalidArguments(format!("{name} expects a positive whole number of periods, got {value}")));
+            }
+
+            Ok(value as i64)
+        };
+
+        let function = match name.as_str() {
+            "lag" => ExpressionFunction::Lag(period(None)?),
+            "lead" => ExpressionFunction::Lead(period(None)?),
+            "rolling_mean" => ExpressionFunction::RollingMean(period(None)? as usize),
+            "pct_change" => ExpressionFunction::PctChange(period(Some(1.0))?),
+            "log" if periods.is_empty() => ExpressionFunction::Log,
+            "log" => return Err(ExpressionError::InvalidArguments("log expects a single argument".to_string())),
+            _ => return Err(ExpressionError::UnknownFunction(name)),
+        };
+
+        Ok(ExpressionNode::Function(function, Box::new(argument)))
+    }
+}
+
+pub fn parse_expression(expression: &str) -> Result<ExpressionNode, ExpressionError> {
+    let mut parser = Parser { tokens: tokenize(expression)?, position: 0 };
+    let node = parser.expression()?;
+
+    match parser.next() {
+        None => Ok(node),
+        Some(token) => Err(ExpressionError::UnexpectedToken(format!("{token:?}"))),
+    }
+}
+
+fn referenced_columns(node: &ExpressionNode, columns: &mut Vec<String>) {
+    match node {
+        ExpressionNode::Number(_) => {},
+        ExpressionNode::Column(name) => columns.push(name.clone()),
+        ExpressionNode::Negate(inner) | ExpressionNode::Function(_, inner) => referenced_columns(inner, columns),
+        ExpressionNode::Binary(left, _, right) => {
+            referenced_columns(left, columns);
+            referenced_columns(right, columns);
+        },
+    }
+}
+
+pub fn compile_expression(node: &ExpressionNode) -> Expr {
+    match node {
+        ExpressionNode::Number(number) => lit(*number),
+        ExpressionNode::Column(name) => col(name.as_str()),
+        ExpressionNode::Negate(inner) => lit(0.0) - compile_expression(inner),
+        ExpressionNode::Binary(left, operator, right) => {
+            let left = compile_expression(left);
+            let right = compile_expression(right);
+
+            match operator {
+                BinaryOperator::Add => left + right,
+                BinaryOperator::Subtract => left - right,
+                BinaryOperator::Multiply => left * right,
+                BinaryOperator::Divide => left / right,
+                BinaryOperator::Power => left.pow(right),
+            }
+        },
+        ExpressionNode::Function(function, inner) => {
+            let inner = compile_expression(inner);
+
+            match function {
+                ExpressionFunction::Lag(periods) => inner.shift(lit(*periods)),
+                ExpressionFunction::Lead(periods) => inner.shift(lit(-periods)),
+                ExpressionFunction::RollingMean(window_size) => inner.rolling_mean(RollingOptionsFixedWindow {
+                    window_size: *window_size,
+                    min_periods: *window_size,
+                    ..Default::default()
+                }),
+                ExpressionFunction::PctChange(periods) => inner.clone() / inner.shift(lit(*periods)) - lit(1.0),
+                ExpressionFunction::Log => inner.log(std::f64::consts::E),
+            }
+        },
+    }
+}
+
+/// Parses and validates every configured expression before evaluating any of them, so that a
+/// typo in the catalog fails the run instead of producing a partial result. An expression can
+/// reference the joined columns and the expression series defined before it, but not replace them.
+pub fn evaluate_expression_series(result: DataFrame,
+                                  column_metadata: &mut Vec<ColumnMetadata>,
+                                  expression_series_configs: &[ExpressionSeriesConfig]) -> Result<DataFrame, Box<dyn Error>> {
+    let mut known_columns: HashSet<String> = result.get_column_names().into_iter().map(|name| name.to_string()).collect();
+    let mut compiled_expressions: Vec<Expr> = vec![];
+
+    for expression_series_config in expression_series_configs {
+        let name = expression_series_config.name;
+        if known_columns.contains(name) {
+            return Err(format!("expression series {name}: {}", ExpressionError::DuplicateColumn(name.to_string())).into());
+        }
+
+        let node = parse_expression(expression_series_config.expression)
+            .map_err(|e| format!("expression series {name}: {e}"))?;
+
+        let mut columns: Vec<String> = vec![];
+        referenced_columns(&node, &mut columns);
+
+        if let Some(unknown_column) = columns.into_iter().find(|column| !known_columns.contains(column)) {
+            return Err(format!("expression series {name}: {}", ExpressionError::UnknownColumn(unknown_column)).into());
+        }
+
+        known_columns.insert(name.to_string());
+        compiled_expressions.push(compile_expression(&node).alias(name));
+    }
+
+    let mut result = result.sort(["quarter"], SortMultipleOptions::new())?;
+
+    for (expression_series_config, expression) in expression_series_configs.iter().zip(compiled_expressions) {
+        let name = expression_series_config.name;
+        result = result.lazy().with_column(expression).collect()?;
+
+        let unit_config = expression_series_config.unit_config;
+        column_metadata.push(ColumnMetadata::new(name, "expression", unit_config.unit, unit_config.scale));
+        info!("expression series {name} added");
+    }
+
+    Ok(result)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::datasets_config::datasets_config::{Scale, UnitConfig};
+
+    use super::*;
+
+    fn joined_dataframe() -> DataFrame {
+        df![
+            "quarter" => &["2023-Q2", "2023-Q1", "2023-Q3", "2023-Q4"],
+            "us_gdp" => &[220.0, 200.0, 240.0, 260.0],
+            "us_gdp_deflator" => &[110.0, 100.0, 120.0, 130.0]
+        ].unwrap()
+    }
+
+    fn evaluate(expression: &'static str) -> Vec<Option<f64>> {
+        let config = ExpressionSeriesConfig::new("result", expression, UnitConfig::new("test", Scale::Units, false));
+        let mut metadata: Vec<ColumnMetadata> = vec![];
+        let result = evaluate_expression_series(joined_dataframe(), &mut metadata, &[config]).unwrap();
+
+        result.column("result").unwrap().f64().unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn test_parse_expression_precedence() {
+        let node = parse_expression("a + b * 2 ^ 2").unwrap();
+
+        let expected = ExpressionNode::Binary(
+            Box::new(ExpressionNode::Column("a".to_string())),
+            BinaryOperator::Add,
+            Box::new(ExpressionNode::Binary(
+                Box::new(ExpressionNode::Column("b".to_string())),
+                BinaryOperator::Multiply,
+                Box::new(ExpressionNode::Binary(
+                    Box::new(ExpressionNode::Number(2.0)),
+                    BinaryOperator::Power,
+                    Box::new(ExpressionNode::Number(2.0)),
+                )),
+            )),
+        );
+
+        assert_eq!(node, expected);
+    }
+
+    #[test]
+    fn test_parse_expression_errors() {
+        assert_eq!(parse_expression("a $ b"), Err(ExpressionError::UnexpectedCharacter('$', 2)));
+        assert_eq!(parse_expression("(a + b"), Err(ExpressionError::UnexpectedEnd));
+        assert_eq!(parse_expression("median(a)"), Err(ExpressionError::UnknownFunction("median".to_string())));
+        assert!(matches!(parse_expression("lag(a)"), Err(ExpressionError::InvalidArguments(_))));
+        assert!(matches!(parse_expression("lag(a, 1.5)"), Err(ExpressionError::InvalidArguments(_))));
+        assert!(matches!(parse_expression("a b"), Err(ExpressionError::UnexpectedToken(_))));
+    }
+
+    #[test]
+    fn test_evaluate_arithmetic() {
+        assert_eq!(evaluate("us_gdp / us_gdp_deflator * 100"), vec![Some(200.0); 4]);
+        assert_eq!(evaluate("-(us_gdp - 200) / 2"), vec![Some(0.0), Some(-10.0), Some(-20.0), Some(-30.0)]);
+    }
+
+    #[test]
+    fn test_evaluate_functions() {
+        assert_eq!(evaluate("lag(us_gdp, 1)"), vec![None, Some(200.0), Some(220.0), Some(240.0)]);
+        assert_eq!(evaluate("lead(us_gdp, 2)"), vec![Some(240.0), Some(260.0), None, None]);
+        assert_eq!(evaluate("rolling_mean(us_gdp, 2)"), vec![None, Some(210.0), Some(230.0), Some(250.0)]);
+        assert!((evaluate("pct_change(us_gdp_deflator)")[1].unwrap() - 0.1).abs() < 1e-9);
+        assert!((evaluate("pct_change(us_gdp, 2)")[2].unwrap() - 0.2).abs() < 1e-9);
+        assert_eq!(evaluate("log(us_gdp / us_gdp)"), vec![Some(0.0); 4]);
+    }
+
+    #[test]
+    fn test_evaluate_expression_series_chains_and_records_metadata() {
+        let configs = vec![
+            ExpressionSeriesConfig::new("us_gdp_real", "us_gdp / us_gdp_deflator * 100", UnitConfig::new("USD", Scale::Billions, true)),
+            ExpressionSeriesConfig::new("us_gdp_real_change", "us_gdp_real - lag(us_gdp_real, 1)", UnitConfig::new("USD", Scale::Billions, true)),
+        ];
+        let mut metadata: Vec<ColumnMetadata> = vec![];
+
+        let result = evaluate_expression_series(joined_dataframe(), &mut metadata, &configs).unwrap();
+
+        let change = result.column("us_gdp_real_change").unwrap().f64().unwrap().into_iter().collect::<Vec<_>>();
+        assert_eq!(change, vec![None, Some(0.0), Some(0.0), Some(0.0)]);
+        assert_eq!(metadata, vec![
+            ColumnMetadata::new("us_gdp_real", "expression", "USD", Scale::Billions),
+            ColumnMetadata::new("us_gdp_real_change", "expression", "USD", Scale::Billions),
+        ]);
+    }
+
+    #[test]
+    fn test_evaluate_expression_series_rejects_unknown_columns() {
+        let configs = vec![
+            ExpressionSeriesConfig::new("spread", "us_10y - de_10y", UnitConfig::new("percent", Scale::Units, false)),
+        ];
+        let mut metadata: Vec<ColumnMetadata> = vec![];
+
+        let error = evaluate_expression_series(joined_dataframe(), &mut metadata, &configs).unwrap_err();
+
+        assert_eq!(error.to_string(), "expression series spread: unknown column 'us_10y'");
+        assert!(metadata.is_empty());
+    }
+
+    #[test]
+    fn test_evaluate_expression_series_rejects_existing_columns() {
+        let configs = vec![
+            ExpressionSeriesConfig::new("us_gdp_real", "us_gdp / us_gdp_deflator * 100", UnitConfig::new("USD", Scale::Billions, true)),
+            ExpressionSeriesConfig::new("us_gdp", "us_gdp_real * 2", UnitConfig::new("USD", Scale::Billions, true)),
+        ];
+        let mut metadata: Vec<ColumnMetadata> = vec![];
+
+        let error = evaluate_expression_series(joined_dataframe(), &mut metadata, &configs).unwrap_err();
+
+        assert_eq!(error.to_string(), "expression series us_gdp: column 'us_gdp' already exists");
+        assert!(metadata.is_empty());
+
+        let repeated = vec![configs[0], configs[0]];
+        assert!(evaluate_expression_series(joined_dataframe(), &mut metadata, &repeated).is_err());
+    }
+}
@@ -0,0 +1,342 @@
+use std::f64::consts::PI;
+
+use log::{info, warn};
+use polars::prelude::*;
+
+use crate::datasets_config::datasets_config::{CycleFilter, CycleFilterConfig, Scale};
+use crate::model::data_model::ColumnMetadata;
+use crate::transformer::regression::ols;
+use crate::transformer::stats::column_values;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct FilteredSeries {
+    pub trend: Vec<Option<f64>>,
+    pub cycle: Vec<Option<f64>>,
+}
+
+/// Adds a trend and a cycle column for every configured filter. Columns that are missing, have
+/// gaps, or are too short for the filter are skipped, as are non-positive columns filtered in logs.
+pub fn apply_cycle_filters(result: DataFrame,
+                           column_metadata: &mut Vec<ColumnMetadata>,
+                           cycle_filter_configs: &[CycleFilterConfig]) -> DataFrame {
+    let mut result = result.sort(["quarter"], SortMultipleOptions::new()).unwrap();
+
+    for cycle_filter_config in cycle_filter_configs {
+        let column_name = cycle_filter_config.column;
+        let suffix = cycle_filter_config.filter.suffix();
+
+        let values = match column_values(&result, column_name).and_then(|values| values.into_iter().collect::<Option<Vec<f64>>>()) {
+            Some(values) => values,
+            None => {
+                warn!("skipping {suffix} filter of {column_name}, column is missing or has gaps");
+                continue;
+            }
+        };
+
+        let filtered = match filter_column(&values, cycle_filter_config) {
+            Some(filtered) => filtered,
+            None => {
+                warn!("skipping {suffix} filter of {column_name}, too few or non-positive values");
+                continue;
+            }
+        };
+
+        let trend_column_name = format!("{column_name}_{suffix}_trend");
+        let cycle_column_name = format!("{column_name}_{suffix}_cycle");
+        result.with_column(Series::new(trend_column_name.as_str().into(), filtered.trend)).unwrap();
+        result.with_column(Series::new(cycle_column_name.as_str().into(), filtered.cycle)).unwrap();
+
+        let (dataset_name, unit, scale) = column_metadata.iter()
+            .find(|metadata| metadata.column_name == column_name)
+            .map(|metadata| (metadata.dataset_name.clone(), metadata.unit.clone(), metadata.scale))
+            .unwrap_or_else(|| ("derived".to_string(), "unspecified".to_string(), Scale::Units));
+        let description = filter_description(&cycle_filter_config.filter);
+
+        let mut trend_metadata = ColumnMetadata::new(&trend_column_name, &dataset_name, &unit, scale);
+        trend_metadata.transformations.push(format!("{description} trend of {column_name}"));
+
+        let mut cycle_metadata = if cycle_filter_config.logarithm {
+            ColumnMetadata::new(&cycle_column_name, &dataset_name, "percent deviation from trend", Scale::Units)
+        } else {
+            ColumnMetadata::new(&cycle_column_name, &dataset_name, &unit, scale)
+        };
+        cycle_metadata.transformations.push(format!("{description} cycle of {column_name}"));
+
+        column_metadata.push(trend_metadata);
+        column_metadata.push(cycle_metadata);
+        info!("{description} filter applied to {column_name}");
+    }
+
+    result
+}
+
+fn filter_description(filter: &CycleFilter) -> String {
+    match filter {
+        CycleFilter::HodrickPrescott { lambda } => format!("Hodrick-Prescott (lambda {lambda})"),
+        CycleFilter::BaxterKing { min_period, max_period, lead_lags } => {
+            format!("Baxter-King ({min_period}-{max_period} quarters, {lead_lags} leads/lags)")
+        },
+        CycleFilter::Hamilton { horizon, lags } => format!("Hamilton ({horizon} quarters ahead, {lags} lags)"),
+    }
+}
+
+/// In logarithms the filter runs on `100 * ln(value)`, the trend is reported back in levels and
+/// the cycle in percent of the trend.
+fn filter_column(values: &[f64], cycle_filter_config: &CycleFilterConfig) -> Option<FilteredSeries> {
+    if !cycle_filter_config.logarithm {
+        return filter_series(values, &cycle_filter_config.filter);
+    }
+
+    if values.iter().any(|value| *value <= 0.0) {
+        return None;
+    }
+
+    let logarithms: Vec<f64> = values.iter().map(|value| 100.0 * value.ln()).collect();
+    let filtered = filter_series(&logarithms, &cycle_filter_config.filter)?;
+
+    Some(FilteredSeries {
+        trend: filtered.trend.into_iter().map(|trend| trend.map(|trend| (trend / 100.0).exp())).collect(),
+        cycle: filtered.cycle,
+    })
+}
+
+pub fn filter_series(values: &[f64], filter: &CycleFilter) -> Option<FilteredSeries> {
+    match *filter {
+        CycleFilter::HodrickPrescott { lambda } => {
+            let trend = hodrick_prescott_trend(values, lambda)?;
+            Some(FilteredSeries {
+                cycle: values.iter().zip(&trend).map(|(value, trend)| Some(value - trend)).collect(),
+                trend: trend.into_iter().map(Some).collect(),
+            })
+        },
+        CycleFilter::BaxterKing { min_period, max_period, lead_lags } => {
+            let cycle = baxter_king_cycle(values, min_period, max_period, lead_lags)?;
+            Some(FilteredSeries {
+                trend: values.iter().zip(&cycle).map(|(value, cycle)| cycle.map(|cycle| value - cycle)).collect(),
+                cycle,
+            })
+        },
+        CycleFilter::Hamilton { horizon, lags } => {
+            let trend = hamilton_trend(values, horizon, lags)?;
+            Some(FilteredSeries {
+                cycle: values.iter().zip(&trend).map(|(value, trend)| trend.map(|trend| value - trend)).collect(),
+                trend,
+            })
+        },
+    }
+}
+
+/// Solves `(I + lambda * D'D) trend = values`, where `D` takes second differences. The system is
+/// symmetric positive definite with two bands on each side of the diagonal, so it is eliminated
+/// without pivoting.
+fn hodrick_prescott_trend(values: &[f64], lambda: f64) -> Option<Vec<f64>> {
+    const BANDS: usize = 2;
+    let size = values.len();
+
+    if size < 3 {
+        return None;
+    }
+
+    // band[row][BANDS + offset] holds the entry of column row + offset
+    let mut band = vec![[0.0; 2 * BANDS + 1]; size];
+    for entries in band.iter_mut() {
+        entries[BANDS] = 1.0;
+    }
+    for start in 0..size - 2 {
+        let difference = [1.0, -2.0, 1.0];
+        for (i, weight_i) in difference.iter().enumerate() {
+            for (j, weight_j) in difference.iter().enumerate() {
+                band[start + i][BANDS + j - i] += lambda * weight_i * weight_j;
+            }
+        }
+    }
+
+    let mut right_hand_side = values.to_vec();
+    for pivot in 0..size {
+        for row in pivot + 1..(pivot + BANDS + 1).min(size) {
+            let factor = band[row][BANDS + pivot - row] / band[pivot][BANDS];
+            for column in pivot..(pivot + BANDS + 1).min(size) {
+                band[row][BANDS + column - row] -= factor * band[pivot][BANDS + column - pivot];
+            }
+            right_hand_side[row] -= factor * right_hand_side[pivot];
+        }
+    }
+
+    let mut trend = vec![0.0; size];
+    for row in (0..size).rev() {
+        let known: f64 = (row + 1..(row + BANDS + 1).min(size))
+            .map(|column| band[row][BANDS + column - row] * trend[column])
+            .sum();
+        trend[row] = (right_hand_side[row] - known) / band[row][BANDS];
+    }
+
+    Some(trend)
+}
+
+/// Symmetric moving average approximating the ideal band-pass filter, with the weights shifted
+/// to sum to zero so that linear trends are removed.
+fn baxter_king_cycle(values: &[f64], min_period: usize, max_period: usize, lead_lags: usize) -> Option<Vec<Option<f64>>> {
+    if min_period < 2 || min_period >= max_period || values.len() <= 2 * lead_lags {
+        return None;
+    }
+
+    let low_frequency = 2.0 * PI / max_period as f64;
+    let high_frequency = 2.0 * PI / min_period as f64;
+
+    let ideal: Vec<f64> = (0..=lead_lags)
+        .map(|lag| {
+            if lag == 0 {
+                (high_frequency - low_frequency) / PI
+            } else {
+                let lag = lag as f64;
+                ((high_frequency * lag).sin() - (low_frequency * lag).sin()) / (PI * lag)
+            }
+        })
+        .collect();
+    let ideal_sum = ideal[0] + 2.0 * ideal[1..].iter().sum::<f64>();
+    let adjustment = ideal_sum / (2 * lead_lags + 1) as f64;
+    let weights: Vec<f64> = ideal.iter().map(|weight| weight - adjustment).collect();
+
+    Some((0..values.len())
+        .map(|index| {
+            if index < lead_lags || index + lead_lags >= values.len() {
+                return None;
+            }
+
+            let leads_and_lags: f64 = (1..=lead_lags)
+                .map(|lag| weights[lag] * (values[index - lag] + values[index + lag]))
+                .sum();
+            Some(weights[0] * values[index] + leads_and_lags)
+        })
+        .collect())
+}
+
+/// The trend is the value predicted `horizon` quarters earlier by regressing on the `lags` most
+/// recent values, and the cycle is the prediction error.
+fn hamilton_trend(values: &[f64], horizon: usize, lags: usize) -> Option<Vec<Option<f64>>> {
+    if horizon == 0 || lags == 0 {
+        return None;
+    }
+
+    let first_target = horizon + lags - 1;
+    if values.len() <= first_target {
+        return None;
+    }
+
+    let predictors: Vec<Vec<f64>> = (first_target..values.len())
+        .map(|target| (0..lags).map(|lag| values[target - horizon - lag]).collect())
+        .collect();
+    let fit = ols(&values[first_target..], &predictors, None)?;
+
+    let mut trend: Vec<Option<f64>> = vec![None; first_target];
+    trend.extend(predictors.iter().map(|row| {
+        Some(fit.coefficients[0] + row.iter().zip(&fit.coefficients[1..]).map(|(value, beta)| value * beta).sum::<f64>())
+    }));
+
+    Some(trend)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_helpers::test_helpers::{assert_close, noise};
+
+    use super::*;
+
+    #[test]
+    fn test_hodrick_prescott() {
+        let trend = hodrick_prescott_trend(&[1.0, 3.0, 2.0, 5.0, 4.0], 1.0).unwrap();
+        for (actual, expected) in trend.iter().zip([1.2708333333, 2.2916666667, 3.0416666667, 3.9583333333, 4.4375]) {
+            assert_close(*actual, expected, 1e-9);
+        }
+
+        let linear: Vec<f64> = (0..20).map(|index| 3.0 + 0.5 * index as f64).collect();
+        let filtered = filter_series(&linear, &CycleFilter::HodrickPrescott { lambda: 1600.0 }).unwrap();
+        for (cycle, trend) in filtered.cycle.iter().zip(&filtered.trend).zip(&linear).map(|((cycle, trend), value)| (cycle.unwrap(), trend.unwrap() - value)) {
+            assert_close(cycle, 0.0, 1e-6);
+            assert_close(trend, 0.0, 1e-6);
+        }
+
+        assert_eq!(hodrick_prescott_trend(&[1.0, 2.0], 1600.0), None);
+    }
+
+    #[test]
+    fn test_baxter_king() {
+        let filter = CycleFilter::BaxterKing { min_period: 6, max_period: 32, lead_lags: 12 };
+
+        let linear: Vec<f64> = (0..40).map(|index| 10.0 + 2.0 * index as f64).collect();
+        let filtered = filter_series(&linear, &filter).unwrap();
+        assert_eq!(filtered.cycle.iter().filter(|cycle| cycle.is_none()).count(), 24);
+        assert_eq!(filtered.cycle[11], None);
+        assert_close(filtered.cycle[12].unwrap(), 0.0, 1e-9);
+        assert_close(filtered.trend[20].unwrap(), linear[20], 1e-9);
+
+        // a 12-quarter cycle passes the band, a 100-quarter one does not
+        let business_cycle: Vec<f64> = (0..60).map(|index| (2.0 * PI * index as f64 / 12.0).sin()).collect();
+        let slow_cycle: Vec<f64> = (0..60).map(|index| (2.0 * PI * index as f64 / 100.0).sin()).collect();
+        let business_cycle_filtered = filter_series(&business_cycle, &filter).unwrap();
+        let slow_cycle_filtered = filter_series(&slow_cycle, &filter).unwrap();
+        for (index, value) in business_cycle.iter().enumerate().take(48).skip(12) {
+            assert_close(business_cycle_filtered.cycle[index].unwrap(), *value, 0.1);
+            assert_close(slow_cycle_filtered.cycle[index].unwrap(), 0.0, 0.1);
+        }
+
+        assert_eq!(filter_series(&linear[..24], &filter), None);
+    }
+
+    #[test]
+    fn test_hamilton() {
+        let values: Vec<f64> = noise(60, 7).iter().scan(100.0, |level, shock| {
+            *level += shock;
+            Some(*level)
+        }).collect();
+
+        let filtered = filter_series(&values, &CycleFilter::Hamilton { horizon: 8, lags: 4 }).unwrap();
+
+        assert!(filtered.trend[..11].iter().all(|trend| trend.is_none()));
+        let cycle: Vec<f64> = filtered.cycle.iter().flatten().copied().collect();
+        assert_eq!(cycle.len(), 49);
+        // regression residuals average to zero
+        assert_close(cycle.iter().sum::<f64>() / cycle.len() as f64, 0.0, 1e-6);
+        assert_close(filtered.trend[30].unwrap() + filtered.cycle[30].unwrap(), values[30], 1e-9);
+
+        assert_eq!(filter_series(&values[..12], &CycleFilter::Hamilton { horizon: 8, lags: 4 }), None);
+    }
+
+    #[test]
+    fn test_apply_cycle_filters() {
+        let gdp: Vec<f64> = (0..20).map(|index| 100.0 * 1.01_f64.powi(index)).collect();
+        let quarters: Vec<String> = (0..20).map(|index| format!("{}-Q{}", 2000 + index / 4, index % 4 + 1)).collect();
+        let df = df![
+            "quarter" => quarters,
+            "gdp" => gdp.clone()
+        ].unwrap();
+        let gdp_metadata = ColumnMetadata::new("gdp", "derived", "USD", Scale::Billions);
+        let mut metadata = vec![gdp_metadata.clone()];
+        let configs = [
+            CycleFilterConfig::new("gdp", CycleFilter::HodrickPrescott { lambda: 1600.0 }, true),
+            CycleFilterConfig::new("gdp", CycleFilter::HodrickPrescott { lambda: 1600.0 }, false),
+            CycleFilterConfig::new("not_there", CycleFilter::HodrickPrescott { lambda: 1600.0 }, true),
+        ];
+
+        let result = apply_cycle_filters(df.clone(), &mut metadata, &configs[..1]);
+
+        // constant growth is a linear trend in logs
+        let trend = column_values(&result, "gdp_hp_trend").unwrap();
+        let cycle = column_values(&result, "gdp_hp_cycle").unwrap();
+        assert_close(trend[10].unwrap(), gdp[10], 1e-6);
+        assert_close(cycle[10].unwrap(), 0.0, 1e-6);
+
+        assert_eq!(metadata.len(), 3);
+        assert_eq!(metadata[1].column_name, "gdp_hp_trend");
+        assert_eq!(metadata[1].scale, Scale::Billions);
+        assert_eq!(metadata[1].transformations, vec!["Hodrick-Prescott (lambda 1600) trend of gdp"]);
+        assert_eq!(metadata[2].unit, "percent deviation from trend");
+
+        let mut metadata = vec![gdp_metadata];
+        let result = apply_cycle_filters(df, &mut metadata, &configs[1..]);
+        assert_eq!(metadata.len(), 3);
+        assert_eq!(metadata[2].unit, "USD");
+        assert!(result.column("not_there_hp_trend").is_err());
+    }
+}
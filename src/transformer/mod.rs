@@ -1 +1,10 @@
-pub mod normalize;
\ No newline at end of file
+pub mod normalize;
+pub mod units;
+pub mod seasonal;
+pub mod derived;
+pub mod filters;
+pub mod expression;
+pub mod rebase;
+pub mod stats;
+pub mod regression;
+pub mod recession;
\ No newline at end of file
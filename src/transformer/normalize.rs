@@ -1,10 +1,12 @@
-use std::collections::HashSet;
+use std::collections::{BTreeMap, HashSet};
 
+use log::warn;
 use polars::prelude::*;
 use rayon::prelude::*;
 
-use crate::datasets_config::datasets_config::QuarterlyAverageConfig;
+use crate::datasets_config::datasets_config::{DatasetConfig, EuConversionMethod, QuarterlyAverageConfig, UnitConfig};
 use crate::model::data_model::{AllDatasets, DatasetWithConfig};
+use crate::transformer::seasonal::is_seasonal_factor;
 
 pub fn normalize_data(all_datasets: AllDatasets) -> Vec<DatasetWithConfig> {
     all_datasets
@@ -120,6 +122,12 @@ pub fn rename_columns(datasets: Vec<DatasetWithConfig>) -> Vec<DatasetWithConfig
             "eu_inflation" => {
                 dataframe.set_column_names(["quarter", "eu_inflation"]).unwrap();
             },
+            "eu_ppp" => {
+                dataframe.set_column_names(["year", "eur_per_intl_usd"]).unwrap();
+            },
+            "eu_population" => {
+                dataframe.set_column_names(["year", "eu_population"]).unwrap();
+            },
             _ => {}
         }
 
@@ -168,23 +176,19 @@ pub fn convert_eu_to_usd(all_datasets: Vec<DatasetWithConfig>) -> Vec<DatasetWit
 
     for eu_dataset in eu_datasets {
         let name = eu_dataset.name;
-        let eu_dataset_config = eu_dataset.dataset_config;
+        let eu_dataset_config = with_currency(eu_dataset.dataset_config, "USD");
         let eu_dataframe = eu_dataset.dataframe;
 
-        let eu_dataframe_joined = eu_dataframe.join(&fx_rates_df, ["quarter"], ["quarter"], JoinArgs::new(JoinType::Left), None).unwrap();
-
-        if name == "eu_gdp" {
-            let eu_dataframe_converted = eu_dataframe_joined.lazy().with_column((col("eu_gdp") * col("eur_to_usd")).alias("eu_gdp_converted")).collect().unwrap();
-            // let eu_dataframe_converted = eu_dataframe.lazy().with_column(col("eu_gdp") * col("eur_to_usd")).alias("eu_gdp_converted");
-            let eu_dataframe_converted = eu_dataframe_converted.select(["quarter", "eu_gdp_converted"]).unwrap();
-            let eu_dataset_with_config = DatasetWithConfig::new(name, eu_dataframe_converted, eu_dataset_config);
-            converted_eu_datasets.push(eu_dataset_with_config);
-        } else {
-            let eu_dataframe_converted = eu_dataframe_joined.lazy().with_column((col("eu_government_debt") * col("eur_to_usd")).alias("eu_government_debt_converted")).collect().unwrap();
-            let eu_dataframe_converted = eu_dataframe_converted.select(["quarter", "eu_government_debt_converted"]).unwrap();
-            let eu_dataset_with_config = DatasetWithConfig::new(name, eu_dataframe_converted, eu_dataset_config);
-            converted_eu_datasets.push(eu_dataset_with_config);
-        }
+        let converted_columns = converted_value_columns(&eu_dataframe, &eu_dataset.dataset_config, name, &format!("{name}_converted"),
+                                                        |value| value * col("eur_to_usd"));
+        let eu_dataframe_converted = eu_dataframe
+            .join(&fx_rates_df, ["quarter"], ["quarter"], JoinArgs::new(JoinType::Left), None).unwrap()
+            .lazy()
+            .select(converted_columns)
+            .collect().unwrap();
+        let eu_dataset_with_config = DatasetWithConfig::new(name, eu_dataframe_converted, eu_dataset_config)
+            .with_transformation(EuConversionMethod::MarketRate.description());
+        converted_eu_datasets.push(eu_dataset_with_config);
     }
 
     let mut us_datasets: Vec<DatasetWithConfig> = all_datasets.clone()
@@ -198,13 +202,159 @@ pub fn convert_eu_to_usd(all_datasets: Vec<DatasetWithConfig>) -> Vec<DatasetWit
     us_datasets
 }
 
+pub fn convert_eu_currency(all_datasets: Vec<DatasetWithConfig>,
+                           conversion_methods: &[EuConversionMethod]) -> Vec<DatasetWithConfig> {
+    // PPP runs first, as the market rate conversion replaces the EUR columns it reads from
+    let datasets = if conversion_methods.contains(&EuConversionMethod::PurchasingPowerParity) {
+        convert_eu_to_ppp(all_datasets)
+    } else {
+        all_datasets.into_iter().filter(|d| d.name != "eu_ppp").collect()
+    };
+
+    if conversion_methods.contains(&EuConversionMethod::MarketRate) {
+        convert_eu_to_usd(datasets)
+    } else {
+        datasets
+    }
+}
+
+/// Adds the EU GDP and debt in international dollars. Without an `eu_ppp` dataset, e.g. when
+/// its fetch failed, the PPP columns are left out with a warning.
+pub fn convert_eu_to_ppp(all_datasets: Vec<DatasetWithConfig>) -> Vec<DatasetWithConfig> {
+    let Some(ppp_df) = all_datasets.iter()
+        .find(|d| d.name == "eu_ppp")
+        .map(|d| d.dataframe.clone()) else {
+        warn!("no eu_ppp dataset, leaving the EU columns at purchasing power parity out of the result");
+        return all_datasets;
+    };
+
+    let eu_dataset_names: HashSet<&'static str> = ["eu_gdp", "eu_government_debt"].into_iter().collect();
+
+    let mut output_datasets: Vec<DatasetWithConfig> = vec![];
+
+    for dataset in all_datasets {
+        if eu_dataset_names.contains(dataset.name) {
+            let ppp_name = match dataset.name {
+                "eu_gdp" => "eu_gdp_ppp",
+                _ => "eu_government_debt_ppp",
+            };
+
+            let converted_columns = converted_value_columns(&dataset.dataframe, &dataset.dataset_config, dataset.name,
+                                                            &format!("{}_ppp_converted", dataset.name),
+                                                            |value| value / col("eur_per_intl_usd"));
+            let ppp_dataframe = dataset.dataframe
+                .join(&ppp_df, ["quarter"], ["quarter"], JoinArgs::new(JoinType::Left), None).unwrap()
+                .lazy()
+                .select(converted_columns)
+                .collect().unwrap();
+
+            output_datasets.push(DatasetWithConfig::new(ppp_name, ppp_dataframe, with_currency(dataset.dataset_config, "international dollar"))
+                .with_transformation(EuConversionMethod::PurchasingPowerParity.description()));
+        }
+
+        output_datasets.push(dataset);
+    }
+
+    output_datasets
+}
+
+/// Converts the value column and the columns built on it, e.g. the seasonal adjustment
+/// components, renaming `<value_column><suffix>` to `<converted_column><suffix>`. Multiplicative
+/// seasonal factors have no unit and are carried over as they are.
+fn converted_value_columns(dataframe: &DataFrame,
+                           dataset_config: &DatasetConfig,
+                           value_column: &str,
+                           converted_column: &str,
+                           convert: impl Fn(Expr) -> Expr) -> Vec<Expr> {
+    let mut columns = vec![col("quarter")];
+
+    for column_name in dataframe.get_column_names() {
+        let Some(suffix) = column_name.strip_prefix(value_column).filter(|suffix| suffix.is_empty() || suffix.starts_with('_')) else {
+            continue;
+        };
+        let converted_name = format!("{converted_column}{suffix}");
+
+        if is_seasonal_factor(column_name, dataset_config) {
+            columns.push(col(column_name.clone()).alias(converted_name));
+        } else {
+            columns.push(convert(col(column_name.clone())).alias(converted_name));
+        }
+    }
+
+    columns
+}
+
+fn with_currency(dataset_config: DatasetConfig, currency: &'static str) -> DatasetConfig {
+    let mut converted_config = dataset_config;
+    converted_config.unit_config = dataset_config.unit_config
+        .map(|unit_config| UnitConfig::new(currency, unit_config.scale, unit_config.monetary));
+
+    converted_config
+}
+
+/// Annual datasets (renamed to a `year` column, e.g. the World Bank series) are expanded to the
+/// quarters present in the quarterly datasets, so that they can be joined on `quarter`.
+pub fn expand_annual_datasets(datasets: Vec<DatasetWithConfig>) -> Vec<DatasetWithConfig> {
+    let quarters: Vec<String> = datasets.iter()
+        .filter_map(|d| d.dataframe.column("quarter").ok())
+        .flat_map(|c| c.str().unwrap().into_no_null_iter().map(|q| q.to_string()).collect::<Vec<_>>())
+        .collect::<HashSet<String>>()
+        .into_iter()
+        .collect();
+
+    datasets
+        .into_iter()
+        .map(|dataset| {
+            if dataset.dataframe.column("year").is_err() {
+                return dataset;
+            }
+
+            let expanded_dataframe = expand_annual_to_quarters(&dataset.dataframe, quarters.clone()).unwrap();
+            DatasetWithConfig::new(dataset.name, expanded_dataframe, dataset.dataset_config)
+        })
+        .collect()
+}
+
+/// Each quarter takes the value of its year. Annual figures are published with a lag, so quarters
+/// after the latest published year carry the latest value forward.
+fn expand_annual_to_quarters(annual_df: &DataFrame, quarters: Vec<String>) -> PolarsResult<DataFrame> {
+    let value_column_name = annual_df.get_column_names()
+        .into_iter()
+        .find(|name| name.as_str() != "year")
+        .cloned()
+        .ok_or_else(|| polars_err!(ColumnNotFound: "annual dataset has no value column"))?;
+
+    let years = annual_df.column("year")?.str()?;
+    let values = annual_df.column(&value_column_name)?.f64()?;
+
+    let values_by_year: BTreeMap<i32, f64> = years.into_iter()
+        .zip(values)
+        .filter_map(|(year, value)| Some((year?.parse::<i32>().ok()?, value?)))
+        .collect();
+
+    let mut quarters = quarters;
+    quarters.sort();
+
+    let quarter_values: Vec<Option<f64>> = quarters.iter()
+        .map(|quarter| {
+            let year = quarter.split('-').next()?.parse::<i32>().ok()?;
+            values_by_year.range(..=year).next_back().map(|(_, value)| *value)
+        })
+        .collect();
+
+    let quarter_series = Series::new(PlSmallStr::from_str("quarter"), quarters);
+    let value_series = Series::new(value_column_name, quarter_values);
+
+    DataFrame::new(vec![quarter_series.into(), value_series.into()])
+}
+
 #[cfg(test)]
 mod tests {
     use std::collections::HashMap;
 
     use polars::prelude::*;
 
-    use crate::datasets_config::datasets_config::DatasetConfig;
+    use crate::datasets_config::datasets_config::{DatasetConfig, Scale, SeasonalAdjustmentConfig, SeasonalAdjustmentMode};
     use crate::model::data_model::{AllDatasets, DatasetWithConfig};
 
     use super::*;
@@ -265,9 +415,11 @@ mod tests {
                 "test_id",
                 true,
                 Some(QuarterlyAverageConfig::new("date", "value", "%Y-%m", "avg_value")),
+                None,
+                None,
             )
         } else {
-            DatasetConfig::new(name, "test_source", "test_id", false, None)
+            DatasetConfig::new(name, "test_source", "test_id", false, None, None, None)
         };
 
         DatasetWithConfig::new(name, mock_dataframe(None).unwrap(), config)
@@ -287,11 +439,15 @@ mod tests {
             dataset2.clone(), // ecb_government_debt_df
             dataset2.clone(), // ecb_gdp_df
             dataset2.clone(), // ecb_inflation_df
+            dataset2.clone(), // world_bank_eu_ppp_df
+            dataset2.clone(), // fred_us_gdp_deflator_df
+            dataset2.clone(), // fred_us_population_df
+            dataset2.clone(), // world_bank_eu_population_df
         );
 
         let normalized = normalize_data(all);
 
-        assert_eq!(normalized.len(), 8);
+        assert_eq!(normalized.len(), 12);
 
         let first = &normalized[0];
         assert_eq!(first.name, "quarterly_dataset");
@@ -304,7 +460,7 @@ mod tests {
     #[test]
     fn test_rename_columns() {
         fn check_column_rename(dataset_name: &'static str, expected_columns: Vec<&str>) {
-            let df_config = DatasetConfig::new(dataset_name, "test_source", "test_id", false, None);
+            let df_config = DatasetConfig::new(dataset_name, "test_source", "test_id", false, None, None, None);
             let df = mock_dataframe(Some(vec!["quarter", "value"])).unwrap();
             let df_with_config = DatasetWithConfig::new(dataset_name, df, df_config);
             let renamed_df = rename_columns(vec![df_with_config]);
@@ -316,6 +472,8 @@ mod tests {
         check_column_rename("eu_government_debt", vec!["quarter", "eu_government_debt"]);
         check_column_rename("eu_gdp", vec!["quarter", "eu_gdp"]);
         check_column_rename("eu_inflation", vec!["quarter", "eu_inflation"]);
+        check_column_rename("eu_ppp", vec!["year", "eur_per_intl_usd"]);
+        check_column_rename("eu_population", vec!["year", "eu_population"]);
         check_column_rename("unchanged", vec!["quarter", "value"]);
     }
 
@@ -345,6 +503,8 @@ mod tests {
                                               "dummy_source",
                                               "dummy_identifier",
                                               false,
+                                              None,
+                                              None,
                                               None);
 
         let datasets = vec![
@@ -378,5 +538,260 @@ mod tests {
         assert!(result_map.contains_key("us_gdp"));
         assert_eq!(**result_map.get("us_gdp").unwrap(), us_df);
     }
-}
 
+    fn create_ppp_df(quarters: Vec<&str>, factors: Vec<Option<f64>>) -> DataFrame {
+        df![
+            "quarter" => quarters,
+            "eur_per_intl_usd" => factors
+        ].unwrap()
+    }
+
+    #[test]
+    fn test_convert_eu_to_ppp() {
+        let quarters = vec!["2022-Q4", "2023-Q1", "2024-Q2"];
+
+        let gdp_df = create_df("eu_gdp", quarters.clone(), vec![80.0, 140.0, 160.0]);
+        let debt_df = create_df("eu_government_debt", quarters.clone(), vec![40.0, 70.0, 80.0]);
+        let ppp_df = create_ppp_df(quarters.clone(), vec![Some(0.8), Some(0.7), Some(0.7)]);
+
+        let dummy_config = DatasetConfig::new("dummy_name",
+                                              "dummy_source",
+                                              "dummy_identifier",
+                                              false,
+                                              None,
+                                              None,
+                                              None);
+
+        let datasets = vec![
+            DatasetWithConfig::new("eu_gdp", gdp_df.clone(), dummy_config),
+            DatasetWithConfig::new("eu_government_debt", debt_df, dummy_config),
+            DatasetWithConfig::new("eu_ppp", ppp_df.clone(), dummy_config),
+        ];
+
+        let result = convert_eu_to_ppp(datasets);
+
+        let result_map: HashMap<&str, &DataFrame> = result.iter()
+            .map(|d| (d.name, &d.dataframe))
+            .collect();
+
+        assert_eq!(result.len(), 5);
+
+        // Original EUR values are kept for the market rate conversion
+        assert_eq!(**result_map.get("eu_gdp").unwrap(), gdp_df);
+        assert_eq!(**result_map.get("eu_ppp").unwrap(), ppp_df);
+
+        let gdp_ppp = result_map.get("eu_gdp_ppp").unwrap()
+            .sort(["quarter"], SortMultipleOptions::new()).unwrap();
+        let gdp_ppp_values = gdp_ppp.column("eu_gdp_ppp_converted").unwrap().f64().unwrap().into_no_null_iter().collect::<Vec<_>>();
+        let expected_gdp_ppp = [100.0, 200.0, 160.0 / 0.7];
+        for (a, e) in gdp_ppp_values.iter().zip(expected_gdp_ppp.iter()) {
+            assert!((a - e).abs() < 1e-6);
+        }
+
+        let debt_ppp = result_map.get("eu_government_debt_ppp").unwrap()
+            .sort(["quarter"], SortMultipleOptions::new()).unwrap();
+        let debt_ppp_values = debt_ppp.column("eu_government_debt_ppp_converted").unwrap().f64().unwrap().into_no_null_iter().collect::<Vec<_>>();
+        assert!((debt_ppp_values[1] - 100.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn test_expand_annual_datasets() {
+        let dummy_config = DatasetConfig::new("dummy_name",
+                                              "dummy_source",
+                                              "dummy_identifier",
+                                              false,
+                                              None,
+                                              None,
+                                              None);
+
+        let annual_df = df![
+            "year" => &["2024", "2023", "2022"],
+            "eu_population" => &[None, Some(350.0), Some(340.0)]
+        ].unwrap();
+
+        let datasets = vec![
+            DatasetWithConfig::new("eu_gdp", create_df("eu_gdp", vec!["2022-Q4", "2023-Q1", "2024-Q2"], vec![1.0, 2.0, 3.0]), dummy_config),
+            DatasetWithConfig::new("fx_rates", create_fx_df(vec!["2021-Q4", "2023-Q1"], vec![1.1, 1.2]), dummy_config),
+            DatasetWithConfig::new("eu_population", annual_df, dummy_config),
+        ];
+
+        let result = expand_annual_datasets(datasets.clone());
+
+        assert_eq!(result[0].dataframe, datasets[0].dataframe);
+
+        let expanded = &result[2].dataframe;
+        let quarters = expanded.column("quarter").unwrap().str().unwrap().into_no_null_iter().collect::<Vec<_>>();
+        let values = expanded.column("eu_population").unwrap().f64().unwrap().into_iter().collect::<Vec<_>>();
+
+        assert_eq!(quarters, vec!["2021-Q4", "2022-Q4", "2023-Q1", "2024-Q2"]);
+        // 2021 precedes the first published year, 2024 carries 2023 forward
+        assert_eq!(values, vec![None, Some(340.0), Some(350.0), Some(350.0)]);
+    }
+
+    #[test]
+    fn test_convert_eu_currency_market_rate_only_drops_ppp_dataset() {
+        let quarters = vec!["2023-Q1"];
+
+        let dummy_config = DatasetConfig::new("dummy_name",
+                                              "dummy_source",
+                                              "dummy_identifier",
+                                              false,
+                                              None,
+                                              None,
+                                              None);
+
+        let datasets = vec![
+            DatasetWithConfig::new("eu_gdp", create_df("eu_gdp", quarters.clone(), vec![100.0]), dummy_config),
+            DatasetWithConfig::new("eu_government_debt", create_df("eu_government_debt", quarters.clone(), vec![300.0]), dummy_config),
+            DatasetWithConfig::new("fx_rates", create_fx_df(quarters.clone(), vec![1.1]), dummy_config),
+            DatasetWithConfig::new("eu_ppp", create_ppp_df(quarters.clone(), vec![Some(0.7)]), dummy_config),
+        ];
+
+        let result = convert_eu_currency(datasets, &[EuConversionMethod::MarketRate]);
+
+        let names: Vec<&str> = result.iter().map(|d| d.name).collect();
+        assert!(!names.contains(&"eu_ppp"));
+        assert!(!names.contains(&"eu_gdp_ppp"));
+
+        let gdp = result.iter().find(|d| d.name == "eu_gdp").unwrap();
+        assert!(gdp.dataframe.column("eu_gdp_converted").is_ok());
+    }
+
+    #[test]
+    fn test_convert_eu_currency_both_methods() {
+        let quarters = vec!["2023-Q1"];
+
+        let dummy_config = DatasetConfig::new("dummy_name",
+                                              "dummy_source",
+                                              "dummy_identifier",
+                                              false,
+                                              None,
+                                              None,
+                                              None);
+
+        let datasets = vec![
+            DatasetWithConfig::new("eu_gdp", create_df("eu_gdp", quarters.clone(), vec![140.0]), dummy_config),
+            DatasetWithConfig::new("eu_government_debt", create_df("eu_government_debt", quarters.clone(), vec![300.0]), dummy_config),
+            DatasetWithConfig::new("fx_rates", create_fx_df(quarters.clone(), vec![1.1]), dummy_config),
+            DatasetWithConfig::new("eu_ppp", create_ppp_df(quarters.clone(), vec![Some(0.7)]), dummy_config),
+        ];
+
+        let result = convert_eu_currency(datasets, &[EuConversionMethod::MarketRate, EuConversionMethod::PurchasingPowerParity]);
+
+        let joined = join_all_datasets(result);
+
+        let market = joined.column("eu_gdp_converted").unwrap().f64().unwrap().get(0).unwrap();
+        let ppp = joined.column("eu_gdp_ppp_converted").unwrap().f64().unwrap().get(0).unwrap();
+
+        assert!((market - 154.0).abs() < 1e-6);
+        assert!((ppp - 200.0).abs() < 1e-6);
+        assert!(joined.column("eur_per_intl_usd").is_ok());
+    }
+
+    #[test]
+    fn test_convert_eu_currency_carries_seasonal_adjustment_components() {
+        let quarters = vec!["2023-Q1"];
+
+        let debt_config = DatasetConfig::new("eu_government_debt",
+                                             "dummy_source",
+                                             "dummy_identifier",
+                                             false,
+                                             None,
+                                             None,
+                                             Some(SeasonalAdjustmentConfig::new(SeasonalAdjustmentMode::Multiplicative)));
+        let dummy_config = DatasetConfig::new("dummy_name",
+                                              "dummy_source",
+                                              "dummy_identifier",
+                                              false,
+                                              None,
+                                              None,
+                                              None);
+
+        let debt_df = df![
+            "quarter" => &quarters,
+            "eu_government_debt" => &[280.0],
+            "eu_government_debt_sa" => &[350.0],
+            "eu_government_debt_trend" => &[140.0],
+            "eu_government_debt_seasonal" => &[0.8]
+        ].unwrap();
+
+        let datasets = vec![
+            DatasetWithConfig::new("eu_government_debt", debt_df, debt_config),
+            DatasetWithConfig::new("fx_rates", create_fx_df(quarters.clone(), vec![1.1]), dummy_config),
+            DatasetWithConfig::new("eu_ppp", create_ppp_df(quarters.clone(), vec![Some(0.7)]), dummy_config),
+        ];
+
+        let joined = join_all_datasets(convert_eu_currency(datasets, &[EuConversionMethod::MarketRate, EuConversionMethod::PurchasingPowerParity]));
+
+        let value = |column: &str| joined.column(column).unwrap().f64().unwrap().get(0).unwrap();
+
+        assert!((value("eu_government_debt_converted") - 308.0).abs() < 1e-6);
+        assert!((value("eu_government_debt_converted_sa") - 385.0).abs() < 1e-6);
+        assert!((value("eu_government_debt_converted_trend") - 154.0).abs() < 1e-6);
+        assert_eq!(value("eu_government_debt_converted_seasonal"), 0.8);
+        assert!((value("eu_government_debt_ppp_converted_sa") - 500.0).abs() < 1e-6);
+        assert!((value("eu_government_debt_ppp_converted_trend") - 200.0).abs() < 1e-6);
+        assert_eq!(value("eu_government_debt_ppp_converted_seasonal"), 0.8);
+    }
+
+    #[test]
+    fn test_convert_eu_to_ppp_without_ppp_dataset() {
+        let dummy_config = DatasetConfig::new("dummy_name", "dummy_source", "dummy_identifier", false, None, None, None);
+        let datasets = vec![
+            DatasetWithConfig::new("eu_gdp", create_df("eu_gdp", vec!["2023-Q1"], vec![140.0]), dummy_config),
+        ];
+
+        let result = convert_eu_to_ppp(datasets);
+
+        assert_eq!(result.iter().map(|d| d.name).collect::<Vec<_>>(), vec!["eu_gdp"]);
+    }
+
+    #[test]
+    fn test_convert_eu_currency_updates_units() {
+        let quarters = vec!["2023-Q1"];
+
+        let eur_config = DatasetConfig::new("eu_gdp",
+                                            "dummy_source",
+                                            "dummy_identifier",
+                                            false,
+                                            None,
+                                            Some(UnitConfig::new("EUR", Scale::Billions, true)),
+                                            None);
+
+        let datasets = vec![
+            DatasetWithConfig::new("eu_gdp", create_df("eu_gdp", quarters.clone(), vec![140.0]), eur_config),
+            DatasetWithConfig::new("eu_government_debt", create_df("eu_government_debt", quarters.clone(), vec![300.0]), eur_config),
+            DatasetWithConfig::new("fx_rates", create_fx_df(quarters.clone(), vec![1.1]), eur_config),
+            DatasetWithConfig::new("eu_ppp", create_ppp_df(quarters.clone(), vec![Some(0.7)]), eur_config),
+        ];
+
+        let result = convert_eu_currency(datasets, &[EuConversionMethod::MarketRate, EuConversionMethod::PurchasingPowerParity]);
+
+        let unit_of = |name: &str| result.iter().find(|d| d.name == name).unwrap().dataset_config.unit_config.unwrap();
+
+        assert_eq!(unit_of("eu_gdp").unit, "USD");
+        assert_eq!(unit_of("eu_gdp_ppp").unit, "international dollar");
+        assert_eq!(unit_of("eu_gdp_ppp").scale, Scale::Billions);
+    }
+
+    #[test]
+    fn test_convert_eu_currency_records_the_conversion_method() {
+        let dummy_config = DatasetConfig::new("dummy_name", "dummy_source", "dummy_identifier", false, None, None, None);
+        let quarters = vec!["2023-Q1"];
+        let datasets = vec![
+            DatasetWithConfig::new("eu_gdp", create_df("eu_gdp", quarters.clone(), vec![140.0]), dummy_config),
+            DatasetWithConfig::new("eu_government_debt", create_df("eu_government_debt", quarters.clone(), vec![300.0]), dummy_config),
+            DatasetWithConfig::new("eu_inflation", create_df("eu_inflation", quarters.clone(), vec![2.5]), dummy_config),
+            DatasetWithConfig::new("fx_rates", create_fx_df(quarters.clone(), vec![1.1]), dummy_config),
+            DatasetWithConfig::new("eu_ppp", create_ppp_df(quarters.clone(), vec![Some(0.7)]), dummy_config),
+        ];
+
+        let result = convert_eu_currency(datasets, &[EuConversionMethod::MarketRate, EuConversionMethod::PurchasingPowerParity]);
+        let transformations_of = |name: &str| result.iter().find(|d| d.name == name).unwrap().transformations.clone();
+
+        assert_eq!(transformations_of("eu_gdp"), vec!["converted from EUR at the quarterly average EUR/USD market rate"]);
+        assert_eq!(transformations_of("eu_gdp_ppp"), vec!["converted from EUR at purchasing power parity (EUR per international dollar)"]);
+        assert_eq!(transformations_of("eu_government_debt_ppp"), vec!["converted from EUR at purchasing power parity (EUR per international dollar)"]);
+        assert!(transformations_of("eu_inflation").is_empty());
+    }
+}
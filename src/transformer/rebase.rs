@@ -0,0 +1,111 @@
+use log::{info, warn};
+use polars::prelude::*;
+
+use crate::datasets_config::datasets_config::{RebaseBasePeriod, RebaseConfig, Scale};
+use crate::model::data_model::ColumnMetadata;
+
+/// Adds a `<column>_rebased` index for every configured column, equal to 100 in the base period.
+/// Columns that are missing or have no value in the base period are skipped.
+pub fn rebase_series(result: DataFrame,
+                     column_metadata: &mut Vec<ColumnMetadata>,
+                     rebase_config: &RebaseConfig) -> DataFrame {
+    let mut result = result;
+    let base_period_label = rebase_config.base_period.label();
+
+    for column_name in rebase_config.columns {
+        let base_value = match base_value(&result, column_name, &rebase_config.base_period) {
+            Some(base_value) if base_value != 0.0 => base_value,
+            _ => {
+                warn!("skipping rebasing of {column_name}, no value in base period {base_period_label}");
+                continue;
+            }
+        };
+
+        let rebased_column_name = format!("{column_name}_rebased");
+        result = result.lazy()
+            .with_column((col(*column_name) / lit(base_value) * lit(100.0)).alias(rebased_column_name.as_str()))
+            .collect()
+            .unwrap();
+
+        let dataset_name = column_metadata.iter()
+            .find(|metadata| metadata.column_name == *column_name)
+            .map(|metadata| metadata.dataset_name.clone())
+            .unwrap_or_else(|| "derived".to_string());
+
+        column_metadata.push(ColumnMetadata::new(&rebased_column_name,
+                                                 &dataset_name,
+                                                 &format!("index {base_period_label}=100"),
+                                                 Scale::Units));
+        info!("rebased {column_name} to {base_period_label}=100");
+    }
+
+    result
+}
+
+fn base_value(result: &DataFrame, column_name: &str, base_period: &RebaseBasePeriod) -> Option<f64> {
+    let quarters = result.column("quarter").ok()?.str().ok()?;
+    let values = result.column(column_name).ok()?.cast(&DataType::Float64).ok()?;
+    let values = values.f64().ok()?;
+
+    let base_values: Vec<f64> = quarters.into_iter()
+        .zip(values)
+        .filter_map(|(quarter, value)| {
+            let quarter = quarter?;
+            let in_base_period = match base_period {
+                RebaseBasePeriod::YearAverage(year) => quarter.starts_with(&format!("{year}-")),
+            };
+
+            if in_base_period { value } else { None }
+        })
+        .collect();
+
+    if base_values.is_empty() {
+        return None;
+    }
+
+    Some(base_values.iter().sum::<f64>() / base_values.len() as f64)
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn joined_dataframe() -> DataFrame {
+        df![
+            "quarter" => &["2014-Q4", "2015-Q1", "2015-Q2", "2016-Q1"],
+            "sp500_usd" => &[1800.0, 2000.0, 3000.0, 2500.0],
+            "us_gdp_usd" => &[None, Some(0.0), Some(0.0), Some(1.0)]
+        ].unwrap()
+    }
+
+    fn rebased_values(df: &DataFrame, column_name: &str) -> Vec<f64> {
+        df.column(column_name).unwrap().f64().unwrap().into_no_null_iter().collect()
+    }
+
+    #[test]
+    fn test_rebase_series_to_year_average() {
+        let rebase_config = RebaseConfig::new(RebaseBasePeriod::YearAverage(2015), &["sp500_usd"]);
+        let mut metadata: Vec<ColumnMetadata> = vec![];
+
+        let result = rebase_series(joined_dataframe(), &mut metadata, &rebase_config);
+
+        let rebased = rebased_values(&result, "sp500_usd_rebased");
+        for (actual, expected) in rebased.iter().zip([72.0, 80.0, 120.0, 100.0]) {
+            assert!((actual - expected).abs() < 1e-9);
+        }
+        assert_eq!(metadata[0].unit, "index 2015=100");
+    }
+
+    #[test]
+    fn test_rebase_series_skips_missing_or_zero_base() {
+        let rebase_config = RebaseConfig::new(RebaseBasePeriod::YearAverage(2015), &["us_gdp_usd", "not_there"]);
+        let mut metadata: Vec<ColumnMetadata> = vec![];
+
+        let result = rebase_series(joined_dataframe(), &mut metadata, &rebase_config);
+
+        assert!(result.column("us_gdp_usd_rebased").is_err());
+        assert!(result.column("not_there_rebased").is_err());
+        assert!(metadata.is_empty());
+    }
+}
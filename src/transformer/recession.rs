@@ -0,0 +1,119 @@
+use log::{info, warn};
+use polars::prelude::*;
+
+use crate::datasets_config::datasets_config::{RecessionRuleConfig, Scale};
+use crate::model::data_model::{ColumnMetadata, RecessionPeriods};
+use crate::transformer::stats::column_values;
+
+const RECESSION_UNIT: &str = "recession indicator (1 = recession)";
+
+/// Adds a 0/1 flag column for every set of dated recession quarters and every technical recession
+/// rule. A rule flags all quarters of a run of at least `consecutive_quarters` negative growth rates.
+pub fn annotate_recessions(result: DataFrame,
+                           column_metadata: &mut Vec<ColumnMetadata>,
+                           recession_periods: &[RecessionPeriods],
+                           recession_rule_configs: &[RecessionRuleConfig]) -> DataFrame {
+    let mut result = result;
+    let quarters: Vec<Option<String>> = result.column("quarter").unwrap().str().unwrap()
+        .into_iter()
+        .map(|quarter| quarter.map(String::from))
+        .collect();
+
+    for periods in recession_periods {
+        let flags: Vec<Option<i32>> = quarters.iter()
+            .map(|quarter| quarter.as_ref().map(|quarter| periods.quarters.contains(quarter) as i32))
+            .collect();
+
+        result.with_column(Series::new(periods.column_name.as_str().into(), flags)).unwrap();
+        push_metadata(column_metadata, &periods.column_name, format!("dated recessions from {}", periods.source));
+        info!("flagged {} recession quarters in {}", periods.quarters.len(), periods.column_name);
+    }
+
+    for rule_config in recession_rule_configs {
+        let Some(growth) = column_values(&result, rule_config.growth_column) else {
+            warn!("skipping {}, column {} not found", rule_config.column, rule_config.growth_column);
+            continue;
+        };
+
+        let flags = technical_recession_flags(&growth, rule_config.consecutive_quarters);
+
+        result.with_column(Series::new(rule_config.column.into(), flags)).unwrap();
+        push_metadata(column_metadata,
+                      rule_config.column,
+                      format!("{} consecutive quarters of negative {}", rule_config.consecutive_quarters, rule_config.growth_column));
+        info!("flagged technical recessions in {}", rule_config.column);
+    }
+
+    result
+}
+
+fn push_metadata(column_metadata: &mut Vec<ColumnMetadata>, column_name: &str, transformation: String) {
+    let mut metadata = ColumnMetadata::new(column_name, "recession", RECESSION_UNIT, Scale::Units);
+    metadata.transformations.push(transformation);
+    column_metadata.push(metadata);
+}
+
+/// Flags every quarter belonging to a run of at least `consecutive_quarters` negative values.
+/// Quarters without a growth rate stay null and break a run.
+fn technical_recession_flags(growth: &[Option<f64>], consecutive_quarters: usize) -> Vec<Option<i32>> {
+    let mut flags: Vec<Option<i32>> = growth.iter().map(|value| value.map(|_| 0)).collect();
+    let mut run_start = 0;
+
+    for index in 0..=growth.len() {
+        let negative = growth.get(index).copied().flatten().is_some_and(|value| value < 0.0);
+        if negative {
+            continue;
+        }
+
+        if index - run_start >= consecutive_quarters.max(1) {
+            flags[run_start..index].fill(Some(1));
+        }
+        run_start = index + 1;
+    }
+
+    flags
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn flags(df: &DataFrame, column_name: &str) -> Vec<Option<i32>> {
+        df.column(column_name).unwrap().i32().unwrap().into_iter().collect()
+    }
+
+    #[test]
+    fn test_technical_recession_flags() {
+        let growth = [Some(0.5), Some(-0.1), Some(-0.3), Some(-0.2), Some(0.4), Some(-0.5), None, Some(-0.1), Some(-0.2)];
+
+        assert_eq!(technical_recession_flags(&growth, 2),
+                   vec![Some(0), Some(1), Some(1), Some(1), Some(0), Some(0), None, Some(1), Some(1)]);
+        assert_eq!(technical_recession_flags(&growth, 3),
+                   vec![Some(0), Some(1), Some(1), Some(1), Some(0), Some(0), None, Some(0), Some(0)]);
+    }
+
+    #[test]
+    fn test_annotate_recessions() {
+        let result = df![
+            "quarter" => &["2008-Q1", "2008-Q2", "2008-Q3", "2008-Q4"],
+            "eu_gdp_qoq_perc" => &[Some(0.4), Some(-0.3), Some(-0.6), None]
+        ].unwrap();
+        let recession_periods = [RecessionPeriods::new("eu_recession_cepr", "csv_data/recessions.csv (euro_area)",
+                                                       vec!["2008-Q2".to_string(), "2008-Q3".to_string()])];
+        let rule_configs = [RecessionRuleConfig::new("eu_technical_recession", "eu_gdp_qoq_perc", 2),
+                            RecessionRuleConfig::new("us_technical_recession", "not_there", 2)];
+        let mut metadata: Vec<ColumnMetadata> = vec![];
+
+        let result = annotate_recessions(result, &mut metadata, &recession_periods, &rule_configs);
+
+        assert_eq!(flags(&result, "eu_recession_cepr"), vec![Some(0), Some(1), Some(1), Some(0)]);
+        assert_eq!(flags(&result, "eu_technical_recession"), vec![Some(0), Some(1), Some(1), None]);
+        assert!(result.column("us_technical_recession").is_err());
+
+        assert_eq!(metadata.len(), 2);
+        assert_eq!(metadata[0].unit, RECESSION_UNIT);
+        assert_eq!(metadata[0].transformations, vec!["dated recessions from csv_data/recessions.csv (euro_area)"]);
+        assert_eq!(metadata[1].transformations, vec!["2 consecutive quarters of negative eu_gdp_qoq_perc"]);
+    }
+}
@@ -0,0 +1,485 @@
+use std::error::Error;
+
+use log::{info, warn};
+use polars::prelude::*;
+
+use crate::datasets_config::datasets_config::{GrangerCausalityConfig, RegressionConfig};
+use crate::model::data_model::{GrangerTest, RegressionCoefficient, RegressionResult};
+use crate::transformer::stats::column_values;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct OlsFit {
+    pub coefficients: Vec<f64>,
+    pub standard_errors: Vec<f64>,
+    pub residual_sum_of_squares: f64,
+    pub r_squared: f64,
+    pub adjusted_r_squared: f64,
+    pub observations: usize,
+    pub degrees_of_freedom: usize,
+}
+
+/// Runs the configured regressions and Granger causality tests on the joined dataset.
+/// Tests whose columns are missing or that have too few observations are skipped.
+pub fn run_configured_tests(result: &DataFrame,
+                            regression_configs: &[RegressionConfig],
+                            granger_configs: &[GrangerCausalityConfig]) -> (Vec<RegressionResult>, Vec<GrangerTest>) {
+    let mut regressions: Vec<RegressionResult> = vec![];
+    let mut granger_tests: Vec<GrangerTest> = vec![];
+
+    for regression_config in regression_configs {
+        let dependent = regression_config.dependent;
+
+        match run_regression(result, dependent, regression_config.regressors, regression_config.newey_west_lags) {
+            Ok(regression) => {
+                info!("regressed {dependent} on {:?}: R² {:.2} over {} quarters",
+                      regression_config.regressors, regression.r_squared, regression.observations);
+                regressions.push(regression);
+            },
+            Err(error) => warn!("skipping regression of {dependent}: {error}"),
+        }
+    }
+
+    for granger_config in granger_configs {
+        let (cause, effect) = (granger_config.cause, granger_config.effect);
+
+        for lags in granger_config.lag_orders {
+            match run_granger_test(result, cause, effect, *lags) {
+                Ok(granger_test) => {
+                    info!("Granger causality of {cause} on {effect} with {lags} lags: F {:.2}, p-value {:.3}",
+                          granger_test.f_statistic, granger_test.p_value);
+                    granger_tests.push(granger_test);
+                },
+                Err(error) => warn!("skipping Granger causality test of {cause} on {effect} with {lags} lags: {error}"),
+            }
+        }
+    }
+
+    (regressions, granger_tests)
+}
+
+/// Regresses `dependent` on `regressors` plus an intercept over the quarters where all of them
+/// have a value.
+pub fn run_regression(result: &DataFrame,
+                      dependent: &str,
+                      regressors: &[&str],
+                      newey_west_lags: Option<usize>) -> Result<RegressionResult, Box<dyn Error>> {
+    let result = result.sort(["quarter"], SortMultipleOptions::new())?;
+    let dependent_values = required_column(&result, dependent)?;
+    let regressor_values = regressors.iter()
+        .map(|regressor| required_column(&result, regressor))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let (y, x): (Vec<f64>, Vec<Vec<f64>>) = (0..dependent_values.len())
+        .filter_map(|row| {
+            let regressor_row = regressor_values.iter().map(|values| values[row]).collect::<Option<Vec<f64>>>()?;
+            Some((dependent_values[row]?, regressor_row))
+        })
+        .unzip();
+
+    let fit = ols(&y, &x, newey_west_lags).ok_or("too few observations or collinear regressors")?;
+
+    let terms = std::iter::once("intercept").chain(regressors.iter().copied());
+    let coefficients = terms
+        .zip(fit.coefficients.iter().zip(&fit.standard_errors))
+        .map(|(term, (estimate, standard_error))| {
+            let t_statistic = estimate / standard_error;
+            RegressionCoefficient::new(term, *estimate, *standard_error, t_statistic, t_test_p_value(t_statistic, fit.degrees_of_freedom))
+        })
+        .collect();
+
+    let covariance = match newey_west_lags {
+        Some(lags) => format!("newey_west({lags})"),
+        None => "ols".to_string(),
+    };
+
+    Ok(RegressionResult::new(dependent, &covariance, fit.observations, fit.r_squared, fit.adjusted_r_squared, coefficients))
+}
+
+/// Compares a regression of `effect` on its own `lags` lags with one that adds `lags` lags of
+/// `cause`, using an F-test on the added coefficients.
+pub fn run_granger_test(result: &DataFrame, cause: &str, effect: &str, lags: usize) -> Result<GrangerTest, Box<dyn Error>> {
+    if lags == 0 {
+        return Err("the lag order must be positive".into());
+    }
+
+    let result = result.sort(["quarter"], SortMultipleOptions::new())?;
+    let cause_values = required_column(&result, cause)?;
+    let effect_values = required_column(&result, effect)?;
+
+    let mut y: Vec<f64> = vec![];
+    let mut restricted: Vec<Vec<f64>> = vec![];
+    let mut unrestricted: Vec<Vec<f64>> = vec![];
+
+    for row in lags..effect_values.len() {
+        let own_lags = (1..=lags).map(|lag| effect_values[row - lag]).collect::<Option<Vec<f64>>>();
+        let cause_lags = (1..=lags).map(|lag| cause_values[row - lag]).collect::<Option<Vec<f64>>>();
+
+        if let (Some(value), Some(own_lags), Some(cause_lags)) = (effect_values[row], own_lags, cause_lags) {
+            y.push(value);
+            unrestricted.push(own_lags.iter().chain(&cause_lags).copied().collect());
+            restricted.push(own_lags);
+        }
+    }
+
+    let too_few = "too few observations or collinear regressors";
+    let restricted_fit = ols(&y, &restricted, None).ok_or(too_few)?;
+    let unrestricted_fit = ols(&y, &unrestricted, None).ok_or(too_few)?;
+
+    let denominator_degrees = unrestricted_fit.degrees_of_freedom;
+    let f_statistic = ((restricted_fit.residual_sum_of_squares - unrestricted_fit.residual_sum_of_squares) / lags as f64)
+        / (unrestricted_fit.residual_sum_of_squares / denominator_degrees as f64);
+
+    Ok(GrangerTest::new(cause, effect, lags, y.len(), f_statistic, f_test_p_value(f_statistic, lags, denominator_degrees)))
+}
+
+fn required_column(result: &DataFrame, column_name: &str) -> Result<Vec<Option<f64>>, Box<dyn Error>> {
+    column_values(result, column_name).ok_or_else(|| format!("unknown column '{column_name}'").into())
+}
+
+/// Ordinary least squares of `y` on the rows of `x` and an intercept, which comes first in the
+/// coefficients. Newey-West standard errors use Bartlett weights without small-sample correction.
+/// Returns `None` without more observations than coefficients or when the regressors are collinear.
+pub fn ols(y: &[f64], x: &[Vec<f64>], newey_west_lags: Option<usize>) -> Option<OlsFit> {
+    let design: Vec<Vec<f64>> = x.iter()
+        .map(|row| std::iter::once(1.0).chain(row.iter().copied()).collect())
+        .collect();
+    let observations = y.len();
+    let parameters = design.first().map(|row| row.len()).unwrap_or(1);
+
+    if observations <= parameters {
+        return None;
+    }
+
+    let inverse = invert(&cross_product(&design, &design))?;
+    let x_transpose_y: Vec<f64> = (0..parameters)
+        .map(|column| design.iter().zip(y).map(|(row, value)| row[column] * value).sum())
+        .collect();
+    let coefficients = multiply(&inverse, &x_transpose_y);
+
+    let residuals: Vec<f64> = design.iter()
+        .zip(y)
+        .map(|(row, value)| value - row.iter().zip(&coefficients).map(|(x, beta)| x * beta).sum::<f64>())
+        .collect();
+    let residual_sum_of_squares: f64 = residuals.iter().map(|residual| residual * residual).sum();
+
+    let mean = y.iter().sum::<f64>() / observations as f64;
+    let total_sum_of_squares: f64 = y.iter().map(|value| (value - mean).powi(2)).sum();
+    let degrees_of_freedom = observations - parameters;
+
+    let r_squared = 1.0 - residual_sum_of_squares / total_sum_of_squares;
+    let adjusted_r_squared = 1.0 - (1.0 - r_squared) * (observations - 1) as f64 / degrees_of_freedom as f64;
+
+    let covariance = match newey_west_lags {
+        Some(lags) => {
+            let meat = newey_west_meat(&design, &residuals, lags);
+            let bread = matrix_product(&inverse, &meat);
+            matrix_product(&bread, &inverse)
+        },
+        None => {
+            let variance = residual_sum_of_squares / degrees_of_freedom as f64;
+            inverse.iter().map(|row| row.iter().map(|value| value * variance).collect()).collect()
+        },
+    };
+
+    Some(OlsFit {
+        standard_errors: (0..parameters).map(|index| covariance[index][index].sqrt()).collect(),
+        coefficients,
+        residual_sum_of_squares,
+        r_squared,
+        adjusted_r_squared,
+        observations,
+        degrees_of_freedom,
+    })
+}
+
+fn newey_west_meat(design: &[Vec<f64>], residuals: &[f64], lags: usize) -> Vec<Vec<f64>> {
+    let parameters = design[0].len();
+    let mut meat = vec![vec![0.0; parameters]; parameters];
+
+    for lag in 0..=lags.min(residuals.len() - 1) {
+        let weight = if lag == 0 { 1.0 } else { 1.0 - lag as f64 / (lags + 1) as f64 };
+
+        for row in lag..residuals.len() {
+            let scaled = weight * residuals[row] * residuals[row - lag];
+            for i in 0..parameters {
+                for j in 0..parameters {
+                    let mut contribution = design[row][i] * design[row - lag][j];
+                    if lag > 0 {
+                        contribution += design[row - lag][i] * design[row][j];
+                    }
+                    meat[i][j] += scaled * contribution;
+                }
+            }
+        }
+    }
+
+    meat
+}
+
+/// `aᵀb` for two matrices given as rows of observations.
+fn cross_product(a: &[Vec<f64>], b: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    let (columns_a, columns_b) = (a[0].len(), b[0].len());
+
+    (0..columns_a)
+        .map(|i| (0..columns_b).map(|j| a.iter().zip(b).map(|(row_a, row_b)| row_a[i] * row_b[j]).sum()).collect())
+        .collect()
+}
+
+fn matrix_product(a: &[Vec<f64>], b: &[Vec<f64>]) -> Vec<Vec<f64>> {
+    a.iter()
+        .map(|row| (0..b[0].len()).map(|j| row.iter().zip(b).map(|(value, b_row)| value * b_row[j]).sum()).collect())
+        .collect()
+}
+
+fn multiply(matrix: &[Vec<f64>], vector: &[f64]) -> Vec<f64> {
+    matrix.iter().map(|row| row.iter().zip(vector).map(|(a, b)| a * b).sum()).collect()
+}
+
+/// Gauss-Jordan elimination with partial pivoting, `None` for a singular matrix.
+fn invert(matrix: &[Vec<f64>]) -> Option<Vec<Vec<f64>>> {
+    let size = matrix.len();
+    let scale = matrix.iter().flatten().fold(0.0_f64, |max, value| max.max(value.abs()));
+    let mut augmented: Vec<Vec<f64>> = matrix.iter()
+        .enumerate()
+        .map(|(i, row)| row.iter().copied().chain((0..size).map(|j| if i == j { 1.0 } else { 0.0 })).collect())
+        .collect();
+
+    for column in 0..size {
+        let pivot = (column..size).max_by(|a, b| augmented[*a][column].abs().total_cmp(&augmented[*b][column].abs()))?;
+        if augmented[pivot][column].abs() <= scale * 1e-12 {
+            return None;
+        }
+        augmented.swap(column, pivot);
+
+        let pivot_value = augmented[column][column];
+        for value in augmented[column].iter_mut() {
+            *value /= pivot_value;
+        }
+
+        for row in 0..size {
+            if row != column {
+                let factor = augmented[row][column];
+                let pivot_row = augmented[column].clone();
+                for (value, pivot_row_value) in augmented[row].iter_mut().zip(pivot_row) {
+                    *value -= factor * pivot_row_value;
+                }
+            }
+        }
+    }
+
+    Some(augmented.into_iter().map(|row| row[size..].to_vec()).collect())
+}
+
+/// Two-sided p-value of a t statistic.
+pub fn t_test_p_value(t_statistic: f64, degrees_of_freedom: usize) -> f64 {
+    let degrees_of_freedom = degrees_of_freedom as f64;
+    regularized_incomplete_beta(degrees_of_freedom / (degrees_of_freedom + t_statistic * t_statistic), degrees_of_freedom / 2.0, 0.5)
+}
+
+/// Upper-tail p-value of an F statistic.
+pub fn f_test_p_value(f_statistic: f64, numerator_degrees: usize, denominator_degrees: usize) -> f64 {
+    if f_statistic <= 0.0 {
+        return 1.0;
+    }
+
+    let (d1, d2) = (numerator_degrees as f64, denominator_degrees as f64);
+    regularized_incomplete_beta(d2 / (d2 + d1 * f_statistic), d2 / 2.0, d1 / 2.0)
+}
+
+fn regularized_incomplete_beta(x: f64, a: f64, b: f64) -> f64 {
+    if x <= 0.0 {
+        return 0.0;
+    }
+    if x >= 1.0 {
+        return 1.0;
+    }
+
+    let log_front = ln_gamma(a + b) - ln_gamma(a) - ln_gamma(b) + a * x.ln() + b * (1.0 - x).ln();
+
+    if x < (a + 1.0) / (a + b + 2.0) {
+        log_front.exp() * beta_continued_fraction(x, a, b) / a
+    } else {
+        1.0 - log_front.exp() * beta_continued_fraction(1.0 - x, b, a) / b
+    }
+}
+
+/// Lentz's method for the continued fraction of the incomplete beta function.
+fn beta_continued_fraction(x: f64, a: f64, b: f64) -> f64 {
+    const TINY: f64 = 1e-300;
+    let clamp = |value: f64| if value.abs() < TINY { TINY } else { value };
+
+    let mut c = 1.0;
+    let mut d = 1.0 / clamp(1.0 - (a + b) * x / (a + 1.0));
+    let mut fraction = d;
+
+    for m in 1..=300 {
+        let m = m as f64;
+
+        let even = m * (b - m) * x / ((a + 2.0 * m - 1.0) * (a + 2.0 * m));
+        d = 1.0 / clamp(1.0 + even * d);
+        c = clamp(1.0 + even / c);
+        fraction *= d * c;
+
+        let odd = -(a + m) * (a + b + m) * x / ((a + 2.0 * m) * (a + 2.0 * m + 1.0));
+        d = 1.0 / clamp(1.0 + odd * d);
+        c = clamp(1.0 + odd / c);
+        let delta = d * c;
+        fraction *= delta;
+
+        if (delta - 1.0).abs() < 1e-15 {
+            break;
+        }
+    }
+
+    fraction
+}
+
+/// Lanczos approximation of the logarithm of the gamma function.
+fn ln_gamma(x: f64) -> f64 {
+    const COEFFICIENTS: [f64; 9] = [
+        0.999_999_999_999_809_9,
+        676.520_368_121_885_1,
+        -1_259.139_216_722_402_8,
+        771.323_428_777_653_1,
+        -176.615_029_162_140_6,
+        12.507_343_278_686_905,
+        -0.138_571_095_265_720_12,
+        9.984_369_578_019_572e-6,
+        1.505_632_735_149_311_6e-7,
+    ];
+
+    if x < 0.5 {
+        return (std::f64::consts::PI / (std::f64::consts::PI * x).sin()).ln() - ln_gamma(1.0 - x);
+    }
+
+    let x = x - 1.0;
+    let t = x + 7.5;
+    let series = COEFFICIENTS[1..].iter()
+        .enumerate()
+        .fold(COEFFICIENTS[0], |sum, (index, coefficient)| sum + coefficient / (x + index as f64 + 1.0));
+
+    0.5 * (2.0 * std::f64::consts::PI).ln() + (x + 0.5) * t.ln() - t + series.ln()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::tests::test_helpers::test_helpers::{assert_close, noise};
+
+    use super::*;
+
+    fn single_regressor(x: &[f64]) -> Vec<Vec<f64>> {
+        x.iter().map(|value| vec![*value]).collect()
+    }
+
+    #[test]
+    fn test_ols_estimates_and_standard_errors() {
+        let fit = ols(&[2.0, 4.0, 5.0, 4.0, 5.0], &single_regressor(&[1.0, 2.0, 3.0, 4.0, 5.0]), None).unwrap();
+
+        assert_close(fit.coefficients[0], 2.2, 1e-9);
+        assert_close(fit.coefficients[1], 0.6, 1e-9);
+        assert_close(fit.r_squared, 0.6, 1e-9);
+        assert_close(fit.adjusted_r_squared, 0.4666666666, 1e-9);
+        assert_close(fit.standard_errors[1], 0.08_f64.sqrt(), 1e-9);
+        assert_eq!(fit.degrees_of_freedom, 3);
+
+        assert_close(t_test_p_value(fit.coefficients[1] / fit.standard_errors[1], 3), 0.124034, 1e-5);
+    }
+
+    #[test]
+    fn test_ols_newey_west_standard_errors() {
+        let y = [2.0, 4.0, 5.0, 4.0, 5.0];
+        let x = single_regressor(&[1.0, 2.0, 3.0, 4.0, 5.0]);
+
+        let white = ols(&y, &x, Some(0)).unwrap();
+        assert_close(white.standard_errors[0], 0.7413501197, 1e-9);
+        assert_close(white.standard_errors[1], 0.1854723699, 1e-9);
+
+        let newey_west = ols(&y, &x, Some(1)).unwrap();
+        assert_close(newey_west.standard_errors[0], 0.6590902821, 1e-9);
+        assert_close(newey_west.standard_errors[1], 0.1649242250, 1e-9);
+        assert_eq!(newey_west.coefficients, white.coefficients);
+    }
+
+    #[test]
+    fn test_ols_rejects_collinear_or_short_samples() {
+        let collinear: Vec<Vec<f64>> = (0..6).map(|value| vec![value as f64, 2.0 * value as f64]).collect();
+        assert_eq!(ols(&[1.0, 3.0, 2.0, 5.0, 4.0, 6.0], &collinear, None), None);
+        assert_eq!(ols(&[1.0, 2.0], &single_regressor(&[1.0, 2.0]), None), None);
+    }
+
+    #[test]
+    fn test_p_values() {
+        assert_close(regularized_incomplete_beta(0.5, 2.0, 2.0), 0.5, 1e-12);
+        assert_close(t_test_p_value(0.0, 10), 1.0, 1e-12);
+        // F with one numerator degree of freedom is the square of t
+        assert_close(f_test_p_value(2.5_f64.powi(2), 1, 12), t_test_p_value(2.5, 12), 1e-12);
+        assert_close(f_test_p_value(3.0, 2, 20), 1.3_f64.powi(-10), 1e-9);
+    }
+
+    #[test]
+    fn test_run_regression_on_dataframe() {
+        let df = df![
+            "quarter" => &["2023-Q3", "2023-Q1", "2023-Q2", "2023-Q4", "2024-Q1", "2024-Q2"],
+            "returns" => &[Some(5.0), Some(2.0), Some(4.0), Some(4.0), Some(5.0), None],
+            "growth" => &[3.0, 1.0, 2.0, 4.0, 5.0, 6.0]
+        ].unwrap();
+
+        let regression = run_regression(&df, "returns", &["growth"], Some(2)).unwrap();
+
+        assert_eq!(regression.dependent, "returns");
+        assert_eq!(regression.covariance, "newey_west(2)");
+        assert_eq!(regression.observations, 5);
+        assert_eq!(regression.coefficients.iter().map(|coefficient| coefficient.term.as_str()).collect::<Vec<_>>(),
+                   vec!["intercept", "growth"]);
+        assert_close(regression.coefficients[1].estimate, 0.6, 1e-9);
+
+        let error = run_regression(&df, "returns", &["not_there"], None).unwrap_err();
+        assert_eq!(error.to_string(), "unknown column 'not_there'");
+    }
+
+    #[test]
+    fn test_run_granger_test() {
+        let count = 80;
+        let cause = noise(count, 1);
+        let unrelated = noise(count, 2);
+        let effect_noise = noise(count, 3);
+        let effect: Vec<f64> = (0..count)
+            .map(|index| if index == 0 { 0.0 } else { 2.0 * cause[index - 1] + 0.1 * effect_noise[index] })
+            .collect();
+
+        let df = df![
+            "quarter" => (0..count).map(|index| format!("{}-Q{}", 2000 + index / 4, index % 4 + 1)).collect::<Vec<_>>(),
+            "cause" => cause,
+            "unrelated" => unrelated,
+            "effect" => effect
+        ].unwrap();
+
+        let causal = run_granger_test(&df, "cause", "effect", 2).unwrap();
+        assert_eq!(causal.lags, 2);
+        assert_eq!(causal.observations, count - 2);
+        assert!(causal.p_value < 1e-6, "p-value {}", causal.p_value);
+
+        let non_causal = run_granger_test(&df, "unrelated", "effect", 2).unwrap();
+        assert!(non_causal.p_value > 0.01, "p-value {}", non_causal.p_value);
+
+        assert!(run_granger_test(&df, "cause", "effect", 0).is_err());
+    }
+
+    #[test]
+    fn test_run_configured_tests_skips_missing_columns() {
+        let df = df![
+            "quarter" => &["2023-Q1", "2023-Q2", "2023-Q3", "2023-Q4", "2024-Q1"],
+            "returns" => &[2.0, 4.0, 5.0, 4.0, 5.0],
+            "growth" => &[1.0, 2.0, 3.0, 4.0, 5.0]
+        ].unwrap();
+
+        let (regressions, granger_tests) = run_configured_tests(
+            &df,
+            &[RegressionConfig::new("returns", &["growth"], None), RegressionConfig::new("returns", &["not_there"], None)],
+            &[GrangerCausalityConfig::new("not_there", "returns", &[1])]);
+
+        assert_eq!(regressions.len(), 1);
+        assert!(granger_tests.is_empty());
+    }
+}
@@ -0,0 +1,360 @@
+use log::{info, warn};
+use polars::prelude::*;
+
+use crate::datasets_config::datasets_config::{DatasetConfig, Scale, SeasonalAdjustmentMode};
+use crate::model::data_model::{ColumnMetadata, DatasetWithConfig};
+
+const PERIOD: usize = 4;
+const MIN_OBSERVATIONS: usize = 3 * PERIOD;
+const CENTERED_MOVING_AVERAGE: [f64; 5] = [0.125, 0.25, 0.25, 0.25, 0.125];
+const SEASONAL_MOVING_AVERAGE: [f64; 5] = [1.0 / 9.0, 2.0 / 9.0, 3.0 / 9.0, 2.0 / 9.0, 1.0 / 9.0];
+const HENDERSON_MOVING_AVERAGE: [f64; 5] = [-0.073, 0.294, 0.558, 0.294, -0.073];
+
+const ADJUSTED_SUFFIX: &str = "_sa";
+const TREND_SUFFIX: &str = "_trend";
+const SEASONAL_SUFFIX: &str = "_seasonal";
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct SeasonalComponents {
+    pub adjusted: Vec<f64>,
+    pub trend: Vec<f64>,
+    pub seasonal: Vec<f64>,
+}
+
+/// Seasonally adjusts the value columns of every dataset that enables it in its config, in the
+/// dataset's own currency so that exchange rate moves do not leak into the seasonal factors. The
+/// original column is kept and `<column>_sa`, `<column>_trend` and `<column>_seasonal` are added,
+/// which the currency conversions carry along. Columns with gaps or fewer than three years of
+/// quarters are left untouched.
+pub fn seasonally_adjust(datasets: Vec<DatasetWithConfig>) -> Vec<DatasetWithConfig> {
+    datasets.into_iter()
+        .map(|dataset| match dataset.dataset_config.seasonal_adjustment_config {
+            Some(seasonal_adjustment_config) => DatasetWithConfig {
+                dataframe: adjust_dataframe(dataset.dataframe, seasonal_adjustment_config.mode),
+                ..dataset
+            },
+            None => dataset,
+        })
+        .collect()
+}
+
+fn adjust_dataframe(dataframe: DataFrame, mode: SeasonalAdjustmentMode) -> DataFrame {
+    let mut result = dataframe.sort(["quarter"], SortMultipleOptions::new()).unwrap();
+
+    let column_names: Vec<String> = result.get_column_names()
+        .into_iter()
+        .filter(|name| name.as_str() != "quarter")
+        .map(|name| name.to_string())
+        .collect();
+
+    for column_name in column_names {
+        let values = match column_values(&result, &column_name) {
+            Some(values) => values,
+            None => {
+                warn!("skipping seasonal adjustment of {column_name}, column has gaps");
+                continue;
+            }
+        };
+
+        let components = match decompose(&values, mode) {
+            Some(components) => components,
+            None => {
+                warn!("skipping seasonal adjustment of {column_name}, needs at least {MIN_OBSERVATIONS} quarters of {} data",
+                      if mode == SeasonalAdjustmentMode::Multiplicative { "positive" } else { "complete" });
+                continue;
+            }
+        };
+
+        result.with_column(Series::new(format!("{column_name}{ADJUSTED_SUFFIX}").into(), components.adjusted)).unwrap();
+        result.with_column(Series::new(format!("{column_name}{TREND_SUFFIX}").into(), components.trend)).unwrap();
+        result.with_column(Series::new(format!("{column_name}{SEASONAL_SUFFIX}").into(), components.seasonal)).unwrap();
+
+        info!("seasonally adjusted {column_name} ({} decomposition)", mode.name());
+    }
+
+    result
+}
+
+/// Whether the column holds the factors of a multiplicative adjustment, which have no unit and
+/// are therefore neither converted to another currency nor rescaled.
+pub fn is_seasonal_factor(column_name: &str, dataset_config: &DatasetConfig) -> bool {
+    column_name.ends_with(SEASONAL_SUFFIX) && dataset_config.seasonal_adjustment_config
+        .is_some_and(|seasonal_adjustment_config| seasonal_adjustment_config.mode == SeasonalAdjustmentMode::Multiplicative)
+}
+
+/// Records the adjustment in the metadata of the component columns added by `seasonally_adjust`
+/// (after any currency conversion, e.g. `eu_government_debt_converted_sa`). Multiplicative
+/// seasonal factors are given their own unit.
+pub fn record_seasonal_adjustments(column_metadata: &mut [ColumnMetadata], dataset_configs: &[DatasetConfig]) {
+    for metadata in column_metadata.iter_mut() {
+        let Some(mode) = dataset_configs.iter()
+            .find(|dataset_config| dataset_config.name == metadata.dataset_name)
+            .and_then(|dataset_config| dataset_config.seasonal_adjustment_config)
+            .map(|seasonal_adjustment_config| seasonal_adjustment_config.mode) else {
+            continue;
+        };
+
+        if metadata.column_name.ends_with(ADJUSTED_SUFFIX) {
+            metadata.transformations.push(format!("seasonally adjusted ({} X-11 moving averages)", mode.name()));
+        } else if metadata.column_name.ends_with(TREND_SUFFIX) {
+            metadata.transformations.push("trend-cycle of seasonal adjustment".to_string());
+        } else if metadata.column_name.ends_with(SEASONAL_SUFFIX) {
+            metadata.transformations.push("seasonal component of seasonal adjustment".to_string());
+            if mode == SeasonalAdjustmentMode::Multiplicative {
+                metadata.unit = "seasonal factor".to_string();
+                metadata.scale = Scale::Units;
+            }
+        }
+    }
+}
+
+fn column_values(result: &DataFrame, column_name: &str) -> Option<Vec<f64>> {
+    let values = result.column(column_name).ok()?.cast(&DataType::Float64).ok()?;
+
+    values.f64().ok()?.into_iter().collect()
+}
+
+/// X-11-style decomposition of a quarterly series. A centered 2x4 moving average gives a first
+/// trend, 3x3 moving averages of each quarter's deviations give the seasonal component, and a
+/// Henderson moving average of the adjusted series refines the trend for a second pass.
+/// Multiplicative decomposition works on logarithms and returns seasonal factors around 1.
+pub fn decompose(values: &[f64], mode: SeasonalAdjustmentMode) -> Option<SeasonalComponents> {
+    if values.len() < MIN_OBSERVATIONS {
+        return None;
+    }
+
+    match mode {
+        SeasonalAdjustmentMode::Additive => Some(decompose_additive(values)),
+        SeasonalAdjustmentMode::Multiplicative => {
+            if values.iter().any(|value| *value <= 0.0) {
+                return None;
+            }
+
+            let logarithms: Vec<f64> = values.iter().map(|value| value.ln()).collect();
+            let components = decompose_additive(&logarithms);
+            let exponential = |values: Vec<f64>| values.into_iter().map(f64::exp).collect();
+
+            Some(SeasonalComponents {
+                adjusted: exponential(components.adjusted),
+                trend: exponential(components.trend),
+                seasonal: exponential(components.seasonal),
+            })
+        },
+    }
+}
+
+fn decompose_additive(series: &[f64]) -> SeasonalComponents {
+    let first_trend = centered_moving_average(series);
+    let first_seasonal = seasonal_component(series, &first_trend);
+    let first_adjusted = subtract(series, &first_seasonal);
+
+    let trend = symmetric_moving_average(&first_adjusted, &HENDERSON_MOVING_AVERAGE);
+    let seasonal = seasonal_component(series, &trend);
+    let adjusted = subtract(series, &seasonal);
+
+    SeasonalComponents {
+        trend: symmetric_moving_average(&adjusted, &HENDERSON_MOVING_AVERAGE),
+        adjusted,
+        seasonal,
+    }
+}
+
+/// Smooths the deviations from the trend across the same quarter of different years, then
+/// centers the result so that the seasonal component sums to zero over any four quarters.
+fn seasonal_component(series: &[f64], trend: &[f64]) -> Vec<f64> {
+    let deviations = subtract(series, trend);
+    let mut seasonal = vec![0.0; series.len()];
+
+    for position in 0..PERIOD {
+        let indices: Vec<usize> = (position..series.len()).step_by(PERIOD).collect();
+        let same_quarter: Vec<f64> = indices.iter().map(|index| deviations[*index]).collect();
+
+        let smoothed = symmetric_moving_average(&same_quarter, &SEASONAL_MOVING_AVERAGE);
+        for (index, value) in indices.into_iter().zip(smoothed) {
+            seasonal[index] = value;
+        }
+    }
+
+    let level = centered_moving_average(&seasonal);
+    subtract(&seasonal, &level)
+}
+
+/// A truncated 2x4 window would no longer cover every season, so the first and last two quarters
+/// extend the complete averages linearly using the slope of their nearest year.
+fn centered_moving_average(values: &[f64]) -> Vec<f64> {
+    let half_window = CENTERED_MOVING_AVERAGE.len() / 2;
+    let last_complete = values.len() - half_window - 1;
+
+    let complete: Vec<f64> = (half_window..=last_complete)
+        .map(|index| {
+            CENTERED_MOVING_AVERAGE.iter()
+                .enumerate()
+                .map(|(offset, weight)| weight * values[index + offset - half_window])
+                .sum()
+        })
+        .collect();
+
+    let first_slope = (complete[PERIOD - 1] - complete[0]) / (PERIOD - 1) as f64;
+    let last_slope = (complete[complete.len() - 1] - complete[complete.len() - PERIOD]) / (PERIOD - 1) as f64;
+
+    (0..values.len())
+        .map(|index| {
+            if index < half_window {
+                complete[0] - first_slope * (half_window - index) as f64
+            } else if index > last_complete {
+                complete[complete.len() - 1] + last_slope * (index - last_complete) as f64
+            } else {
+                complete[index - half_window]
+            }
+        })
+        .collect()
+}
+
+/// Near the ends the window is truncated and the remaining weights are rescaled to sum to one.
+fn symmetric_moving_average(values: &[f64], weights: &[f64]) -> Vec<f64> {
+    let half_window = weights.len() / 2;
+
+    (0..values.len())
+        .map(|index| {
+            let (total, weight_sum) = weights.iter()
+                .enumerate()
+                .filter_map(|(offset, weight)| {
+                    let position = (index + offset).checked_sub(half_window)?;
+                    values.get(position).map(|value| (weight * value, *weight))
+                })
+                .fold((0.0, 0.0), |(total, weight_sum), (value, weight)| (total + value, weight_sum + weight));
+
+            total / weight_sum
+        })
+        .collect()
+}
+
+fn subtract(left: &[f64], right: &[f64]) -> Vec<f64> {
+    left.iter().zip(right).map(|(left, right)| left - right).collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::datasets_config::datasets_config::{SeasonalAdjustmentConfig, UnitConfig};
+
+    use super::*;
+
+    const ADDITIVE_PATTERN: [f64; 4] = [5.0, -3.0, -4.0, 2.0];
+    const MULTIPLICATIVE_PATTERN: [f64; 4] = [1.05, 0.97, 0.96, 1.02];
+
+    fn quarters(count: usize) -> Vec<String> {
+        (0..count).map(|index| format!("{}-Q{}", 2010 + index / 4, index % 4 + 1)).collect()
+    }
+
+    fn additive_series(count: usize) -> Vec<f64> {
+        (0..count).map(|index| 100.0 + index as f64 + ADDITIVE_PATTERN[index % 4]).collect()
+    }
+
+    fn dataset_config(name: &'static str, mode: Option<SeasonalAdjustmentMode>) -> DatasetConfig {
+        DatasetConfig::new(name,
+                           "test_source",
+                           "test_id",
+                           false,
+                           None,
+                           Some(UnitConfig::new("EUR", Scale::Billions, true)),
+                           mode.map(SeasonalAdjustmentConfig::new))
+    }
+
+    #[test]
+    fn test_decompose_additive_recovers_seasonal_pattern() {
+        let components = decompose(&additive_series(24), SeasonalAdjustmentMode::Additive).unwrap();
+
+        for index in 4..20 {
+            assert!((components.seasonal[index] - ADDITIVE_PATTERN[index % 4]).abs() < 0.1,
+                    "seasonal {index}: {}", components.seasonal[index]);
+            assert!((components.adjusted[index] - (100.0 + index as f64)).abs() < 0.1);
+            assert!((components.trend[index] - (100.0 + index as f64)).abs() < 0.1);
+        }
+    }
+
+    #[test]
+    fn test_decompose_multiplicative_recovers_seasonal_factors() {
+        let values: Vec<f64> = (0..24)
+            .map(|index| 100.0 * 1.01_f64.powi(index as i32) * MULTIPLICATIVE_PATTERN[index % 4])
+            .collect();
+
+        let components = decompose(&values, SeasonalAdjustmentMode::Multiplicative).unwrap();
+
+        for index in 4..20 {
+            assert!((components.seasonal[index] - MULTIPLICATIVE_PATTERN[index % 4]).abs() < 0.005,
+                    "seasonal {index}: {}", components.seasonal[index]);
+            let growth = components.adjusted[index + 1] / components.adjusted[index];
+            assert!((growth - 1.01).abs() < 0.005, "growth {index}: {growth}");
+        }
+    }
+
+    #[test]
+    fn test_decompose_rejects_short_or_non_positive_series() {
+        assert_eq!(decompose(&additive_series(11), SeasonalAdjustmentMode::Additive), None);
+
+        let mut values = additive_series(16);
+        values[3] = -1.0;
+        assert!(decompose(&values, SeasonalAdjustmentMode::Additive).is_some());
+        assert_eq!(decompose(&values, SeasonalAdjustmentMode::Multiplicative), None);
+    }
+
+    #[test]
+    fn test_seasonally_adjust_enabled_datasets() {
+        let debt = DatasetWithConfig::new("debt",
+                                          df!["quarter" => quarters(16), "debt" => additive_series(16)].unwrap(),
+                                          dataset_config("debt", Some(SeasonalAdjustmentMode::Additive)));
+        let gdp = DatasetWithConfig::new("gdp",
+                                         df!["quarter" => quarters(16), "gdp" => additive_series(16)].unwrap(),
+                                         dataset_config("gdp", None));
+
+        let result = seasonally_adjust(vec![debt.clone(), gdp.clone()]);
+
+        assert_eq!(result[1].dataframe, gdp.dataframe);
+
+        let expected = decompose(&additive_series(16), SeasonalAdjustmentMode::Additive).unwrap();
+        assert_eq!(result[0].dataframe.get_column_names(), vec!["quarter", "debt", "debt_sa", "debt_trend", "debt_seasonal"]);
+        assert_eq!(result[0].dataframe.column("debt").unwrap(), debt.dataframe.column("debt").unwrap());
+        assert_eq!(column_values(&result[0].dataframe, "debt_sa").unwrap(), expected.adjusted);
+        assert_eq!(column_values(&result[0].dataframe, "debt_trend").unwrap(), expected.trend);
+        assert_eq!(column_values(&result[0].dataframe, "debt_seasonal").unwrap(), expected.seasonal);
+    }
+
+    #[test]
+    fn test_seasonally_adjust_skips_columns_with_gaps() {
+        let mut values: Vec<Option<f64>> = additive_series(16).into_iter().map(Some).collect();
+        values[5] = None;
+        let debt = DatasetWithConfig::new("debt",
+                                          df!["quarter" => quarters(16), "debt" => values].unwrap(),
+                                          dataset_config("debt", Some(SeasonalAdjustmentMode::Multiplicative)));
+
+        let result = seasonally_adjust(vec![debt.clone()]);
+
+        assert_eq!(result[0].dataframe, debt.dataframe);
+    }
+
+    #[test]
+    fn test_record_seasonal_adjustments() {
+        let dataset_configs = vec![
+            dataset_config("debt", Some(SeasonalAdjustmentMode::Multiplicative)),
+            dataset_config("gdp", None),
+        ];
+        let mut metadata = vec![
+            ColumnMetadata::new("debt_converted", "debt", "USD", Scale::Billions),
+            ColumnMetadata::new("debt_converted_sa", "debt", "USD", Scale::Billions),
+            ColumnMetadata::new("debt_converted_trend", "debt", "USD", Scale::Billions),
+            ColumnMetadata::new("debt_converted_seasonal", "debt", "USD", Scale::Billions),
+            ColumnMetadata::new("gdp_sa", "gdp", "USD", Scale::Billions),
+        ];
+
+        record_seasonal_adjustments(&mut metadata, &dataset_configs);
+
+        assert!(metadata[0].transformations.is_empty());
+        assert_eq!(metadata[1].transformations, vec!["seasonally adjusted (multiplicative X-11 moving averages)"]);
+        assert_eq!(metadata[2].transformations, vec!["trend-cycle of seasonal adjustment"]);
+        assert_eq!(metadata[2].unit, "USD");
+        assert_eq!(metadata[3].transformations, vec!["seasonal component of seasonal adjustment"]);
+        assert_eq!((metadata[3].unit.as_str(), metadata[3].scale), ("seasonal factor", Scale::Units));
+        assert!(metadata[4].transformations.is_empty());
+    }
+}
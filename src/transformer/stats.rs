@@ -0,0 +1,278 @@
+use log::{info, warn};
+use polars::prelude::*;
+
+use crate::datasets_config::datasets_config::StatisticsConfig;
+use crate::model::data_model::Statistic;
+
+const MIN_OBSERVATIONS: usize = 3;
+
+/// Compares every configured column to the benchmark: Pearson and Spearman correlations over
+/// the whole sample, cross-correlations at leads and lags, and rolling Pearson correlations.
+/// Columns that are missing from the joined dataset are skipped.
+pub fn compute_statistics(result: &DataFrame, statistics_config: &StatisticsConfig) -> Vec<Statistic> {
+    let result = result.sort(["quarter"], SortMultipleOptions::new()).unwrap();
+    let benchmark_name = statistics_config.benchmark;
+
+    let quarters: Vec<String> = result.column("quarter").unwrap()
+        .str().unwrap()
+        .into_iter()
+        .map(|quarter| quarter.unwrap_or_default().to_string())
+        .collect();
+
+    let benchmark = match column_values(&result, benchmark_name) {
+        Some(benchmark) => benchmark,
+        None => {
+            warn!("skipping statistics, benchmark column {benchmark_name} is missing");
+            return vec![];
+        }
+    };
+
+    let mut statistics: Vec<Statistic> = vec![];
+
+    for column_name in statistics_config.columns {
+        let values = match column_values(&result, column_name) {
+            Some(values) => values,
+            None => {
+                warn!("skipping statistics of {column_name}, column is missing");
+                continue;
+            }
+        };
+
+        let column_statistics = compare(column_name, &values, benchmark_name, &benchmark, &quarters, statistics_config);
+        log_summary(column_name, benchmark_name, &column_statistics);
+        statistics.extend(column_statistics);
+    }
+
+    statistics
+}
+
+pub fn column_values(result: &DataFrame, column_name: &str) -> Option<Vec<Option<f64>>> {
+    let values = result.column(column_name).ok()?.cast(&DataType::Float64).ok()?;
+
+    Some(values.f64().ok()?
+        .into_iter()
+        .map(|value| value.filter(|value| value.is_finite()))
+        .collect())
+}
+
+fn compare(column_name: &str,
+           values: &[Option<f64>],
+           benchmark_name: &str,
+           benchmark: &[Option<f64>],
+           quarters: &[String],
+           statistics_config: &StatisticsConfig) -> Vec<Statistic> {
+    let mut statistics: Vec<Statistic> = vec![];
+
+    let pairs = complete_pairs(values, benchmark);
+    if let Some(correlation) = pearson(&pairs) {
+        statistics.push(Statistic::new(column_name, benchmark_name, "pearson", 0, None, pairs.len(), correlation));
+    }
+    if let Some(correlation) = spearman(&pairs) {
+        statistics.push(Statistic::new(column_name, benchmark_name, "spearman", 0, None, pairs.len(), correlation));
+    }
+
+    let max_lag = statistics_config.max_lag as i64;
+    for lag in -max_lag..=max_lag {
+        let pairs = lagged_pairs(values, benchmark, lag);
+        if let Some(correlation) = pearson(&pairs) {
+            statistics.push(Statistic::new(column_name, benchmark_name, "cross_correlation", lag, None, pairs.len(), correlation));
+        }
+    }
+
+    let window = statistics_config.rolling_window;
+    for end in window.saturating_sub(1)..values.len() {
+        let start = end + 1 - window;
+        let pairs = complete_pairs(&values[start..=end], &benchmark[start..=end]);
+
+        if pairs.len() < window {
+            continue;
+        }
+        if let Some(correlation) = pearson(&pairs) {
+            statistics.push(Statistic::new(column_name, benchmark_name, "rolling_pearson", 0, Some(&quarters[end]), window, correlation));
+        }
+    }
+
+    statistics
+}
+
+pub fn complete_pairs(values: &[Option<f64>], benchmark: &[Option<f64>]) -> Vec<(f64, f64)> {
+    values.iter()
+        .zip(benchmark)
+        .filter_map(|(value, benchmark)| Some(((*value)?, (*benchmark)?)))
+        .collect()
+}
+
+/// Pairs each value with the benchmark `lag` quarters later, so a positive lag measures how well
+/// the series leads the benchmark.
+fn lagged_pairs(values: &[Option<f64>], benchmark: &[Option<f64>], lag: i64) -> Vec<(f64, f64)> {
+    let shift = lag.unsigned_abs() as usize;
+
+    if shift >= values.len() {
+        return vec![];
+    }
+
+    if lag >= 0 {
+        complete_pairs(&values[..values.len() - shift], &benchmark[shift..])
+    } else {
+        complete_pairs(&values[shift..], &benchmark[..benchmark.len() - shift])
+    }
+}
+
+pub fn pearson(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < MIN_OBSERVATIONS {
+        return None;
+    }
+
+    let count = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y = pairs.iter().map(|(_, y)| y).sum::<f64>() / count;
+
+    let (covariance, variance_x, variance_y) = pairs.iter()
+        .fold((0.0, 0.0, 0.0), |(covariance, variance_x, variance_y), (x, y)| {
+            let (dx, dy) = (x - mean_x, y - mean_y);
+            (covariance + dx * dy, variance_x + dx * dx, variance_y + dy * dy)
+        });
+
+    if variance_x == 0.0 || variance_y == 0.0 {
+        return None;
+    }
+
+    Some(covariance / (variance_x * variance_y).sqrt())
+}
+
+fn spearman(pairs: &[(f64, f64)]) -> Option<f64> {
+    let x_ranks = ranks(&pairs.iter().map(|(x, _)| *x).collect::<Vec<f64>>());
+    let y_ranks = ranks(&pairs.iter().map(|(_, y)| *y).collect::<Vec<f64>>());
+
+    pearson(&x_ranks.into_iter().zip(y_ranks).collect::<Vec<(f64, f64)>>())
+}
+
+/// One-based ranks, with tied values sharing the average of their ranks.
+fn ranks(values: &[f64]) -> Vec<f64> {
+    let mut order: Vec<usize> = (0..values.len()).collect();
+    order.sort_by(|a, b| values[*a].total_cmp(&values[*b]));
+
+    let mut ranks = vec![0.0; values.len()];
+    let mut start = 0;
+
+    while start < order.len() {
+        let mut end = start;
+        while end + 1 < order.len() && values[order[end + 1]] == values[order[start]] {
+            end += 1;
+        }
+
+        let average_rank = (start + end) as f64 / 2.0 + 1.0;
+        for index in &order[start..=end] {
+            ranks[*index] = average_rank;
+        }
+        start = end + 1;
+    }
+
+    ranks
+}
+
+fn log_summary(column_name: &str, benchmark_name: &str, statistics: &[Statistic]) {
+    let value_of = |statistic_name: &str| {
+        statistics.iter()
+            .find(|statistic| statistic.statistic == statistic_name)
+            .map(|statistic| format!("{:.2}", statistic.value))
+            .unwrap_or_else(|| "n/a".to_string())
+    };
+
+    let strongest_lag = statistics.iter()
+        .filter(|statistic| statistic.statistic == "cross_correlation")
+        .max_by(|a, b| a.value.abs().total_cmp(&b.value.abs()))
+        .map(|statistic| format!("{:.2} at lag {}", statistic.value, statistic.lag))
+        .unwrap_or_else(|| "n/a".to_string());
+
+    info!("{column_name} vs {benchmark_name}: pearson {}, spearman {}, strongest cross-correlation {strongest_lag}",
+          value_of("pearson"),
+          value_of("spearman"));
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(actual: f64, expected: f64) {
+        assert!((actual - expected).abs() < 1e-9, "{actual} != {expected}");
+    }
+
+    fn pairs(x: &[f64], y: &[f64]) -> Vec<(f64, f64)> {
+        x.iter().copied().zip(y.iter().copied()).collect()
+    }
+
+    #[test]
+    fn test_pearson() {
+        assert_close(pearson(&pairs(&[1.0, 2.0, 3.0, 4.0], &[2.0, 4.0, 6.0, 8.0])).unwrap(), 1.0);
+        assert_close(pearson(&pairs(&[1.0, 2.0, 3.0, 4.0], &[8.0, 6.0, 4.0, 2.0])).unwrap(), -1.0);
+        assert_close(pearson(&pairs(&[1.0, 2.0, 3.0], &[1.0, 3.0, 2.0])).unwrap(), 0.5);
+        assert_eq!(pearson(&pairs(&[1.0, 2.0, 3.0], &[5.0, 5.0, 5.0])), None);
+        assert_eq!(pearson(&pairs(&[1.0, 2.0], &[1.0, 2.0])), None);
+    }
+
+    #[test]
+    fn test_spearman_uses_average_ranks() {
+        assert_eq!(ranks(&[10.0, 30.0, 20.0, 20.0]), vec![1.0, 4.0, 2.5, 2.5]);
+        assert_close(spearman(&pairs(&[1.0, 2.0, 3.0, 4.0, 5.0], &[1.0, 4.0, 9.0, 16.0, 100.0])).unwrap(), 1.0);
+    }
+
+    #[test]
+    fn test_lagged_pairs() {
+        let values = [Some(1.0), Some(2.0), None, Some(4.0)];
+        let benchmark = [Some(10.0), Some(20.0), Some(30.0), Some(40.0)];
+
+        assert_eq!(lagged_pairs(&values, &benchmark, 1), vec![(1.0, 20.0), (2.0, 30.0)]);
+        assert_eq!(lagged_pairs(&values, &benchmark, -1), vec![(2.0, 10.0), (4.0, 30.0)]);
+        assert!(lagged_pairs(&values, &benchmark, 4).is_empty());
+    }
+
+    #[test]
+    fn test_compute_statistics() {
+        let series = [1.0, 5.0, 2.0, 8.0, 3.0, 9.0, 4.0, 7.0, 6.0, 10.0];
+        // the benchmark follows the series two quarters later
+        let benchmark: Vec<Option<f64>> = (0..series.len())
+            .map(|index| index.checked_sub(2).map(|lagged| series[lagged] * 2.0))
+            .collect();
+        let df = df![
+            "quarter" => (0..series.len()).map(|index| format!("{}-Q{}", 2020 + index / 4, index % 4 + 1)).collect::<Vec<_>>(),
+            "macro" => &series,
+            "benchmark" => benchmark
+        ].unwrap();
+
+        let statistics_config = StatisticsConfig::new("benchmark", &["macro", "not_there"], 4, 2);
+        let statistics = compute_statistics(&df, &statistics_config);
+
+        let cross_correlations: Vec<&Statistic> = statistics.iter()
+            .filter(|statistic| statistic.statistic == "cross_correlation")
+            .collect();
+        assert_eq!(cross_correlations.iter().map(|statistic| statistic.lag).collect::<Vec<_>>(), vec![-2, -1, 0, 1, 2]);
+        assert_close(cross_correlations[4].value, 1.0);
+        assert_eq!(cross_correlations[4].observations, 8);
+
+        let rolling: Vec<&Statistic> = statistics.iter()
+            .filter(|statistic| statistic.statistic == "rolling_pearson")
+            .collect();
+        assert_eq!(rolling.len(), 5);
+        assert_eq!(rolling[0].quarter.as_deref(), Some("2021-Q2"));
+        assert_eq!(rolling[4].quarter.as_deref(), Some("2022-Q2"));
+
+        let pearson = statistics.iter().find(|statistic| statistic.statistic == "pearson").unwrap();
+        assert_eq!(pearson.series, "macro");
+        assert_eq!(pearson.benchmark, "benchmark");
+        assert_eq!(pearson.observations, 8);
+
+        assert!(statistics.iter().all(|statistic| statistic.series == "macro"));
+    }
+
+    #[test]
+    fn test_compute_statistics_without_benchmark() {
+        let df = df![
+            "quarter" => &["2023-Q1"],
+            "macro" => &[1.0]
+        ].unwrap();
+
+        assert!(compute_statistics(&df, &StatisticsConfig::new("benchmark", &["macro"], 4, 1)).is_empty());
+    }
+}
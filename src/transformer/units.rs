@@ -0,0 +1,130 @@
+use log::{info};
+use polars::prelude::*;
+
+use crate::datasets_config::datasets_config::{Scale, UnitConfig};
+use crate::model::data_model::{ColumnMetadata, DatasetWithConfig};
+
+pub fn harmonize_units(datasets: Vec<DatasetWithConfig>, target_scale: Scale) -> Vec<DatasetWithConfig> {
+    let mut output_datasets: Vec<DatasetWithConfig> = vec![];
+
+    for dataset in datasets {
+        let dataset_name = dataset.name;
+        let mut dataset_config = dataset.dataset_config;
+
+        let unit_config = match dataset_config.unit_config {
+            Some(unit_config) if unit_config.monetary && unit_config.scale != target_scale => unit_config,
+            _ => {
+                output_datasets.push(dataset);
+                continue;
+            }
+        };
+
+        let factor = unit_config.scale.multiplier() / target_scale.multiplier();
+        info!("rescaling {dataset_name} from {} to {}", unit_config.scale.name(), target_scale.name());
+
+        let rescaled_dataframe = rescale_value_columns(dataset.dataframe, factor).unwrap();
+        dataset_config.unit_config = Some(UnitConfig::new(unit_config.unit, target_scale, unit_config.monetary));
+
+        output_datasets.push(DatasetWithConfig::new(dataset_name, rescaled_dataframe, dataset_config));
+    }
+
+    output_datasets
+}
+
+fn rescale_value_columns(df: DataFrame, factor: f64) -> PolarsResult<DataFrame> {
+    let value_columns: Vec<Expr> = df.get_column_names()
+        .into_iter()
+        .filter(|name| name.as_str() != "quarter")
+        .map(|name| (col(name.clone()).cast(DataType::Float64) * lit(factor)).alias(name.clone()))
+        .collect();
+
+    df.lazy().with_columns(value_columns).collect()
+}
+
+pub fn collect_column_metadata(datasets: &[DatasetWithConfig]) -> Vec<ColumnMetadata> {
+    let mut column_metadata: Vec<ColumnMetadata> = vec![];
+
+    for dataset in datasets {
+        let (unit, scale) = match dataset.dataset_config.unit_config {
+            Some(unit_config) => (unit_config.unit, unit_config.scale),
+            None => ("unspecified", Scale::Units),
+        };
+
+        for column_name in dataset.dataframe.get_column_names() {
+            if column_name.as_str() == "quarter" {
+                continue;
+            }
+
+            let mut metadata = ColumnMetadata::new(column_name, dataset.dataset_config.name, unit, scale);
+            metadata.transformations = dataset.transformations.clone();
+            column_metadata.push(metadata);
+        }
+    }
+
+    column_metadata
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::datasets_config::datasets_config::DatasetConfig;
+
+    use super::*;
+
+    fn dataset(name: &'static str, unit_config: Option<UnitConfig>) -> DatasetWithConfig {
+        let df = df![
+            "quarter" => &["2023-Q1", "2023-Q2"],
+            name => &[1500.0, 2500.0]
+        ].unwrap();
+
+        DatasetWithConfig::new(name, df, DatasetConfig::new(name, "test_source", "test_id", false, None, unit_config, None))
+    }
+
+    #[test]
+    fn test_harmonize_units_rescales_monetary_datasets() {
+        let datasets = vec![
+            dataset("debt", Some(UnitConfig::new("USD", Scale::Millions, true))),
+            dataset("gdp", Some(UnitConfig::new("USD", Scale::Billions, true))),
+        ];
+
+        let result = harmonize_units(datasets, Scale::Billions);
+
+        let debt = result[0].dataframe.column("debt").unwrap().f64().unwrap().into_no_null_iter().collect::<Vec<_>>();
+        assert!((debt[0] - 1.5).abs() < 1e-9);
+        assert!((debt[1] - 2.5).abs() < 1e-9);
+        assert_eq!(result[0].dataset_config.unit_config.unwrap().scale, Scale::Billions);
+
+        let gdp = result[1].dataframe.column("gdp").unwrap().f64().unwrap().into_no_null_iter().collect::<Vec<_>>();
+        assert_eq!(gdp, vec![1500.0, 2500.0]);
+    }
+
+    #[test]
+    fn test_harmonize_units_leaves_non_monetary_datasets() {
+        let datasets = vec![
+            dataset("inflation", Some(UnitConfig::new("percent", Scale::Units, false))),
+            dataset("unknown", None),
+        ];
+
+        let result = harmonize_units(datasets.clone(), Scale::Billions);
+
+        assert_eq!(result[0].dataframe, datasets[0].dataframe);
+        assert_eq!(result[1].dataframe, datasets[1].dataframe);
+    }
+
+    #[test]
+    fn test_collect_column_metadata() {
+        let datasets = vec![
+            dataset("debt", Some(UnitConfig::new("USD", Scale::Billions, true))),
+            dataset("unknown", None).with_transformation("converted"),
+        ];
+
+        let metadata = collect_column_metadata(&datasets);
+
+        let mut converted = ColumnMetadata::new("unknown", "unknown", "unspecified", Scale::Units);
+        converted.transformations.push("converted".to_string());
+        assert_eq!(metadata, vec![
+            ColumnMetadata::new("debt", "debt", "USD", Scale::Billions),
+            converted,
+        ]);
+    }
+}
@@ -0,0 +1,290 @@
+use std::collections::HashMap;
+
+use log::{info, warn};
+use polars::prelude::*;
+
+use crate::datasets_config::datasets_config::{AnomalyConfig, OutlierMethod, ValidationConfig};
+use crate::model::data_model::{Anomaly, DatasetWithConfig};
+use crate::validator::validation::period_start;
+
+/// Flags outliers within every extracted dataset, and scale changes or level shifts of the
+/// periods its fetched observations share with the previous run's cached copy. The comparison
+/// uses `fetched_datasets` rather than `datasets`, as an update merges the cached observations
+/// before the revision window back in, and those would always match the cache.
+pub fn detect_anomalies(datasets: &[DatasetWithConfig],
+                        fetched_datasets: &[DatasetWithConfig],
+                        cached_datasets: &HashMap<String, DataFrame>,
+                        validation_configs: &[ValidationConfig],
+                        anomaly_config: &AnomalyConfig) -> Vec<Anomaly> {
+    let mut anomalies: Vec<Anomaly> = vec![];
+
+    for dataset in datasets {
+        let Some(validation_config) = validation_configs.iter().find(|config| config.dataset == dataset.name) else {
+            continue;
+        };
+
+        let Some(mut current) = observations(&dataset.dataframe, validation_config) else {
+            warn!("skipping anomaly detection of {}, columns do not match its validation config", dataset.name);
+            continue;
+        };
+        // The rolling windows need neighbouring periods next to each other, whatever the source's row order
+        current.sort_by_key(|(period, _)| period_start(period));
+
+        let values: Vec<f64> = current.iter().map(|(_, value)| *value).collect();
+
+        for outlier_method in anomaly_config.outlier_methods {
+            let scores = outlier_scores(&values, *outlier_method, anomaly_config.window);
+
+            let outliers: Vec<Anomaly> = current.iter()
+                .zip(scores)
+                .filter_map(|((period, value), score)| {
+                    let score = score.filter(|score| score.abs() > anomaly_config.outlier_threshold)?;
+                    Some(Anomaly::new(dataset.name, period, outlier_method.name(), *value, score))
+                })
+                .collect();
+
+            info!("{} {} outliers in {}", outliers.len(), outlier_method.name(), dataset.name);
+            anomalies.extend(outliers);
+        }
+
+        let fetched = fetched_datasets.iter()
+            .find(|fetched| fetched.name == dataset.name)
+            .and_then(|fetched| observations(&fetched.dataframe, validation_config));
+        let cached = cached_datasets.get(dataset.name).and_then(|cached| observations(cached, validation_config));
+        if let Some(anomaly) = fetched.zip(cached).and_then(|(fetched, cached)| compare_with_cache(dataset.name, &fetched, &cached, anomaly_config)) {
+            warn!("{} of {} from {}, magnitude {}", anomaly.kind, dataset.name, anomaly.period, anomaly.magnitude);
+            anomalies.push(anomaly);
+        }
+    }
+
+    anomalies
+}
+
+/// Datasets with more anomalies than `max_anomalies` allows.
+pub fn datasets_above_threshold(anomalies: &[Anomaly], anomaly_config: &AnomalyConfig) -> Vec<String> {
+    let Some(max_anomalies) = anomaly_config.max_anomalies else {
+        return vec![];
+    };
+
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for anomaly in anomalies {
+        *counts.entry(anomaly.dataset.as_str()).or_default() += 1;
+    }
+
+    let mut datasets: Vec<String> = counts.into_iter()
+        .filter(|(_, count)| *count > max_anomalies)
+        .map(|(dataset, _)| dataset.to_string())
+        .collect();
+    datasets.sort();
+    datasets
+}
+
+fn observations(dataframe: &DataFrame, validation_config: &ValidationConfig) -> Option<Vec<(String, f64)>> {
+    let dates = dataframe.column(validation_config.date_column).ok()?.str().ok()?;
+    let values = dataframe.column(validation_config.value_column).ok()?.f64().ok()?;
+
+    Some(dates.into_iter()
+        .zip(values)
+        .filter_map(|(date, value)| Some((date?.to_string(), value?)))
+        .collect())
+}
+
+/// Scores each observation against its neighbours; `None` where the window is too short or flat.
+fn outlier_scores(values: &[f64], outlier_method: OutlierMethod, window: usize) -> Vec<Option<f64>> {
+    (0..values.len())
+        .map(|index| match outlier_method {
+            OutlierMethod::RollingZScore => {
+                let preceding = &values[index.saturating_sub(window)..index];
+                if preceding.len() < 2 {
+                    return None;
+                }
+
+                let mean = preceding.iter().sum::<f64>() / preceding.len() as f64;
+                let variance = preceding.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / (preceding.len() - 1) as f64;
+                (variance > 0.0).then(|| (values[index] - mean) / variance.sqrt())
+            },
+            OutlierMethod::RollingMedianAbsoluteDeviation => {
+                let half_window = window / 2;
+                let neighbourhood = &values[index.saturating_sub(half_window)..(index + half_window + 1).min(values.len())];
+                if neighbourhood.len() <= half_window {
+                    return None;
+                }
+
+                let median_value = median(neighbourhood.to_vec())?;
+                let deviations: Vec<f64> = neighbourhood.iter().map(|value| (value - median_value).abs()).collect();
+                let mad = median(deviations)?;
+                (mad > 0.0).then(|| 0.6745 * (values[index] - median_value) / mad)
+            },
+        })
+        .collect()
+}
+
+fn compare_with_cache(dataset: &str,
+                      current: &[(String, f64)],
+                      cached: &[(String, f64)],
+                      anomaly_config: &AnomalyConfig) -> Option<Anomaly> {
+    let cached: HashMap<&str, f64> = cached.iter().map(|(period, value)| (period.as_str(), *value)).collect();
+    let shared: Vec<(&str, f64, f64)> = current.iter()
+        .filter_map(|(period, value)| Some((period.as_str(), *value, *cached.get(period.as_str())?)))
+        .collect();
+
+    let ratio = median(shared.iter().filter(|(_, _, cached)| *cached != 0.0).map(|(_, current, cached)| current / cached).collect());
+    if let Some(ratio) = ratio {
+        let factor = anomaly_config.scale_change_factor;
+        let rescaled = |ratio: f64| ratio > factor || ratio < 1.0 / factor;
+
+        if rescaled(ratio) {
+            let (period, value, _) = shared.iter().find(|(_, current, cached)| *cached != 0.0 && rescaled(current / cached))?;
+            return Some(Anomaly::new(dataset, period, "scale_change", *value, ratio));
+        }
+    }
+
+    let difference = median(shared.iter().map(|(_, current, cached)| current - cached).collect())?;
+    let tolerance = anomaly_config.level_shift_tolerance * median(shared.iter().map(|(_, _, cached)| cached.abs()).collect())?;
+
+    if difference.abs() > tolerance {
+        let (period, value, _) = shared.iter().find(|(_, current, cached)| (current - cached).abs() > tolerance)?;
+        return Some(Anomaly::new(dataset, period, "level_shift", *value, difference));
+    }
+
+    None
+}
+
+fn median(mut values: Vec<f64>) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    values.sort_by(|a, b| a.total_cmp(b));
+    let middle = values.len() / 2;
+
+    Some(if values.len().is_multiple_of(2) { (values[middle - 1] + values[middle]) / 2.0 } else { values[middle] })
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::datasets_config::datasets_config::get_all_datasets_configs;
+
+    use super::*;
+
+    fn anomaly_config(outlier_methods: &'static [OutlierMethod]) -> AnomalyConfig {
+        AnomalyConfig::new(outlier_methods, 5, 5.0, 2.0, 0.05, Some(1))
+    }
+
+    fn validation_config() -> ValidationConfig {
+        ValidationConfig::new("us_gdp", "date", "value", 0.9, None, 92, 200)
+    }
+
+    fn dataset(values: &[Option<f64>]) -> DatasetWithConfig {
+        let dates: Vec<String> = (0..values.len()).map(|index| format!("{}-01-01", 2000 + index)).collect();
+        let dataset_config = get_all_datasets_configs().into_iter().find(|config| config.name == "us_gdp").unwrap();
+
+        DatasetWithConfig::new("us_gdp", df!["date" => dates, "value" => values].unwrap(), dataset_config)
+    }
+
+    #[test]
+    fn test_outlier_scores() {
+        let values = [10.0, 11.0, 10.5, 11.5, 10.0, 11.0, 10500.0, 10.5, 11.0, 10.0];
+
+        for outlier_method in [OutlierMethod::RollingZScore, OutlierMethod::RollingMedianAbsoluteDeviation] {
+            let scores = outlier_scores(&values, outlier_method, 5);
+            let flagged: Vec<usize> = scores.iter()
+                .enumerate()
+                .filter(|(_, score)| score.is_some_and(|score| score.abs() > 5.0))
+                .map(|(index, _)| index)
+                .collect();
+
+            assert_eq!(flagged, vec![6], "{}", outlier_method.name());
+        }
+
+        assert_eq!(outlier_scores(&[1.0, 1.0, 1.0], OutlierMethod::RollingZScore, 5), vec![None, None, None]);
+    }
+
+    #[test]
+    fn test_detect_anomalies_flags_outliers() {
+        let values: Vec<Option<f64>> = [10.0, 11.0, 10.5, 11.5, 10.0, 11.0, 10500.0, 10.5, 11.0, 10.0].into_iter().map(Some).collect();
+
+        let anomalies = detect_anomalies(&[dataset(&values)],
+                                         &[dataset(&values)],
+                                         &HashMap::new(),
+                                         &[validation_config()],
+                                         &anomaly_config(&[OutlierMethod::RollingMedianAbsoluteDeviation]));
+
+        assert_eq!(anomalies.len(), 1);
+        assert_eq!(anomalies[0].period, "2006-01-01");
+        assert_eq!(anomalies[0].kind, "rolling_mad");
+        assert_eq!(anomalies[0].value, 10500.0);
+    }
+
+    #[test]
+    fn test_detect_anomalies_scores_observations_in_period_order() {
+        // A smooth trend, with its last year listed after the fifth as ECB rows can be
+        let rows: Vec<usize> = (0..5).chain([19]).chain(5..19).collect();
+        let dates: Vec<String> = rows.iter().map(|row| format!("{}-01-01", 2000 + row)).collect();
+        let values: Vec<f64> = rows.iter().map(|row| 10.0 + 0.1 * *row as f64).collect();
+        let dataset_config = get_all_datasets_configs().into_iter().find(|config| config.name == "us_gdp").unwrap();
+        let unordered = DatasetWithConfig::new("us_gdp", df!["date" => dates, "value" => values].unwrap(), dataset_config);
+
+        let anomalies = detect_anomalies(&[unordered],
+                                         &[],
+                                         &HashMap::new(),
+                                         &[validation_config()],
+                                         &anomaly_config(&[OutlierMethod::RollingZScore, OutlierMethod::RollingMedianAbsoluteDeviation]));
+
+        assert_eq!(anomalies, vec![]);
+    }
+
+    #[test]
+    fn test_detect_anomalies_flags_scale_change_against_cache() {
+        let cached = dataset(&[Some(1.0), Some(2.0), Some(3.0)]);
+        let current = [dataset(&[Some(1000.0), Some(2000.0), Some(3000.0), Some(4000.0)])];
+        let cached_datasets: HashMap<String, DataFrame> = [("us_gdp".to_string(), cached.dataframe)].into_iter().collect();
+
+        let anomalies = detect_anomalies(&current, &current, &cached_datasets, &[validation_config()], &anomaly_config(&[OutlierMethod::RollingZScore]));
+
+        assert_eq!(anomalies, vec![Anomaly::new("us_gdp", "2000-01-01", "scale_change", 1000.0, 1000.0)]);
+    }
+
+    #[test]
+    fn test_detect_anomalies_flags_scale_change_in_the_refetched_window() {
+        let cached = dataset(&[Some(1.0), Some(2.0), Some(3.0), Some(4.0), Some(5.0)]);
+        let merged = dataset(&[Some(1.0), Some(2.0), Some(3.0), Some(4000.0), Some(5000.0)]);
+        let fetched = DatasetWithConfig::new("us_gdp", merged.dataframe.slice(3, 2), merged.dataset_config);
+        let cached_datasets: HashMap<String, DataFrame> = [("us_gdp".to_string(), cached.dataframe)].into_iter().collect();
+
+        let anomalies = detect_anomalies(&[merged], &[fetched], &cached_datasets, &[validation_config()], &anomaly_config(&[]));
+
+        assert_eq!(anomalies, vec![Anomaly::new("us_gdp", "2003-01-01", "scale_change", 4000.0, 1000.0)]);
+    }
+
+    #[test]
+    fn test_compare_with_cache() {
+        let anomaly_config = anomaly_config(&[OutlierMethod::RollingZScore]);
+        let observations = |values: &[f64]| -> Vec<(String, f64)> {
+            values.iter().enumerate().map(|(index, value)| (format!("2024-Q{}", index + 1), *value)).collect()
+        };
+
+        let cached = observations(&[2.0, 2.5, 3.0, 2.0]);
+
+        assert_eq!(compare_with_cache("eu_inflation", &observations(&[2.0, 2.6, 3.0, 2.0]), &cached, &anomaly_config), None);
+        assert_eq!(compare_with_cache("eu_inflation", &observations(&[2.0, 3.5, 4.0, 3.0]), &cached, &anomaly_config),
+                   Some(Anomaly::new("eu_inflation", "2024-Q2", "level_shift", 3.5, 1.0)));
+
+        let scale_change = compare_with_cache("eu_inflation", &observations(&[0.002, 0.0025, 0.003, 0.002]), &cached, &anomaly_config).unwrap();
+        assert_eq!((scale_change.period.as_str(), scale_change.kind), ("2024-Q1", "scale_change"));
+        assert!((scale_change.magnitude - 0.001).abs() < 1e-12);
+    }
+
+    #[test]
+    fn test_datasets_above_threshold() {
+        let anomalies = vec![
+            Anomaly::new("sp500", "2020-03-16", "rolling_mad", 2386.0, -6.1),
+            Anomaly::new("sp500", "2020-03-23", "rolling_mad", 2237.0, -5.4),
+            Anomaly::new("us_gdp", "2020-04-01", "rolling_mad", 19477.0, -5.2),
+        ];
+
+        assert_eq!(datasets_above_threshold(&anomalies, &anomaly_config(&[OutlierMethod::RollingZScore])), vec!["sp500"]);
+        assert!(datasets_above_threshold(&anomalies, &AnomalyConfig::new(&[], 5, 5.0, 2.0, 0.05, None)).is_empty());
+    }
+}
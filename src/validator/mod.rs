@@ -0,0 +1,2 @@
+pub mod validation;
+pub mod anomaly;
\ No newline at end of file
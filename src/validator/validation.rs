@@ -0,0 +1,310 @@
+use chrono::NaiveDate;
+use log::{error, info, warn};
+use polars::prelude::*;
+
+use crate::datasets_config::datasets_config::ValidationConfig;
+use crate::model::data_model::{DatasetWithConfig, ValidationCheck, ValidationStatus};
+
+/// Runs the validation rules of every extracted dataset. Schema and content problems that would
+/// corrupt the result are errors, gaps and stale data are warnings.
+pub fn validate_datasets(datasets: &[DatasetWithConfig],
+                         validation_configs: &[ValidationConfig],
+                         today: NaiveDate) -> Vec<ValidationCheck> {
+    let mut checks: Vec<ValidationCheck> = vec![];
+
+    for dataset in datasets {
+        match validation_configs.iter().find(|config| config.dataset == dataset.name) {
+            Some(validation_config) => checks.extend(validate_dataset(&dataset.dataframe, validation_config, today)),
+            None => warn!("no validation rules configured for {}", dataset.name),
+        }
+    }
+
+    for check in &checks {
+        match check.status {
+            ValidationStatus::Passed => {},
+            ValidationStatus::Warning => warn!("validation of {} ({}): {}", check.dataset, check.rule, check.message),
+            ValidationStatus::Error => error!("validation of {} ({}): {}", check.dataset, check.rule, check.message),
+        }
+    }
+
+    let count = |status: ValidationStatus| checks.iter().filter(|check| check.status == status).count();
+    info!("validation finished: {} passed, {} warnings, {} errors",
+          count(ValidationStatus::Passed),
+          count(ValidationStatus::Warning),
+          count(ValidationStatus::Error));
+
+    checks
+}
+
+pub fn validate_dataset(dataframe: &DataFrame, validation_config: &ValidationConfig, today: NaiveDate) -> Vec<ValidationCheck> {
+    let dataset = validation_config.dataset;
+    let date_column = validation_config.date_column;
+    let value_column = validation_config.value_column;
+    let check = |rule: &'static str, status: ValidationStatus, message: String| ValidationCheck::new(dataset, rule, status, &message);
+
+    let schema_problems: Vec<String> = [(date_column, DataType::String), (value_column, DataType::Float64)]
+        .into_iter()
+        .filter_map(|(column_name, expected_dtype)| match dataframe.column(column_name) {
+            Ok(column) if *column.dtype() == expected_dtype => None,
+            Ok(column) => Some(format!("column {column_name} is {} instead of {expected_dtype}", column.dtype())),
+            Err(_) => Some(format!("column {column_name} is missing")),
+        })
+        .collect();
+
+    if !schema_problems.is_empty() {
+        return vec![check("schema", ValidationStatus::Error, schema_problems.join(", "))];
+    }
+
+    let mut checks = vec![check("schema", ValidationStatus::Passed, format!("{date_column} is text and {value_column} is float"))];
+
+    let dates: Vec<Option<&str>> = dataframe.column(date_column).unwrap().str().unwrap().into_iter().collect();
+    let values: Vec<Option<f64>> = dataframe.column(value_column).unwrap().f64().unwrap().into_iter().collect();
+
+    if values.is_empty() {
+        checks.push(check("non_null_ratio", ValidationStatus::Error, "no observations".to_string()));
+        return checks;
+    }
+
+    // Nulls before the first and after the last observation, such as the World Bank years before
+    // a series starts or its unpublished latest year, are left to the freshness check
+    let observed_values = match (values.iter().position(Option::is_some), values.iter().rposition(Option::is_some)) {
+        (Some(first), Some(last)) => &values[first..=last],
+        _ => &values[..],
+    };
+    let non_null_ratio = observed_values.iter().flatten().count() as f64 / observed_values.len() as f64;
+    let non_null_status = if non_null_ratio >= validation_config.min_non_null_ratio { ValidationStatus::Passed } else { ValidationStatus::Error };
+    checks.push(check("non_null_ratio",
+                      non_null_status,
+                      format!("{:.1}% of {} values are non-null, minimum {:.1}%",
+                              non_null_ratio * 100.0,
+                              observed_values.len(),
+                              validation_config.min_non_null_ratio * 100.0)));
+
+    if let Some((min, max)) = validation_config.value_bounds {
+        let out_of_bounds: Vec<(Option<&str>, f64)> = dates.iter()
+            .zip(&values)
+            .filter_map(|(date, value)| value.filter(|value| *value < min || *value > max).map(|value| (*date, value)))
+            .collect();
+
+        checks.push(match out_of_bounds.first() {
+            None => check("value_bounds", ValidationStatus::Passed, format!("all values within [{min}, {max}]")),
+            Some((date, value)) => check("value_bounds",
+                                         ValidationStatus::Error,
+                                         format!("{} values outside [{min}, {max}], first {value} on {}",
+                                                 out_of_bounds.len(),
+                                                 date.unwrap_or("unknown date"))),
+        });
+    }
+
+    let unparsable_dates: Vec<&str> = dates.iter()
+        .filter(|date| date.and_then(period_start).is_none())
+        .map(|date| date.unwrap_or("null"))
+        .collect();
+
+    if let Some(first_unparsable) = unparsable_dates.first() {
+        checks.push(check("monotonic_dates",
+                          ValidationStatus::Error,
+                          format!("{} dates could not be parsed, first '{first_unparsable}'", unparsable_dates.len())));
+        return checks;
+    }
+
+    let periods: Vec<(&str, NaiveDate)> = dates.iter()
+        .map(|date| (date.unwrap(), period_start(date.unwrap()).unwrap()))
+        .collect();
+
+    checks.push(match unordered_dates(&periods) {
+        None => check("monotonic_dates", ValidationStatus::Passed, "dates are strictly monotonic".to_string()),
+        Some((previous, next)) => check("monotonic_dates",
+                                        ValidationStatus::Error,
+                                        format!("dates are not strictly monotonic, {next} follows {previous}")),
+    });
+
+    if let Some((gap_days, from, to)) = periods.windows(2)
+        .map(|pair| ((pair[1].1 - pair[0].1).num_days().abs(), pair[0].0, pair[1].0))
+        .max_by_key(|(gap_days, _, _)| *gap_days) {
+        let gap_status = if gap_days <= validation_config.max_gap_days { ValidationStatus::Passed } else { ValidationStatus::Warning };
+        checks.push(check("max_gap",
+                          gap_status,
+                          format!("largest gap is {gap_days} days between {from} and {to}, maximum {}",
+                                  validation_config.max_gap_days)));
+    }
+
+    let Some((latest_date, latest_period)) = periods.iter()
+        .zip(&values)
+        .filter(|(_, value)| value.is_some())
+        .map(|(period, _)| *period)
+        .max_by_key(|(_, period)| *period) else {
+        return checks;
+    };
+    let staleness_days = (today - latest_period).num_days();
+    let freshness_status = if staleness_days <= validation_config.max_staleness_days { ValidationStatus::Passed } else { ValidationStatus::Warning };
+    checks.push(check("freshness",
+                      freshness_status,
+                      format!("last observation {latest_date} is {staleness_days} days old, maximum {}",
+                              validation_config.max_staleness_days)));
+
+    checks
+}
+
+/// Dates must be strictly increasing or strictly decreasing; the World Bank lists the newest first.
+fn unordered_dates<'a>(periods: &[(&'a str, NaiveDate)]) -> Option<(&'a str, &'a str)> {
+    let ascending = periods.len() < 2 || periods[0].1 < periods[1].1;
+
+    periods.windows(2)
+        .find(|pair| if ascending { pair[0].1 >= pair[1].1 } else { pair[0].1 <= pair[1].1 })
+        .map(|pair| (pair[0].0, pair[1].0))
+}
+
+/// First day of a `2024-01-31`, `2024-01`, `2024-Q1` or `2024` period.
+pub fn period_start(date: &str) -> Option<NaiveDate> {
+    if let Some((year, quarter)) = date.split_once("-Q") {
+        let quarter: u32 = quarter.parse().ok().filter(|quarter| (1..=4).contains(quarter))?;
+        return NaiveDate::from_ymd_opt(year.parse().ok()?, quarter * 3 - 2, 1);
+    }
+
+    match date.len() {
+        10 => NaiveDate::parse_from_str(date, "%Y-%m-%d").ok(),
+        7 => NaiveDate::parse_from_str(&format!("{date}-01"), "%Y-%m-%d").ok(),
+        4 => NaiveDate::from_ymd_opt(date.parse().ok()?, 1, 1),
+        _ => None,
+    }
+}
+
+
+#[cfg(test)]
+mod tests {
+    use crate::datasets_config::datasets_config::get_all_validation_configs;
+
+    use super::*;
+
+    fn today() -> NaiveDate {
+        NaiveDate::from_ymd_opt(2024, 12, 31).unwrap()
+    }
+
+    fn quarterly_config() -> ValidationConfig {
+        ValidationConfig::new("eu_gdp", "quarter", "value", 0.75, Some((0.0, 100.0)), 92, 200)
+    }
+
+    fn statuses(checks: &[ValidationCheck]) -> Vec<(&'static str, ValidationStatus)> {
+        checks.iter().map(|check| (check.rule, check.status)).collect()
+    }
+
+    #[test]
+    fn test_period_start() {
+        assert_eq!(period_start("2024-02-29"), NaiveDate::from_ymd_opt(2024, 2, 29));
+        assert_eq!(period_start("2024-02"), NaiveDate::from_ymd_opt(2024, 2, 1));
+        assert_eq!(period_start("2024-Q3"), NaiveDate::from_ymd_opt(2024, 7, 1));
+        assert_eq!(period_start("2024"), NaiveDate::from_ymd_opt(2024, 1, 1));
+        assert_eq!(period_start("2024-Q5"), None);
+        assert_eq!(period_start("."), None);
+    }
+
+    #[test]
+    fn test_validate_dataset_passes() {
+        let dataframe = df![
+            "quarter" => &["2024-Q1", "2024-Q2", "2024-Q3"],
+            "value" => &[Some(1.0), None, Some(3.0)]
+        ].unwrap();
+
+        let checks = validate_dataset(&dataframe, &ValidationConfig::new("eu_gdp", "quarter", "value", 0.5, Some((0.0, 100.0)), 92, 200), today());
+
+        assert_eq!(statuses(&checks), vec![
+            ("schema", ValidationStatus::Passed),
+            ("non_null_ratio", ValidationStatus::Passed),
+            ("value_bounds", ValidationStatus::Passed),
+            ("monotonic_dates", ValidationStatus::Passed),
+            ("max_gap", ValidationStatus::Passed),
+            ("freshness", ValidationStatus::Passed),
+        ]);
+        assert_eq!(checks[5].message, "last observation 2024-Q3 is 183 days old, maximum 200");
+    }
+
+    #[test]
+    fn test_validate_dataset_reports_implausible_values() {
+        let dataframe = df![
+            "quarter" => &["2023-Q4", "2023-Q3", "2024-Q1", "2024-Q3"],
+            "value" => &[Some(1.0), None, None, Some(1000.0)]
+        ].unwrap();
+
+        let checks = validate_dataset(&dataframe, &quarterly_config(), today());
+
+        assert_eq!(statuses(&checks), vec![
+            ("schema", ValidationStatus::Passed),
+            ("non_null_ratio", ValidationStatus::Error),
+            ("value_bounds", ValidationStatus::Error),
+            ("monotonic_dates", ValidationStatus::Error),
+            ("max_gap", ValidationStatus::Warning),
+            ("freshness", ValidationStatus::Passed),
+        ]);
+        assert_eq!(checks[1].message, "50.0% of 4 values are non-null, minimum 75.0%");
+        assert_eq!(checks[2].message, "1 values outside [0, 100], first 1000 on 2024-Q3");
+        assert_eq!(checks[3].message, "dates are not strictly monotonic, 2024-Q1 follows 2023-Q3");
+        assert_eq!(checks[4].message, "largest gap is 184 days between 2023-Q3 and 2024-Q1, maximum 92");
+    }
+
+    #[test]
+    fn test_validate_dataset_accepts_descending_dates_and_warns_when_stale() {
+        let dataframe = df![
+            "date" => &["2022", "2021", "2020"],
+            "value" => &[0.71, 0.7, 0.69]
+        ].unwrap();
+        let validation_config = ValidationConfig::new("eu_ppp", "date", "value", 0.9, None, 366, 365);
+
+        let checks = validate_dataset(&dataframe, &validation_config, today());
+
+        assert_eq!(statuses(&checks), vec![
+            ("schema", ValidationStatus::Passed),
+            ("non_null_ratio", ValidationStatus::Passed),
+            ("monotonic_dates", ValidationStatus::Passed),
+            ("max_gap", ValidationStatus::Passed),
+            ("freshness", ValidationStatus::Warning),
+        ]);
+    }
+
+    #[test]
+    fn test_validate_dataset_ignores_nulls_outside_the_observed_span() {
+        // As listed by the World Bank: newest year first, the latest year not yet published and
+        // the years before the series starts null
+        let dates: Vec<String> = (1960..=2024).rev().map(|year| year.to_string()).collect();
+        let values: Vec<Option<f64>> = (1960..=2024).rev().map(|year| (1995..=2023).contains(&year).then_some(0.7)).collect();
+        let dataframe = df!["date" => dates, "value" => values].unwrap();
+        let eu_ppp_config = get_all_validation_configs().into_iter().find(|config| config.dataset == "eu_ppp").unwrap();
+
+        let checks = validate_dataset(&dataframe, &eu_ppp_config, today());
+
+        assert!(checks.iter().all(|check| check.status == ValidationStatus::Passed));
+        assert_eq!(checks[1].message, "100.0% of 29 values are non-null, minimum 90.0%");
+        assert_eq!(checks.last().unwrap().message, "last observation 2023 is 730 days old, maximum 1000");
+
+        let stale_config = ValidationConfig::new("eu_ppp", "date", "value", 0.9, None, 366, 500);
+        assert_eq!(validate_dataset(&dataframe, &stale_config, today()).last().unwrap().status, ValidationStatus::Warning);
+    }
+
+    #[test]
+    fn test_validate_dataset_rejects_changed_schema() {
+        let dataframe = df![
+            "quarter" => &["2024-Q1"],
+            "value" => &["1.0"]
+        ].unwrap();
+
+        let checks = validate_dataset(&dataframe, &ValidationConfig::new("eu_gdp", "date", "value", 0.9, None, 92, 200), today());
+
+        assert_eq!(checks, vec![ValidationCheck::new("eu_gdp",
+                                                     "schema",
+                                                     ValidationStatus::Error,
+                                                     "column date is missing, column value is str instead of f64")]);
+    }
+
+    #[test]
+    fn test_validate_dataset_rejects_unparsable_dates() {
+        let dataframe = df![
+            "quarter" => &["2024-Q1", "Q2 2024"],
+            "value" => &[1.0, 2.0]
+        ].unwrap();
+
+        let checks = validate_dataset(&dataframe, &quarterly_config(), today());
+
+        assert_eq!(checks.last().unwrap().status, ValidationStatus::Error);
+        assert_eq!(checks.last().unwrap().message, "1 dates could not be parsed, first 'Q2 2024'");
+    }
+}